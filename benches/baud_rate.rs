@@ -0,0 +1,91 @@
+//! Transaction time with and without `TrackedBaudRate`
+//!
+//! Baud rate changes reconfigure a real serial port, a pseudo-terminal, so they cost what the OS spends on `tcsetattr`.
+//! Everything else goes to a mock interface answering every instruction with a status, so the cost of the transfer itself is left out.
+//!
+//! Pseudo-terminals are only available on unix.
+
+#[cfg(unix)]
+mod bench {
+    use std::time::Duration;
+
+    use criterion::Criterion;
+    use serialport::posix::TTYPort;
+    use serialport::SerialPort;
+
+    use dynamixel::dynamixel::ax12::AX12;
+    use dynamixel::mock::{protocol1_status, MockInterface};
+    use dynamixel::protocol1::ServoID;
+    use dynamixel::{BaudRate, CommunicationError, Interface, Servo, Timeouts, TrackedBaudRate};
+
+    /// Reconfigures a pseudo-terminal on every baud rate change, and answers instructions from a mock
+    struct Reconfiguring {
+        port: TTYPort,
+        // Keeps the pseudo-terminal open
+        _master: TTYPort,
+        mock: MockInterface,
+    }
+
+    impl Reconfiguring {
+        fn new() -> Self {
+            let (master, port) = TTYPort::pair().expect("failed to create a pseudo-terminal");
+            Reconfiguring{port, _master: master, mock: MockInterface::new()}
+        }
+    }
+
+    impl Interface for Reconfiguring {
+        fn set_baud_rate(&mut self, b: BaudRate) -> Result<(), CommunicationError> {
+            // Not passed on to the mock, which would log every change for the whole run
+            SerialPort::set_baud_rate(&mut self.port, u32::from(b)).map_err(|_| CommunicationError::UnsupportedBaud(b))
+        }
+
+        fn flush(&mut self) {
+            self.mock.flush()
+        }
+
+        fn read(&mut self, data: &mut [u8]) -> Result<(), CommunicationError> {
+            self.mock.read(data)
+        }
+
+        fn read_with_timeout(&mut self, data: &mut [u8], timeout: Duration) -> Result<(), CommunicationError> {
+            self.mock.read_with_timeout(data, timeout)
+        }
+
+        fn write(&mut self, _data: &[u8]) -> Result<(), CommunicationError> {
+            // A new mock for every transaction, so its logs don't grow for the whole run
+            self.mock = MockInterface::new();
+            self.mock.queue_response(&protocol1_status(1, 0, &[0x00, 0x02]));
+            Ok(())
+        }
+
+        fn timeouts(&self) -> Timeouts {
+            self.mock.timeouts()
+        }
+    }
+
+    pub fn baud_rate(c: &mut Criterion) {
+        let mut group = c.benchmark_group("get_position");
+
+        group.bench_function("set baud rate every transaction", |b| {
+            let mut interface = Reconfiguring::new();
+            let mut servo = AX12::new(ServoID::new(1), BaudRate::Baud1000000);
+            b.iter(|| servo.get_position(&mut interface).unwrap());
+        });
+
+        group.bench_function("tracked baud rate", |b| {
+            let mut interface = TrackedBaudRate::new(Reconfiguring::new());
+            let mut servo = AX12::new(ServoID::new(1), BaudRate::Baud1000000);
+            b.iter(|| servo.get_position(&mut interface).unwrap());
+        });
+
+        group.finish();
+    }
+}
+
+#[cfg(unix)]
+criterion::criterion_group!(benches, bench::baud_rate);
+#[cfg(unix)]
+criterion::criterion_main!(benches);
+
+#[cfg(not(unix))]
+fn main() {}
@@ -0,0 +1,22 @@
+extern crate dynamixel;
+
+use std::time::Instant;
+
+use dynamixel::{BaudRate, Interface, SerialPortInterface};
+
+/// Measure the round trip time of a serial adapter with TX and RX connected, e.g. "loopback /dev/ttyUSB0"
+fn main() {
+    let path = std::env::args().nth(1).expect("usage: loopback <serial port>");
+    let mut interface = SerialPortInterface::open(&path).unwrap();
+    interface.set_baud_rate(BaudRate::Baud1000000).unwrap();
+
+    let transactions = 1000;
+    let start = Instant::now();
+    for _ in 0..transactions {
+        interface.flush();
+        interface.write(&[0xff, 0xff, 0x01, 0x02, 0x01, 0xfb]).unwrap();
+        let mut echo = [0u8; 6];
+        interface.read(&mut echo).unwrap();
+    }
+    println!("{} transactions, {:?} per transaction", transactions, start.elapsed() / transactions);
+}
@@ -31,8 +31,8 @@ fn main() {
 
     let interfaces = dynamixel::enumerate(&mut serial).unwrap();
     println!("Found following servos:");
-    for (i, port) in interfaces.iter().enumerate() {
-        println!("({}) {:?}", i, port);
+    for (i, servo) in interfaces.iter().enumerate() {
+        println!("({}) {}", i, servo);
     }
 
     let mut buffer = String::new();
@@ -49,5 +49,5 @@ fn main() {
 
     println!("moving to center");
     servo.set_enable_torque(&mut serial, true).unwrap();
-    servo.set_position(&mut serial, 0.0).unwrap();
+    servo.set_position(&mut serial, dynamixel::units::Radians(0.0)).unwrap();
 }
@@ -0,0 +1,17 @@
+extern crate dynamixel;
+
+use dynamixel::sim::pty::PtyBridge;
+use dynamixel::sim::{SimServo, Simulator};
+
+fn main() {
+    let simulator = Simulator::new()
+        .with_servo(SimServo::ax12(1))
+        .with_servo(SimServo::mx28(2));
+
+    let bridge = PtyBridge::spawn(simulator).unwrap();
+    println!("Serving simulated servos on {}", bridge.path());
+    println!("Press enter to stop");
+
+    let mut buffer = String::new();
+    std::io::stdin().read_line(&mut buffer).ok();
+}
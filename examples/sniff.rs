@@ -0,0 +1,16 @@
+extern crate dynamixel;
+
+use std::io::Read;
+
+/// Decode a capture of bus traffic, read from the file given as argument or stdin
+fn main() {
+    let mut capture = Vec::new();
+    match std::env::args().nth(1) {
+        Some(path) => std::fs::File::open(path).unwrap().read_to_end(&mut capture).unwrap(),
+        None => std::io::stdin().read_to_end(&mut capture).unwrap(),
+    };
+
+    for event in dynamixel::sniffer::decode(&capture) {
+        println!("{}", event);
+    }
+}
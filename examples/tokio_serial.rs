@@ -0,0 +1,18 @@
+extern crate dynamixel;
+
+use dynamixel::asynchronous::protocol1::{enumerate, AsyncServo};
+use dynamixel::asynchronous::TokioSerialInterface;
+
+/// Enumerate protocol 1 servos and read their positions without blocking the runtime
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    let path = std::env::args().nth(1).expect("usage: tokio_serial <serial port>");
+    let mut interface = TokioSerialInterface::open(&path).unwrap();
+
+    let servos = enumerate(&mut interface).await.unwrap();
+    for info in &servos {
+        let mut servo = AsyncServo::from_info(info);
+        let position = servo.read_data_raw::<_, 2>(&mut interface, 36).await.unwrap();
+        println!("{} is at position {}", info, u16::from_le_bytes(position));
+    }
+}
@@ -0,0 +1,60 @@
+//! Async servo communication
+//!
+//! `AsyncInterface` is the async counterpart of `Interface`, for use from async runtimes like tokio,
+//! where blocking reads would stall the executor.
+//! `protocol1::AsyncServo` and `protocol2::AsyncServo` communicate with servos over an `AsyncInterface`,
+//! encoding instructions and decoding statuses with the same code as the blocking servo types.
+//!
+//! `TokioSerialInterface` implements `AsyncInterface` for serial ports using [tokio-serial](https://crates.io/crates/tokio-serial).
+//!
+//! This module requires the `async` feature, and `TokioSerialInterface` requires the `tokio-serial` feature.
+
+use std::future::Future;
+use std::time::Duration;
+
+use crate::{BaudRate, CommunicationError, Timeouts};
+
+pub mod protocol1;
+pub mod protocol2;
+
+#[cfg(feature="tokio-serial")]
+mod tokio_serial_impl;
+#[cfg(feature="tokio-serial")]
+pub use self::tokio_serial_impl::TokioSerialInterface;
+
+/// The async counterpart of `Interface`
+///
+/// Reads and writes return futures instead of blocking.
+/// The futures must be `Send`, so servos can be used from tasks on multi-threaded runtimes.
+pub trait AsyncInterface: Send {
+    /// Set the baud rate of the interface
+    ///
+    /// `BaudRate` must not be matched against exhaustively.
+    fn set_baud_rate(&mut self, b: BaudRate) -> Result<(), CommunicationError>;
+
+    /// Flush out the read buffer
+    ///
+    /// Whenever a new transmission is started, old data from the read buffer needs to be flushed out first.
+    fn flush(&mut self);
+
+    /// Read `data.len()` bytes into `data`
+    ///
+    /// If bytes are not received for `timeout`, the read fails with `CommunicationError::TimedOut`.
+    fn read_with_timeout(&mut self, data: &mut [u8], timeout: Duration) -> impl Future<Output = Result<(), CommunicationError>> + Send;
+
+    /// Read `data.len()` bytes into `data`, with the `status` timeout of `timeouts`
+    fn read(&mut self, data: &mut [u8]) -> impl Future<Output = Result<(), CommunicationError>> + Send {
+        let timeout = self.timeouts().status;
+        self.read_with_timeout(data, timeout)
+    }
+
+    /// Write all of `data`
+    fn write(&mut self, data: &[u8]) -> impl Future<Output = Result<(), CommunicationError>> + Send;
+
+    /// The timeouts used by servos and enumeration on this interface, unless a servo has its own.
+    ///
+    /// The default implementation returns `Timeouts::default()`.
+    fn timeouts(&self) -> Timeouts {
+        Timeouts::default()
+    }
+}
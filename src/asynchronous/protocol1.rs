@@ -0,0 +1,283 @@
+//! Async communication with protocol 1 servos
+
+use std::future::Future;
+use std::time::Duration;
+
+use log::{info, warn};
+
+use crate::asynchronous::AsyncInterface;
+use crate::protocol1::instruction;
+use crate::protocol1::{Error, GenericModelInfo, Instruction, PacketID, ReadRegister, ServoID, ServoInfo, Status, WriteRegister};
+use crate::{BaudRate, CommunicationError, StatusReturnLevel, Timeouts};
+
+/// Write the instruction on the interface
+pub(crate) async fn write_instruction<I: AsyncInterface, T: Instruction + Send>(interface: &mut I, instruction: T) -> Result<(), CommunicationError>
+where
+    T::Array: AsRef<[u8]>,
+{
+    let packet = instruction.serialize();
+    interface.write(&packet.as_ref()[..T::LENGTH as usize + 4]).await
+}
+
+/// Read a status from the interface
+///
+/// The header is read first, and then the rest of the status depending on the length in the header.
+pub(crate) async fn read_status<I: AsyncInterface, T: Status>(interface: &mut I, timeout: Duration) -> Result<T, Error> {
+    let mut data = [0u8; 20];
+    interface.read_with_timeout(&mut data[..4], timeout).await?;
+
+    let length = data[3] as usize;
+    if 4 + length > data.len() {
+        return Err(Error::Format{id: None, error: crate::protocol1::FormatError::Length});
+    }
+    interface.read_with_timeout(&mut data[4..4+length], timeout).await?;
+    T::deserialize(&data[..4+length])
+}
+
+/// Run `f`, attaching the ID of the servo to its communication and format errors
+async fn transaction<T>(id: ServoID, f: impl Future<Output = Result<T, Error>>) -> Result<T, Error> {
+    f.await.map_err(|e| e.with_id(id))
+}
+
+/// Enumerate all protocol 1 servos connected to the interface
+pub async fn enumerate<I: AsyncInterface>(interface: &mut I) -> Result<Vec<ServoInfo>, CommunicationError> {
+    let mut servos = Vec::new();
+
+    for b in &[BaudRate::Baud1000000] {
+
+        if interface.set_baud_rate(*b).is_err() {
+            warn!(target: "protocol1", "not able to enumerate devices on baudrate: {}", u32::from(*b));
+        }
+
+        interface.flush();
+        write_instruction(interface, instruction::Ping::new(PacketID::Broadcast)).await?;
+
+        // Collect every pong before reading model info, since reading flushes the remaining pongs
+        let mut ids = Vec::new();
+        loop {
+            let timeout = interface.timeouts().ping;
+            match read_status::<I, instruction::Pong>(interface, timeout).await {
+                Ok(pong) => ids.push(pong.id),
+                Err(Error::Communication{error: CommunicationError::TimedOut, ..}) => break,
+                Err(Error::Communication{error, ..}) => return Err(error),
+                Err(e) => warn!(target: "protocol1", "received error: {:?} when waiting for enumeration on baud: {}", e, u32::from(*b)),
+            };
+        }
+
+        for id in ids {
+            interface.flush();
+            write_instruction(interface, instruction::ReadData::<GenericModelInfo>::new(PacketID::from(id))).await?;
+            let timeout = interface.timeouts().status;
+            let model_info = match read_status::<I, instruction::ReadDataResponse<GenericModelInfo>>(interface, timeout).await {
+                Ok(response) => response.data,
+                Err(Error::Communication{error, ..}) => return Err(error),
+                Err(e) => {
+                    warn!(target: "protocol1", "Found servo with baud: {} and id: {}. Could not resolve model number due to error: {:?}", u32::from(*b), u8::from(id), e);
+                    continue;
+                },
+            };
+
+            let info = ServoInfo{
+                baud_rate: *b,
+                model_number: model_info.model_number,
+                fw_version: model_info.fw_version,
+                id,
+            };
+            info!(target: "protocol1", "found {}", info);
+            servos.push(info);
+        }
+    }
+    Ok(servos)
+}
+
+/// A protocol 1 servo used through an `AsyncInterface`
+///
+/// Registers of any protocol 1 model can be read and written, it's up to the user to use the right control table.
+#[derive(Debug, Clone)]
+pub struct AsyncServo {
+    id: ServoID,
+    baud_rate: BaudRate,
+    eeprom_end: u16,
+    timeouts: Option<Timeouts>,
+    status_return_level: StatusReturnLevel,
+}
+
+impl AsyncServo {
+    /// Create a new servo without `ping`ing or taking any other measure to make sure it exists.
+    ///
+    /// The EEPROM area is assumed to be that of the generic protocol 1 control table.
+    pub fn new(id: ServoID, baud_rate: BaudRate) -> Self {
+        AsyncServo{
+            id,
+            baud_rate,
+            eeprom_end: crate::registry::ControlTable::Protocol1Generic.eeprom_end(),
+            timeouts: None,
+            status_return_level: StatusReturnLevel::All,
+        }
+    }
+
+    /// Create a servo found by `enumerate`, using the control table of its model from the `registry`
+    pub fn from_info(info: &ServoInfo) -> Self {
+        let mut servo = Self::new(info.id, info.baud_rate);
+        if let Some(model) = info.model() {
+            servo.eeprom_end = model.control_table.eeprom_end();
+        }
+        servo
+    }
+
+    pub fn id(&self) -> ServoID {
+        self.id
+    }
+
+    /// Set the timeouts used for this servo, or `None` to use the timeouts of the interface
+    pub fn set_timeouts(&mut self, timeouts: Option<Timeouts>) {
+        self.timeouts = timeouts;
+    }
+
+    /// Assume the servo has status return level `level`
+    ///
+    /// Writes only wait for a status when the level is `StatusReturnLevel::All`, which is assumed by default.
+    /// Reads fail with `CommunicationError::NoStatus`, without sending anything, when the level is `StatusReturnLevel::Ping`.
+    pub fn assume_status_return_level(&mut self, level: StatusReturnLevel) {
+        self.status_return_level = level;
+    }
+
+    /// The timeouts of this servo, or the interface if not set
+    fn timeouts<I: AsyncInterface>(&self, interface: &I) -> Timeouts {
+        self.timeouts.unwrap_or_else(|| interface.timeouts())
+    }
+
+    /// The timeout for the status of a write starting at `address`
+    fn write_timeout<I: AsyncInterface>(&self, interface: &I, address: u8) -> Duration {
+        let timeouts = self.timeouts(interface);
+        if u16::from(address) < self.eeprom_end {
+            timeouts.eeprom_write
+        } else {
+            timeouts.status
+        }
+    }
+
+    /// Ping the servo, returning `Ok(())` if it exists.
+    pub async fn ping<I: AsyncInterface>(&mut self, interface: &mut I) -> Result<(), Error> {
+        transaction(self.id, async {
+            interface.set_baud_rate(self.baud_rate)?;
+            interface.flush();
+
+            write_instruction(interface, instruction::Ping::new(PacketID::from(self.id))).await?;
+            let timeout = self.timeouts(interface).ping;
+            read_status::<I, instruction::Pong>(interface, timeout).await?;
+            Ok(())
+        }).await
+    }
+
+    /// Write the given data `register` to the servo.
+    pub async fn write_data<I: AsyncInterface, W: WriteRegister + Send>(&mut self, interface: &mut I, register: W) -> Result<(), Error> {
+        transaction(self.id, async {
+            interface.set_baud_rate(self.baud_rate)?;
+            interface.flush();
+
+            let timeout = self.write_timeout(interface, W::ADDRESS);
+            write_instruction(interface, instruction::WriteData::new(PacketID::from(self.id), register)).await?;
+            if self.status_return_level.replies_to_writes() {
+                read_status::<I, instruction::WriteDataResponse>(interface, timeout).await?;
+            }
+            Ok(())
+        }).await
+    }
+
+    /// Read data from a register
+    pub async fn read_data<I: AsyncInterface, R: ReadRegister + Send>(&mut self, interface: &mut I) -> Result<R, Error> {
+        transaction(self.id, async {
+            if !self.status_return_level.replies_to_reads() {
+                return Err(CommunicationError::NoStatus.into());
+            }
+            interface.set_baud_rate(self.baud_rate)?;
+            interface.flush();
+
+            write_instruction(interface, instruction::ReadData::<R>::new(PacketID::from(self.id))).await?;
+            let timeout = self.timeouts(interface).status;
+            Ok(read_status::<I, instruction::ReadDataResponse<R>>(interface, timeout).await?.data)
+        }).await
+    }
+
+    /// Write `N` raw bytes to the control table, starting at `address`.
+    ///
+    /// At most 4 bytes can be written at a time.
+    pub async fn write_data_raw<I: AsyncInterface, const N: usize>(&mut self, interface: &mut I, address: u8, data: [u8; N]) -> Result<(), Error> {
+        transaction(self.id, async {
+            interface.set_baud_rate(self.baud_rate)?;
+            interface.flush();
+
+            let timeout = self.write_timeout(interface, address);
+            write_instruction(interface, instruction::WriteDataRaw::new(PacketID::from(self.id), address, data)).await?;
+            if self.status_return_level.replies_to_writes() {
+                read_status::<I, instruction::WriteDataResponse>(interface, timeout).await?;
+            }
+            Ok(())
+        }).await
+    }
+
+    /// Read `N` raw bytes from the control table, starting at `address`.
+    ///
+    /// At most 4 bytes can be read at a time.
+    pub async fn read_data_raw<I: AsyncInterface, const N: usize>(&mut self, interface: &mut I, address: u8) -> Result<[u8; N], Error> {
+        transaction(self.id, async {
+            if !self.status_return_level.replies_to_reads() {
+                return Err(CommunicationError::NoStatus.into());
+            }
+            interface.set_baud_rate(self.baud_rate)?;
+            interface.flush();
+
+            write_instruction(interface, instruction::ReadDataRaw::<N>::new(PacketID::from(self.id), address)).await?;
+            let timeout = self.timeouts(interface).status;
+            Ok(read_status::<I, instruction::ReadDataRawResponse<N>>(interface, timeout).await?.data)
+        }).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::asynchronous::protocol1::*;
+    use crate::mock::{protocol1_packet, protocol1_status, MockInterface};
+
+    #[tokio::test]
+    async fn servo() {
+        let mut interface = MockInterface::new();
+        let mut servo = AsyncServo::new(ServoID::new(1), BaudRate::Baud1000000);
+
+        interface.queue_response(&protocol1_status(1, 0, &[]));
+        servo.ping(&mut interface).await.unwrap();
+
+        interface.queue_response(&protocol1_status(1, 0, &[0x00, 0x02]));
+        assert_eq!(servo.read_data_raw::<_, 2>(&mut interface, 36).await, Ok([0x00, 0x02]));
+
+        interface.queue_response(&protocol1_status(1, 0, &[]));
+        servo.write_data_raw(&mut interface, 30, [0x00, 0x02]).await.unwrap();
+
+        interface.assert_sent_exactly(&[
+            &protocol1_packet(1, 0x01, &[]),
+            &protocol1_packet(1, 0x02, &[36, 2]),
+            &protocol1_packet(1, 0x03, &[30, 0x00, 0x02]),
+        ]);
+
+        // Processing errors in the status are returned
+        interface.queue_response(&protocol1_status(1, 0x08, &[]));
+        assert!(matches!(servo.write_data_raw(&mut interface, 30, [0x00, 0x02]).await, Err(Error::Processing{..})));
+    }
+
+    #[tokio::test]
+    async fn enumerate_servos() {
+        let mut interface = MockInterface::new();
+        interface.queue_response(&protocol1_status(1, 0, &[]));
+        interface.queue_response(&protocol1_status(4, 0, &[]));
+        interface.queue_timeout();
+        interface.queue_response(&protocol1_status(1, 0, &[12, 0, 24]));
+        interface.queue_response(&protocol1_status(4, 0, &[29, 0, 30]));
+
+        let servos = enumerate(&mut interface).await.unwrap();
+        assert_eq!(servos.len(), 2);
+        assert_eq!((u8::from(servos[0].id), servos[0].model_number), (1, 12));
+        assert_eq!((u8::from(servos[1].id), servos[1].model_number, servos[1].fw_version), (4, 29, 30));
+        interface.assert_all_read();
+    }
+}
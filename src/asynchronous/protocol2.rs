@@ -0,0 +1,308 @@
+//! Async communication with protocol 2 servos
+
+use std::future::Future;
+use std::time::Duration;
+
+use log::{info, warn};
+
+use crate::asynchronous::AsyncInterface;
+use crate::protocol2::instruction;
+use crate::protocol2::{Deserializer, Error, Instruction, PacketID, ReadRegister, Response, ServoID, ServoInfo, Status, WriteRegister};
+use crate::{BaudRate, CommunicationError, StatusReturnLevel, Timeouts};
+
+/// Write the instruction on the interface
+pub(crate) async fn write_instruction<I: AsyncInterface, T: Instruction + Send + Sync>(interface: &mut I, instruction: T) -> Result<(), CommunicationError> {
+    let packet: Vec<u8> = instruction.serialize().collect();
+    interface.write(&packet).await
+}
+
+/// Read a status from the interface, keeping the alert bit
+///
+/// If no instructions have been sent, there will not be any status to read
+pub(crate) async fn read_response<I: AsyncInterface, T: Status + Send>(interface: &mut I, timeout: Duration) -> Result<Response<T>, Error> {
+    let mut header = [0u8; 9];
+    interface.read_with_timeout(&mut header, timeout).await?;
+
+    let mut deserializer = Deserializer::<T>::new()
+        .deserialize_header(header)?;
+
+    let mut body = [0u8; 10];
+
+    loop {
+        let remaining_bytes = deserializer.remaining_bytes() as usize;
+        if remaining_bytes > 10 {
+            interface.read_with_timeout(&mut body, timeout).await?;
+            deserializer.deserialize(&body)?;
+        } else {
+            interface.read_with_timeout(&mut body[..remaining_bytes], timeout).await?;
+            deserializer.deserialize(&body[..remaining_bytes])?;
+            break;
+        }
+    }
+
+    Ok(Response{alert: deserializer.alert(), status: deserializer.build()})
+}
+
+/// Run `f`, attaching the ID of the servo to its communication and format errors
+async fn transaction<T>(id: ServoID, f: impl Future<Output = Result<T, Error>>) -> Result<T, Error> {
+    f.await.map_err(|e| e.with_id(id))
+}
+
+/// Enumerate all protocol 2 servos connected to the interface
+pub async fn enumerate<I: AsyncInterface>(interface: &mut I) -> Result<Vec<ServoInfo>, CommunicationError> {
+    let mut servos = Vec::new();
+
+    for b in BaudRate::variants() {
+
+        if interface.set_baud_rate(*b).is_err() {
+            warn!(target: "protocol2", "not able to enumerate devices on baudrate: {}", u32::from(*b));
+        }
+
+        interface.flush();
+        write_instruction(interface, instruction::Ping::new(PacketID::Broadcast)).await?;
+
+        loop {
+            let timeout = interface.timeouts().ping;
+            match read_response::<I, instruction::Pong>(interface, timeout).await.and_then(|response| response.status) {
+                Ok(pong) => {
+                    let info = ServoInfo{
+                        baud_rate: *b,
+                        model_number: pong.model_number,
+                        fw_version: pong.fw_version,
+                        id: pong.id,
+                    };
+                    info!(target: "protocol2", "found {}", info);
+                    servos.push(info);
+                },
+                Err(Error::Communication{error: CommunicationError::TimedOut, ..}) => break,
+                Err(e) => {
+                    warn!(target: "protocol2", "received error: {:?} when waiting for enumeration on baud: {}", e, u32::from(*b));
+                    break;
+                },
+            };
+        }
+    }
+    Ok(servos)
+}
+
+/// A protocol 2 servo used through an `AsyncInterface`
+///
+/// Registers of any protocol 2 model can be read and written, it's up to the user to use the right control table.
+#[derive(Debug, Clone)]
+pub struct AsyncServo {
+    id: ServoID,
+    baud_rate: BaudRate,
+    eeprom_end: u16,
+    timeouts: Option<Timeouts>,
+    status_return_level: StatusReturnLevel,
+    alert: bool,
+}
+
+impl AsyncServo {
+    /// Create a new servo without `ping`ing or taking any other measure to make sure it exists.
+    ///
+    /// The EEPROM area is assumed to be that of the generic protocol 2 control table.
+    pub fn new(id: ServoID, baud_rate: BaudRate) -> Self {
+        AsyncServo{
+            id,
+            baud_rate,
+            eeprom_end: crate::registry::ControlTable::Protocol2Generic.eeprom_end(),
+            timeouts: None,
+            status_return_level: StatusReturnLevel::All,
+            alert: false,
+        }
+    }
+
+    /// Create a servo found by `enumerate`, using the control table of its model from the `registry`
+    pub fn from_info(info: &ServoInfo) -> Self {
+        let mut servo = Self::new(info.id, info.baud_rate);
+        if let Some(model) = info.model() {
+            servo.eeprom_end = model.control_table.eeprom_end();
+        }
+        servo
+    }
+
+    pub fn id(&self) -> ServoID {
+        self.id
+    }
+
+    /// Set the timeouts used for this servo, or `None` to use the timeouts of the interface
+    pub fn set_timeouts(&mut self, timeouts: Option<Timeouts>) {
+        self.timeouts = timeouts;
+    }
+
+    /// Assume the servo has status return level `level`
+    ///
+    /// Writes only wait for a status when the level is `StatusReturnLevel::All`, which is assumed by default.
+    /// Reads fail with `CommunicationError::NoStatus`, without sending anything, when the level is `StatusReturnLevel::Ping`.
+    pub fn assume_status_return_level(&mut self, level: StatusReturnLevel) {
+        self.status_return_level = level;
+    }
+
+    /// Whether the alert bit was set in the last status from the servo, meaning it has a hardware error.
+    pub fn alert(&self) -> bool {
+        self.alert
+    }
+
+    /// Read a status from the servo, remembering its alert bit
+    async fn read_status<I: AsyncInterface, T: Status + Send>(&mut self, interface: &mut I, timeout: Duration) -> Result<T, Error> {
+        let response = read_response::<I, T>(interface, timeout).await?;
+        self.alert = response.alert;
+        response.status
+    }
+
+    /// The timeouts of this servo, or the interface if not set
+    fn timeouts<I: AsyncInterface>(&self, interface: &I) -> Timeouts {
+        self.timeouts.unwrap_or_else(|| interface.timeouts())
+    }
+
+    /// The timeout for the status of a write starting at `address`
+    fn write_timeout<I: AsyncInterface>(&self, interface: &I, address: u16) -> Duration {
+        let timeouts = self.timeouts(interface);
+        if address < self.eeprom_end {
+            timeouts.eeprom_write
+        } else {
+            timeouts.status
+        }
+    }
+
+    /// Ping the servo, returning `Ok(ServoInfo)` if it exists.
+    pub async fn ping<I: AsyncInterface>(&mut self, interface: &mut I) -> Result<ServoInfo, Error> {
+        transaction(self.id, async {
+            interface.set_baud_rate(self.baud_rate)?;
+            interface.flush();
+
+            write_instruction(interface, instruction::Ping::new(PacketID::from(self.id))).await?;
+            let timeout = self.timeouts(interface).ping;
+            let pong = self.read_status::<I, instruction::Pong>(interface, timeout).await?;
+            Ok(ServoInfo{
+                baud_rate: self.baud_rate,
+                model_number: pong.model_number,
+                fw_version: pong.fw_version,
+                id: pong.id,
+            })
+        }).await
+    }
+
+    /// Write the given data `register` to the servo.
+    pub async fn write<I: AsyncInterface, W: WriteRegister + Send + Sync>(&mut self, interface: &mut I, register: W) -> Result<(), Error> {
+        transaction(self.id, async {
+            interface.set_baud_rate(self.baud_rate)?;
+            let timeout = self.write_timeout(interface, W::ADDRESS);
+            write_instruction(interface, instruction::Write::new(PacketID::from(self.id), register)).await?;
+            if self.status_return_level.replies_to_writes() {
+                self.read_status::<I, instruction::WriteResponse>(interface, timeout).await?;
+            }
+            Ok(())
+        }).await
+    }
+
+    /// Read data from a register
+    pub async fn read<I: AsyncInterface, R: ReadRegister + Send + Sync>(&mut self, interface: &mut I) -> Result<R, Error> {
+        transaction(self.id, async {
+            if !self.status_return_level.replies_to_reads() {
+                return Err(CommunicationError::NoStatus.into());
+            }
+            interface.set_baud_rate(self.baud_rate)?;
+            interface.flush();
+
+            write_instruction(interface, instruction::Read::<R>::new(PacketID::from(self.id))).await?;
+            let timeout = self.timeouts(interface).status;
+            Ok(self.read_status::<I, instruction::ReadResponse<R>>(interface, timeout).await?.value)
+        }).await
+    }
+
+    /// Write `N` raw bytes to the control table, starting at `address`.
+    ///
+    /// At most 4 bytes can be written at a time.
+    pub async fn write_raw<I: AsyncInterface, const N: usize>(&mut self, interface: &mut I, address: u16, data: [u8; N]) -> Result<(), Error> {
+        transaction(self.id, async {
+            interface.set_baud_rate(self.baud_rate)?;
+            let timeout = self.write_timeout(interface, address);
+            write_instruction(interface, instruction::WriteRaw::new(PacketID::from(self.id), address, data)).await?;
+            if self.status_return_level.replies_to_writes() {
+                self.read_status::<I, instruction::WriteResponse>(interface, timeout).await?;
+            }
+            Ok(())
+        }).await
+    }
+
+    /// Read `N` raw bytes from the control table, starting at `address`.
+    ///
+    /// At most 4 bytes can be read at a time.
+    pub async fn read_raw<I: AsyncInterface, const N: usize>(&mut self, interface: &mut I, address: u16) -> Result<[u8; N], Error> {
+        transaction(self.id, async {
+            if !self.status_return_level.replies_to_reads() {
+                return Err(CommunicationError::NoStatus.into());
+            }
+            interface.set_baud_rate(self.baud_rate)?;
+            interface.flush();
+
+            write_instruction(interface, instruction::ReadRaw::<N>::new(PacketID::from(self.id), address)).await?;
+            let timeout = self.timeouts(interface).status;
+            Ok(self.read_status::<I, instruction::ReadRawResponse<N>>(interface, timeout).await?.data)
+        }).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::asynchronous::protocol2::*;
+    use crate::mock::{protocol2_packet, protocol2_status, MockInterface};
+
+    #[tokio::test]
+    async fn servo() {
+        let mut interface = MockInterface::new();
+        let mut servo = AsyncServo::new(ServoID::new(1), BaudRate::Baud57600);
+
+        interface.queue_response(&protocol2_status(1, 0, &[0x06, 0x04, 0x26]));
+        let info = servo.ping(&mut interface).await.unwrap();
+        assert_eq!((info.model_number, info.fw_version), (0x0406, 0x26));
+
+        interface.queue_response(&protocol2_status(1, 0, &[0x00, 0x08, 0x00, 0x00]));
+        assert_eq!(servo.read_raw::<_, 4>(&mut interface, 132).await, Ok([0x00, 0x08, 0x00, 0x00]));
+
+        interface.queue_response(&protocol2_status(1, 0, &[]));
+        servo.write_raw(&mut interface, 116, [0x00, 0x08, 0x00, 0x00]).await.unwrap();
+
+        interface.assert_sent_exactly(&[
+            &protocol2_packet(1, 0x01, &[]),
+            &protocol2_packet(1, 0x02, &[132, 0, 4, 0]),
+            &protocol2_packet(1, 0x03, &[116, 0, 0x00, 0x08, 0x00, 0x00]),
+        ]);
+        assert_eq!(interface.read_timeouts().last(), Some(&Some(Timeouts::default().status)));
+
+        // Writes to the EEPROM area get the longer timeout
+        interface.queue_response(&protocol2_status(1, 0, &[]));
+        servo.write_raw(&mut interface, 11, [1]).await.unwrap();
+        assert_eq!(interface.read_timeouts().last(), Some(&Some(Timeouts::default().eeprom_write)));
+
+        // The alert bit is kept, also when the servo reports an error
+        assert!(!servo.alert());
+        interface.queue_response(&protocol2_status(1, 0x80 | 0x07, &[]));
+        assert_eq!(servo.read_raw::<_, 1>(&mut interface, 11).await, Err(Error::Processing{id: ServoID::new(1), error: crate::protocol2::ProcessingError::AccessError, alert: true}));
+        assert!(servo.alert());
+
+        // Reads are not sent to servos that won't answer them
+        let mut interface = MockInterface::new();
+        servo.assume_status_return_level(StatusReturnLevel::Ping);
+        assert_eq!(servo.read_raw::<_, 4>(&mut interface, 132).await, Err(Error::Communication{id: Some(ServoID::new(1)), error: CommunicationError::NoStatus}));
+        interface.assert_sent_exactly(&[]);
+
+        // The futures can be spawned on multi-threaded runtimes
+        fn assert_send<T: Send>(_: &T) {}
+        let future = servo.read_raw::<_, 4>(&mut interface, 132);
+        assert_send(&future);
+    }
+
+    #[tokio::test]
+    async fn enumerate_servos() {
+        let mut interface = MockInterface::new();
+        interface.queue_response(&protocol2_status(1, 0, &[0x06, 0x04, 0x26]));
+        interface.queue_response(&protocol2_status(3, 0, &[0x06, 0x04, 0x26]));
+
+        let servos = enumerate(&mut interface).await.unwrap();
+        assert_eq!(servos.iter().map(|s| u8::from(s.id)).collect::<Vec<_>>(), vec![1, 3]);
+        assert_eq!(servos[0].baud_rate, BaudRate::variants()[0]);
+    }
+}
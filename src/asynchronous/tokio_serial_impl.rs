@@ -0,0 +1,118 @@
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio_serial::{ClearBuffer, SerialPort, SerialPortBuilderExt, SerialStream};
+
+use crate::asynchronous::AsyncInterface;
+use crate::{BaudRate, CommunicationError, Timeouts};
+
+/// A tokio serial port configured for servo communication
+///
+/// Timeouts are implemented with `tokio::time`, so a tokio runtime with the time driver enabled is required.
+/// The baud rate is only reconfigured when it changes.
+pub struct TokioSerialInterface {
+    port: SerialStream,
+    timeouts: Timeouts,
+    /// The baud rate last set through this interface
+    baud_rate: Option<BaudRate>,
+}
+
+impl TokioSerialInterface {
+    /// Use `port` for servo communication
+    pub fn new(port: SerialStream) -> Self {
+        TokioSerialInterface{port, timeouts: Timeouts::default(), baud_rate: None}
+    }
+
+    /// Open the serial port at `path`
+    ///
+    /// The baud rate is set by the servos before communicating.
+    pub fn open(path: &str) -> Result<Self, tokio_serial::Error> {
+        let port = tokio_serial::new(path, u32::from(BaudRate::Baud1000000)).open_native_async()?;
+        Ok(Self::new(port))
+    }
+
+    /// Set the timeouts used by servos and enumeration on this interface
+    pub fn set_timeouts(&mut self, timeouts: Timeouts) {
+        self.timeouts = timeouts;
+    }
+
+    pub fn port(&self) -> &SerialStream {
+        &self.port
+    }
+
+    /// The serial port
+    ///
+    /// The baud rate is set again before the next transaction, in case it is changed through the returned reference.
+    pub fn port_mut(&mut self) -> &mut SerialStream {
+        self.baud_rate = None;
+        &mut self.port
+    }
+
+    pub fn into_inner(self) -> SerialStream {
+        self.port
+    }
+}
+
+impl AsyncInterface for TokioSerialInterface {
+    fn set_baud_rate(&mut self, b: BaudRate) -> Result<(), CommunicationError> {
+        if self.baud_rate == Some(b) {
+            return Ok(());
+        }
+        self.baud_rate = None;
+        SerialPort::set_baud_rate(&mut self.port, u32::from(b)).map_err(|_| CommunicationError::UnsupportedBaud(b))?;
+        self.baud_rate = Some(b);
+        Ok(())
+    }
+
+    fn flush(&mut self) {
+        let _res = self.port.clear(ClearBuffer::Input);
+    }
+
+    async fn read_with_timeout(&mut self, data: &mut [u8], timeout: Duration) -> Result<(), CommunicationError> {
+        let mut received = 0;
+        while received < data.len() {
+            match tokio::time::timeout(timeout, self.port.read(&mut data[received..])).await {
+                Ok(Ok(0)) | Ok(Err(_)) => return Err(CommunicationError::Other),
+                Ok(Ok(n)) => received += n,
+                Err(_) => return Err(CommunicationError::TimedOut),
+            }
+        }
+        Ok(())
+    }
+
+    async fn write(&mut self, data: &[u8]) -> Result<(), CommunicationError> {
+        let timeout = self.timeouts.status;
+        match tokio::time::timeout(timeout, self.port.write_all(data)).await {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(_)) => Err(CommunicationError::Other),
+            Err(_) => Err(CommunicationError::TimedOut),
+        }
+    }
+
+    fn timeouts(&self) -> Timeouts {
+        self.timeouts
+    }
+}
+
+#[cfg(all(test, unix, feature="serialport"))]
+mod tests {
+    use crate::asynchronous::tokio_serial_impl::*;
+    use crate::sim::pty::PtyBridge;
+    use crate::sim::{SimServo, Simulator};
+
+    #[tokio::test]
+    async fn simulated_servos() {
+        let bridge = PtyBridge::spawn(Simulator::new().with_servo(SimServo::ax12(1)).with_servo(SimServo::ax12(2))).unwrap();
+        let mut interface = TokioSerialInterface::open(bridge.path()).unwrap();
+
+        let servos = crate::asynchronous::protocol1::enumerate(&mut interface).await.unwrap();
+        assert_eq!(servos.iter().map(|s| u8::from(s.id)).collect::<Vec<_>>(), vec![1, 2]);
+
+        let mut servo = crate::asynchronous::protocol1::AsyncServo::from_info(&servos[1]);
+        servo.write_data_raw(&mut interface, 30, [0x00, 0x02]).await.unwrap();
+        assert_eq!(bridge.simulator().servo(crate::registry::Protocol::Protocol1, 2).unwrap().get(30, 2), 512);
+
+        let mut data = [0u8; 1];
+        assert_eq!(interface.read_with_timeout(&mut data, Duration::from_millis(10)).await, Err(CommunicationError::TimedOut));
+    }
+}
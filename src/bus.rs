@@ -0,0 +1,316 @@
+//! Sharing one interface between threads
+//!
+//! A `Bus` owns an `Interface` and the servos connected to it.
+//! `ServoHandle`s are cheap to clone and can be sent to other threads, every call on a handle is one transaction on the bus,
+//! so transactions from different threads never interleave.
+//!
+//! Servos set their baud rate before every transaction. The bus keeps track of the current baud rate,
+//! and only changes it when a servo with another baud rate is used.
+//!
+//! This module requires the `std` feature.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use crate::protocol2::HardwareError;
+use crate::registry::Protocol;
+use crate::units::{Amperes, Percent, Radians, RadiansPerSecond};
+use crate::{BaudRate, CommunicationError, Error, Interface, LimitPolicy, OperatingMode, Servo, ServoProtocol, StatusReturnLevel, Timeouts, TrackedBaudRate};
+
+/// The interface used by servos on a `Bus`, changing the baud rate only when it differs from the current one
+pub type BusInterface<I> = TrackedBaudRate<I>;
+
+/// The protocol, baud rate and ID of a servo, identifying it on the bus
+///
+/// Servos at different baud rates never hear each other, so they can share an ID.
+type ServoKey = (Protocol, BaudRate, u8);
+
+fn servo_key(info: &ServoProtocol) -> ServoKey {
+    match info {
+        ServoProtocol::Protocol1(si) => (Protocol::Protocol1, si.baud_rate, u8::from(si.id)),
+        ServoProtocol::Protocol2(si) => (Protocol::Protocol2, si.baud_rate, u8::from(si.id)),
+    }
+}
+
+type BoxedServo<I> = Box<dyn Servo<BusInterface<I>> + Send>;
+
+struct State<I> {
+    interface: BusInterface<I>,
+    servos: HashMap<ServoKey, BoxedServo<I>>,
+}
+
+/// An `Interface` shared between threads
+///
+/// Cloning the bus gives another reference to the same interface.
+pub struct Bus<I> {
+    state: Arc<Mutex<State<I>>>,
+}
+
+impl<I> Clone for Bus<I> {
+    fn clone(&self) -> Self {
+        Bus{state: self.state.clone()}
+    }
+}
+
+impl<I: Interface + Send + 'static> Bus<I> {
+    pub fn new(interface: I) -> Self {
+        Bus{
+            state: Arc::new(Mutex::new(State{
+                interface: TrackedBaudRate::new(interface),
+                servos: HashMap::new(),
+            })),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State<I>> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Use the interface directly, without other threads using it in between
+    pub fn transaction<R>(&self, f: impl FnOnce(&mut BusInterface<I>) -> R) -> R {
+        f(&mut self.lock().interface)
+    }
+
+    /// Enumerate all servos connected to the bus, see `enumerate`
+    pub fn enumerate(&self) -> Result<Vec<ServoProtocol>, CommunicationError> {
+        self.transaction(crate::enumerate)
+    }
+
+    /// Connect to a servo, see `connect`
+    ///
+    /// All handles to the same servo share its state, like the cached position limits and limit policy.
+    /// Connecting to a servo that is already connected returns another handle to it.
+    pub fn connect(&self, info: ServoProtocol) -> Result<ServoHandle<I>, Error> {
+        self.connect_with(info, crate::connect)
+    }
+
+    /// Connect to a servo, using the generic servo types for unknown models, see `connect_or_generic`
+    pub fn connect_or_generic(&self, info: ServoProtocol) -> Result<ServoHandle<I>, Error> {
+        self.connect_with(info, crate::connect_or_generic)
+    }
+
+    /// Set the return delay time of every connected servo, see `Servo::set_return_delay_time`
+    ///
+    /// Zero gives the highest throughput. Stops at the first servo that fails.
+    pub fn set_return_delay_time(&self, delay: Duration) -> Result<(), Error> {
+        let mut state = self.lock();
+        let State{interface, servos} = &mut *state;
+        servos.values_mut().try_for_each(|servo| servo.set_return_delay_time(interface, delay))
+    }
+
+    fn connect_with(&self, info: ServoProtocol, connect: fn(&mut BusInterface<I>, ServoProtocol) -> Result<BoxedServo<I>, Error>) -> Result<ServoHandle<I>, Error> {
+        let key = servo_key(&info);
+        let mut state = self.lock();
+        if !state.servos.contains_key(&key) {
+            let servo = connect(&mut state.interface, info.clone())?;
+            state.servos.insert(key, servo);
+        }
+        Ok(ServoHandle{bus: self.clone(), info, key})
+    }
+}
+
+/// A servo on a `Bus`
+///
+/// Handles are cheap to clone, and can be sent to other threads.
+/// Every method is performed as one transaction on the bus.
+pub struct ServoHandle<I> {
+    bus: Bus<I>,
+    info: ServoProtocol,
+    key: ServoKey,
+}
+
+impl<I> Clone for ServoHandle<I> {
+    fn clone(&self) -> Self {
+        ServoHandle{bus: self.bus.clone(), info: self.info.clone(), key: self.key}
+    }
+}
+
+impl<I: Interface + Send + 'static> ServoHandle<I> {
+    /// The information the servo was connected with
+    pub fn info(&self) -> &ServoProtocol {
+        &self.info
+    }
+
+    /// Use the servo with the bus' interface, without other threads using the bus in between
+    pub fn transaction<R>(&self, f: impl FnOnce(&mut dyn Servo<BusInterface<I>>, &mut BusInterface<I>) -> R) -> R {
+        let mut state = self.bus.lock();
+        let State{interface, servos} = &mut *state;
+        let servo = servos.get_mut(&self.key).expect("servos are never removed from the bus");
+        f(servo.as_mut(), interface)
+    }
+
+    pub fn set_enable_torque(&self, enable_torque: bool) -> Result<(), Error> {
+        self.transaction(|servo, interface| servo.set_enable_torque(interface, enable_torque))
+    }
+
+    pub fn set_position(&self, value: Radians) -> Result<(), Error> {
+        self.transaction(|servo, interface| servo.set_position(interface, value))
+    }
+
+    pub fn get_position(&self) -> Result<Radians, Error> {
+        self.transaction(|servo, interface| servo.get_position(interface))
+    }
+
+    pub fn set_goal_velocity(&self, value: RadiansPerSecond) -> Result<(), Error> {
+        self.transaction(|servo, interface| servo.set_goal_velocity(interface, value))
+    }
+
+    pub fn get_velocity(&self) -> Result<RadiansPerSecond, Error> {
+        self.transaction(|servo, interface| servo.get_velocity(interface))
+    }
+
+    pub fn get_load(&self) -> Result<Percent, Error> {
+        self.transaction(|servo, interface| servo.get_load(interface))
+    }
+
+    pub fn get_current(&self) -> Result<Amperes, Error> {
+        self.transaction(|servo, interface| servo.get_current(interface))
+    }
+
+    pub fn get_temperature(&self) -> Result<f32, Error> {
+        self.transaction(|servo, interface| servo.get_temperature(interface))
+    }
+
+    pub fn get_voltage(&self) -> Result<f32, Error> {
+        self.transaction(|servo, interface| servo.get_voltage(interface))
+    }
+
+    pub fn set_operating_mode(&self, mode: OperatingMode) -> Result<(), Error> {
+        self.transaction(|servo, interface| servo.set_operating_mode(interface, mode))
+    }
+
+    pub fn get_operating_mode(&self) -> Result<OperatingMode, Error> {
+        self.transaction(|servo, interface| servo.get_operating_mode(interface))
+    }
+
+    pub fn set_limit_policy(&self, policy: LimitPolicy) {
+        self.transaction(|servo, _| servo.set_limit_policy(policy))
+    }
+
+    pub fn get_limit_policy(&self) -> LimitPolicy {
+        self.transaction(|servo, _| servo.get_limit_policy())
+    }
+
+    pub fn get_position_limits(&self) -> Result<(Radians, Radians), Error> {
+        self.transaction(|servo, interface| servo.get_position_limits(interface))
+    }
+
+    pub fn set_timeouts(&self, timeouts: Option<Timeouts>) {
+        self.transaction(|servo, _| servo.set_timeouts(timeouts))
+    }
+
+    pub fn assume_status_return_level(&self, level: StatusReturnLevel) {
+        self.transaction(|servo, _| servo.assume_status_return_level(level))
+    }
+
+    pub fn read_status_return_level(&self) -> Result<StatusReturnLevel, Error> {
+        self.transaction(|servo, interface| servo.read_status_return_level(interface))
+    }
+
+    pub fn get_return_delay_time(&self) -> Result<Duration, Error> {
+        self.transaction(|servo, interface| servo.get_return_delay_time(interface))
+    }
+
+    pub fn set_return_delay_time(&self, delay: Duration) -> Result<(), Error> {
+        self.transaction(|servo, interface| servo.set_return_delay_time(interface, delay))
+    }
+
+    pub fn hardware_error(&self) -> Result<HardwareError, Error> {
+        self.transaction(|servo, interface| servo.hardware_error(interface))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bus::*;
+    use crate::mock::{protocol1_status, MockInterface};
+    use crate::sim::{SimServo, Simulator};
+
+    #[test]
+    fn threads() {
+        let simulator = (1..=4).fold(Simulator::new(), |sim, id| sim.with_servo(SimServo::ax12(id)));
+        let bus = Bus::new(simulator);
+
+        let threads: Vec<_> = bus.enumerate().unwrap().into_iter()
+            .map(|info| bus.connect(info).unwrap())
+            .map(|servo| std::thread::spawn(move || {
+                for i in 0..10u8 {
+                    let goal = Radians(0.1 * f32::from(i));
+                    servo.set_position(goal).unwrap();
+                    servo.get_position().unwrap();
+                }
+            }))
+            .collect();
+        assert_eq!(threads.len(), 4);
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        bus.transaction(|interface| {
+            for servo in interface.inner().servos() {
+                assert_eq!(servo.get(30, 2), i64::from(crate::dynamixel::ax12::ENCODER.to_steps(Radians(0.9)).unwrap()));
+            }
+        });
+    }
+
+    #[test]
+    fn baud_rate() {
+        let mut mock = MockInterface::new();
+        for _ in 0..3 {
+            mock.queue_response(&protocol1_status(1, 0, &[0x00, 0x02]));
+        }
+        let bus = Bus::new(mock);
+        let info = crate::protocol1::ServoInfo{baud_rate: BaudRate::Baud1000000, model_number: 12, fw_version: 0, id: crate::protocol1::ServoID::new(1)};
+        let servo = bus.connect(ServoProtocol::Protocol1(info)).unwrap();
+        let other = servo.clone();
+
+        assert_eq!(servo.get_position(), Ok(Radians(0.0)));
+        assert_eq!(other.get_position(), Ok(Radians(0.0)));
+        assert_eq!(bus.transaction(|interface| interface.inner().baud_rates().len()), 1);
+
+        // Using the interface directly sets the baud rate again
+        bus.transaction(|interface| interface.inner_mut().flush());
+        assert_eq!(other.get_position(), Ok(Radians(0.0)));
+        assert_eq!(bus.transaction(|interface| interface.inner().baud_rates().len()), 2);
+
+        // Handles share the servo state
+        servo.set_limit_policy(LimitPolicy::Clamp);
+        assert_eq!(other.get_limit_policy(), LimitPolicy::Clamp);
+    }
+
+    #[test]
+    fn same_id_at_different_baud_rates() {
+        let bus = Bus::new(MockInterface::new());
+        let info = |baud_rate| ServoProtocol::Protocol1(crate::protocol1::ServoInfo{baud_rate, model_number: 12, fw_version: 0, id: crate::protocol1::ServoID::new(1)});
+        let slow = bus.connect(info(BaudRate::Baud57600)).unwrap();
+        let fast = bus.connect(info(BaudRate::Baud1000000)).unwrap();
+
+        slow.set_limit_policy(LimitPolicy::Clamp);
+        assert_eq!(fast.get_limit_policy(), LimitPolicy::Reject);
+
+        bus.transaction(|interface| interface.inner_mut().queue_response(&protocol1_status(1, 0, &[0x00, 0x02])));
+        assert_eq!(fast.get_position(), Ok(Radians(0.0)));
+        assert_eq!(bus.transaction(|interface| interface.inner().baud_rates().to_vec()), vec![BaudRate::Baud1000000]);
+        match fast.info() {
+            ServoProtocol::Protocol1(si) => assert_eq!(si.baud_rate, BaudRate::Baud1000000),
+            _ => panic!("expected a protocol 1 servo"),
+        }
+    }
+
+    #[test]
+    fn return_delay_time() {
+        let simulator = (1..=3).fold(Simulator::new(), |sim, id| sim.with_servo(SimServo::ax12(id)));
+        let bus = Bus::new(simulator);
+        let servos: Vec<_> = bus.enumerate().unwrap().into_iter().map(|info| bus.connect(info).unwrap()).collect();
+        assert_eq!(servos[0].get_return_delay_time(), Ok(Duration::from_micros(500)));
+
+        bus.set_return_delay_time(Duration::ZERO).unwrap();
+        for servo in &servos {
+            assert_eq!(servo.get_return_delay_time(), Ok(Duration::ZERO));
+        }
+        bus.transaction(|interface| {
+            assert!(interface.inner().servos().iter().all(|servo| servo.get(5, 1) == 0));
+        });
+    }
+}
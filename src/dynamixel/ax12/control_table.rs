@@ -2,7 +2,14 @@ pub trait Register: crate::protocol1::Register {}
 pub trait ReadRegister: crate::protocol1::ReadRegister {}
 pub trait WriteRegister: crate::protocol1::WriteRegister {}
 
+rw_reg1!(CwAngleLimit, u16, 6);
+rw_reg1!(CcwAngleLimit, u16, 8);
 rw_reg1!(TorqueEnable, bool, 24);
 rw_reg1!(Led, bool, 25);
 rw_reg1!(GoalPosition, u16, 30);
+rw_reg1!(MovingSpeed, u16, 32);
 r_reg1!(PresentPosition, u16, 36);
+r_reg1!(PresentSpeed, u16, 38);
+r_reg1!(PresentLoad, u16, 40);
+r_reg1!(PresentVoltage, u8, 42);
+r_reg1!(PresentTemperature, u8, 43);
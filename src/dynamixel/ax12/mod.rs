@@ -1,35 +1,75 @@
 pub mod control_table;
 
 use crate::Interface;
-use crate::Servo;
+use crate::units::{Percent, Radians, RadiansPerSecond, Rpm};
 
-protocol1_servo!(AX12, crate::dynamixel::ax12::control_table::WriteRegister, crate::dynamixel::ax12::control_table::ReadRegister, 12);
+protocol1_servo!(AX12, crate::dynamixel::ax12::control_table::WriteRegister, crate::dynamixel::ax12::control_table::ReadRegister, 24, 16, 12);
 
-fn constrain(value: u16, min: u16, max: u16) -> u16 {
-    if value < min {
-        min
-    } else if value > max {
-        max
-    } else {
-        value
+/// The position encoder, 1024 steps over 300 degrees with 0 radians in the center.
+pub const ENCODER: crate::units::Encoder = crate::units::Encoder{resolution: 1024, zero_offset: 512, range: crate::units::Radians(300.0 * crate::lib::f32::consts::PI / 180.0)};
+
+/// Unit of moving speed and present speed, in rpm.
+const SPEED_UNIT: f32 = 0.111;
+
+impl<I: Interface> AX12<I> {
+    fn read_position_limits(&mut self, interface: &mut I) -> Result<(i32, i32), crate::Error> {
+        let cw = u16::from(self.read_data::<control_table::CwAngleLimit>(interface)?);
+        let ccw = u16::from(self.read_data::<control_table::CcwAngleLimit>(interface)?);
+        Ok(crate::protocol1::decode_position_limits(cw, ccw, 1023, false))
     }
 }
 
-impl<I: Interface> Servo<I> for AX12<I> {
+protocol1_servo!(impl Servo for AX12 with ENCODER {
     fn set_enable_torque(&mut self, interface: &mut I, enable_torque: bool) -> Result<(), crate::Error> {
         Ok(self.write_data(interface, control_table::TorqueEnable::new(enable_torque))?)
     }
     
-    fn set_position(&mut self, interface: &mut I, angle: f32) -> Result<(), crate::Error> {
-        let goal_position = ((angle*3.41) as i32) as u16;
-        let goal_limited = constrain(goal_position, 0, 1023);
-        let a = self.write_data(interface, control_table::GoalPosition::new(goal_limited))?;
-        Ok(a)
+    fn set_position(&mut self, interface: &mut I, value: Radians) -> Result<(), crate::Error> {
+        let limits = self.position_limits(interface, Self::read_position_limits)?;
+        let goal_position = self.limits.apply(&ENCODER, value, limits)?;
+        Ok(self.write_data(interface, control_table::GoalPosition::new(goal_position as u16))?)
     }
     
-    fn get_position(&mut self, interface: &mut I) -> Result<f32, crate::Error> {
-        let pos_fixed = f32::from(u16::from(self.read_data::<crate::dynamixel::ax12::control_table::PresentPosition>(interface)?));
-        let pos_rad = pos_fixed / 3.41;
-        Ok(pos_rad)
+    fn get_position(&mut self, interface: &mut I) -> Result<Radians, crate::Error> {
+        let pos_fixed = i32::from(u16::from(self.read_data::<crate::dynamixel::ax12::control_table::PresentPosition>(interface)?));
+        Ok(ENCODER.to_radians(pos_fixed))
     }
-}
+
+    fn set_goal_velocity(&mut self, interface: &mut I, value: RadiansPerSecond) -> Result<(), crate::Error> {
+        let speed = crate::units::to_steps(f64::from(Rpm::from(value).value() / SPEED_UNIT)).ok_or(crate::Error::InvalidValue)?;
+        let moving_speed = crate::protocol1::encode_direction_bit(speed);
+        Ok(self.write_data(interface, control_table::MovingSpeed::new(moving_speed))?)
+    }
+
+    fn get_velocity(&mut self, interface: &mut I) -> Result<RadiansPerSecond, crate::Error> {
+        let speed = crate::protocol1::decode_direction_bit(u16::from(self.read_data::<control_table::PresentSpeed>(interface)?));
+        Ok(RadiansPerSecond::from(Rpm(speed as f32 * SPEED_UNIT)))
+    }
+
+    fn get_load(&mut self, interface: &mut I) -> Result<Percent, crate::Error> {
+        let load = crate::protocol1::decode_direction_bit(u16::from(self.read_data::<control_table::PresentLoad>(interface)?));
+        Ok(Percent(load as f32 / 10.0))
+    }
+
+    fn get_temperature(&mut self, interface: &mut I) -> Result<f32, crate::Error> {
+        Ok(f32::from(u8::from(self.read_data::<control_table::PresentTemperature>(interface)?)))
+    }
+
+    fn get_voltage(&mut self, interface: &mut I) -> Result<f32, crate::Error> {
+        Ok(f32::from(u8::from(self.read_data::<control_table::PresentVoltage>(interface)?)) / 10.0)
+    }
+
+    fn set_operating_mode(&mut self, interface: &mut I, mode: crate::OperatingMode) -> Result<(), crate::Error> {
+        let (cw, ccw) = crate::protocol1::encode_angle_limits(mode, 1023, false)
+            .ok_or(crate::Error::Unsupported(crate::Capability::OperatingMode(mode)))?;
+        self.limits.cached = None;
+        self.write_data(interface, control_table::CwAngleLimit::new(cw))?;
+        Ok(self.write_data(interface, control_table::CcwAngleLimit::new(ccw))?)
+    }
+
+    fn get_operating_mode(&mut self, interface: &mut I) -> Result<crate::OperatingMode, crate::Error> {
+        let cw = u16::from(self.read_data::<control_table::CwAngleLimit>(interface)?);
+        let ccw = u16::from(self.read_data::<control_table::CcwAngleLimit>(interface)?);
+        Ok(crate::protocol1::decode_angle_limits(cw, ccw, 1023, false))
+    }
+});
@@ -4,3 +4,4 @@
 
 pub mod mx28;
 pub mod ax12;
+pub mod xl430;
@@ -1,23 +1,76 @@
 pub mod control_table;
 
 use crate::Interface;
-use crate::Servo;
+use crate::units::{Percent, Radians, RadiansPerSecond, Rpm};
 
-protocol1_servo!(MX28, crate::dynamixel::mx28::control_table::WriteRegister, crate::dynamixel::mx28::control_table::ReadRegister, 0x001D);
+protocol1_servo!(MX28, crate::dynamixel::mx28::control_table::WriteRegister, crate::dynamixel::mx28::control_table::ReadRegister, 24, 16, 0x001D);
 
-impl<I: Interface> Servo<I> for MX28<I> {
+/// The position encoder, 4096 steps per revolution with 0 radians in the center.
+pub const ENCODER: crate::units::Encoder = crate::units::Encoder{resolution: 4096, zero_offset: 2048, range: crate::units::Radians(2.0 * crate::lib::f32::consts::PI)};
+
+/// Unit of moving speed and present speed, in rpm.
+const SPEED_UNIT: f32 = 0.114;
+
+impl<I: Interface> MX28<I> {
+    fn read_position_limits(&mut self, interface: &mut I) -> Result<(i32, i32), crate::Error> {
+        let cw = u16::from(self.read_data::<control_table::CwAngleLimit>(interface)?);
+        let ccw = u16::from(self.read_data::<control_table::CcwAngleLimit>(interface)?);
+        Ok(crate::protocol1::decode_position_limits(cw, ccw, 4095, true))
+    }
+}
+
+protocol1_servo!(impl Servo for MX28 with ENCODER {
     fn set_enable_torque(&mut self, interface: &mut I, enable_torque: bool) -> Result<(), crate::Error> {
         Ok(self.write_data(interface, control_table::TorqueEnable::new(enable_torque))?)
     }
     
-    fn set_position(&mut self, interface: &mut I, value: f32) -> Result<(), crate::Error> {
-        let goal_position = (2048i32 + (value*651.08854) as i32) as u16;
-        Ok(self.write_data(interface, control_table::GoalPosition::new(goal_position))?)
+    fn set_position(&mut self, interface: &mut I, value: Radians) -> Result<(), crate::Error> {
+        let limits = self.position_limits(interface, Self::read_position_limits)?;
+        let goal_position = self.limits.apply(&ENCODER, value, limits)?;
+        // Negative goals are only possible in multi-turn mode, where the register is signed
+        Ok(self.write_data(interface, control_table::GoalPosition::new(goal_position as u16))?)
     }
     
-    fn get_position(&mut self, interface: &mut I) -> Result<f32, crate::Error> {
-        let pos_fixed = i32::from(u16::from(self.read_data::<crate::dynamixel::mx28::control_table::PresentPosition>(interface)?));
-        let pos_rad = ((pos_fixed - 2048i32) as f32)/652.23f32;
-        Ok(pos_rad)
+    fn get_position(&mut self, interface: &mut I) -> Result<Radians, crate::Error> {
+        let pos_fixed = i32::from(u16::from(self.read_data::<crate::dynamixel::mx28::control_table::PresentPosition>(interface)?) as i16);
+        Ok(ENCODER.to_radians(pos_fixed))
     }
-}
+
+    fn set_goal_velocity(&mut self, interface: &mut I, value: RadiansPerSecond) -> Result<(), crate::Error> {
+        let speed = crate::units::to_steps(f64::from(Rpm::from(value).value() / SPEED_UNIT)).ok_or(crate::Error::InvalidValue)?;
+        let moving_speed = crate::protocol1::encode_direction_bit(speed);
+        Ok(self.write_data(interface, control_table::MovingSpeed::new(moving_speed))?)
+    }
+
+    fn get_velocity(&mut self, interface: &mut I) -> Result<RadiansPerSecond, crate::Error> {
+        let speed = crate::protocol1::decode_direction_bit(u16::from(self.read_data::<control_table::PresentSpeed>(interface)?));
+        Ok(RadiansPerSecond::from(Rpm(speed as f32 * SPEED_UNIT)))
+    }
+
+    fn get_load(&mut self, interface: &mut I) -> Result<Percent, crate::Error> {
+        let load = crate::protocol1::decode_direction_bit(u16::from(self.read_data::<control_table::PresentLoad>(interface)?));
+        Ok(Percent(load as f32 / 10.0))
+    }
+
+    fn get_temperature(&mut self, interface: &mut I) -> Result<f32, crate::Error> {
+        Ok(f32::from(u8::from(self.read_data::<control_table::PresentTemperature>(interface)?)))
+    }
+
+    fn get_voltage(&mut self, interface: &mut I) -> Result<f32, crate::Error> {
+        Ok(f32::from(u8::from(self.read_data::<control_table::PresentVoltage>(interface)?)) / 10.0)
+    }
+
+    fn set_operating_mode(&mut self, interface: &mut I, mode: crate::OperatingMode) -> Result<(), crate::Error> {
+        let (cw, ccw) = crate::protocol1::encode_angle_limits(mode, 4095, true)
+            .ok_or(crate::Error::Unsupported(crate::Capability::OperatingMode(mode)))?;
+        self.limits.cached = None;
+        self.write_data(interface, control_table::CwAngleLimit::new(cw))?;
+        Ok(self.write_data(interface, control_table::CcwAngleLimit::new(ccw))?)
+    }
+
+    fn get_operating_mode(&mut self, interface: &mut I) -> Result<crate::OperatingMode, crate::Error> {
+        let cw = u16::from(self.read_data::<control_table::CwAngleLimit>(interface)?);
+        let ccw = u16::from(self.read_data::<control_table::CcwAngleLimit>(interface)?);
+        Ok(crate::protocol1::decode_angle_limits(cw, ccw, 4095, true))
+    }
+});
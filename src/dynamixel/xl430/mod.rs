@@ -0,0 +1,53 @@
+//! XL430 servos, and the other protocol 2 servos reporting load instead of current, like the MX-28(2.0)
+//!
+//! The control table is the one of the X series, except that Present Load is found where the other X series servos have Present Current,
+//! and there are no current based operating modes.
+//!
+//! Documentation can be found in: http://emanual.robotis.com/docs/en/dxl/x/xl430-w250/
+
+pub mod control_table;
+
+use crate::Interface;
+use crate::units::Percent;
+
+protocol2_servo!(XL430, control_table::WriteRegister, control_table::ReadRegister, 64, 68, 70, 1060);
+
+pub use crate::protocol2::generic::ENCODER;
+
+/// The operating modes and their values of the operating mode register
+const OPERATING_MODES: &[(crate::OperatingMode, u8)] = &[
+    (crate::OperatingMode::Velocity, 1),
+    (crate::OperatingMode::Position, 3),
+    (crate::OperatingMode::ExtendedPosition, 4),
+    (crate::OperatingMode::Pwm, 16),
+];
+
+x_series_servo!(impl Servo for XL430 with control_table, OPERATING_MODES {
+    fn get_load(&mut self, interface: &mut I) -> Result<Percent, crate::Error> {
+        // Present Load is given in 0.1 % of the maximum torque, with the same sign convention as the position
+        let load = i16::from(self.read::<control_table::PresentLoad>(interface)?);
+        Ok(Percent(f32::from(load) / 10.0))
+    }
+});
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature="std")]
+    #[test]
+    fn load_instead_of_current() {
+        use crate::mock::*;
+        use crate::Servo;
+
+        let mut mock = MockInterface::new();
+        mock.queue_response(&protocol2_status(1, 0, &[0x0c, 0xfe]));
+
+        let mut servo = crate::dynamixel::xl430::XL430::new(crate::protocol2::ServoID::new(1), crate::BaudRate::Baud57600);
+        assert_eq!(servo.get_load(&mut mock), Ok(crate::units::Percent(-50.0)));
+        mock.assert_sent_exactly(&[&protocol2_packet(1, 0x02, &[126, 0, 2, 0])]);
+
+        assert_eq!(servo.get_current(&mut mock), Err(crate::Error::Unsupported(crate::Capability::Current)));
+        let mode = crate::OperatingMode::CurrentBasedPosition;
+        assert_eq!(servo.set_operating_mode(&mut mock, mode), Err(crate::Error::Unsupported(crate::Capability::OperatingMode(mode))));
+        mock.assert_all_read();
+    }
+}
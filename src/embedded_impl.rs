@@ -0,0 +1,199 @@
+use embedded_hal::serial;
+use embedded_hal::timer::CountDown;
+
+use crate::lib::time::Duration;
+use crate::{BaudRate, CommunicationError, Interface, Timeouts};
+
+/// An `Interface` over an `embedded-hal` serial port
+///
+/// Timeouts are measured with a `CountDown` timer, restarted for every received byte.
+/// The timer's time type must be creatable from a `Duration`, a HAL timer with another time type can be wrapped to convert.
+///
+/// `embedded-hal` has no way of changing the baud rate of a serial port,
+/// so the baud rate is given when creating the interface and other baud rates return `CommunicationError::UnsupportedBaud`.
+pub struct EmbeddedInterface<S, T> {
+    serial: S,
+    timer: T,
+    baud_rate: BaudRate,
+    timeouts: Timeouts,
+}
+
+impl<S, T> EmbeddedInterface<S, T>
+where
+    S: serial::Read<u8> + serial::Write<u8>,
+    T: CountDown,
+    T::Time: From<Duration>,
+{
+    /// Use `serial`, configured for `baud_rate`, with `timer` for timeouts
+    pub fn new(serial: S, timer: T, baud_rate: BaudRate) -> Self {
+        EmbeddedInterface{
+            serial,
+            timer,
+            baud_rate,
+            timeouts: Timeouts::default(),
+        }
+    }
+
+    /// Set the timeouts used by servos and enumeration on this interface
+    pub fn set_timeouts(&mut self, timeouts: Timeouts) {
+        self.timeouts = timeouts;
+    }
+
+    /// Release the serial port and timer
+    pub fn free(self) -> (S, T) {
+        (self.serial, self.timer)
+    }
+
+    /// Read a byte, returning `CommunicationError::TimedOut` if the timer expires first
+    fn read_byte(&mut self) -> Result<u8, CommunicationError> {
+        loop {
+            match self.serial.read() {
+                Ok(byte) => return Ok(byte),
+                Err(nb::Error::WouldBlock) => (),
+                Err(nb::Error::Other(_)) => return Err(CommunicationError::Other),
+            }
+            if self.timer.wait().is_ok() {
+                return Err(CommunicationError::TimedOut);
+            }
+        }
+    }
+}
+
+impl<S, T> Interface for EmbeddedInterface<S, T>
+where
+    S: serial::Read<u8> + serial::Write<u8>,
+    T: CountDown,
+    T::Time: From<Duration>,
+{
+    fn set_baud_rate(&mut self, b: BaudRate) -> Result<(), CommunicationError> {
+        if b == self.baud_rate {
+            Ok(())
+        } else {
+            Err(CommunicationError::UnsupportedBaud(b))
+        }
+    }
+
+    fn flush(&mut self) {
+        // Discard received bytes until the receive buffer is empty
+        while self.serial.read().is_ok() {}
+    }
+
+    fn read(&mut self, data: &mut [u8]) -> Result<(), CommunicationError> {
+        self.read_with_timeout(data, self.timeouts.status)
+    }
+
+    fn read_with_timeout(&mut self, data: &mut [u8], timeout: Duration) -> Result<(), CommunicationError> {
+        for byte in data.iter_mut() {
+            self.timer.start(timeout);
+            *byte = self.read_byte()?;
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, data: &[u8]) -> Result<(), CommunicationError> {
+        for byte in data {
+            nb::block!(self.serial.write(*byte)).map_err(|_| CommunicationError::Other)?;
+        }
+        nb::block!(self.serial.flush()).map_err(|_| CommunicationError::Other)
+    }
+
+    fn timeouts(&self) -> Timeouts {
+        self.timeouts
+    }
+}
+
+#[cfg(all(test, feature="std"))]
+mod tests {
+    use crate::embedded_impl::*;
+    use crate::Servo;
+    use crate::units::Radians;
+    use embedded_hal_mock::eh0::serial::{Mock, Transaction};
+    use embedded_hal_mock::eh0::MockError;
+
+    /// A timer expiring after a number of polls, instead of a time
+    struct PollTimer {
+        polls: u32,
+        started: Option<Duration>,
+    }
+
+    impl CountDown for PollTimer {
+        type Time = Duration;
+
+        fn start<D: Into<Duration>>(&mut self, count: D) {
+            self.polls = 0;
+            self.started = Some(count.into());
+        }
+
+        fn wait(&mut self) -> nb::Result<(), void::Void> {
+            self.polls += 1;
+            if self.polls > 2 {
+                Ok(())
+            } else {
+                Err(nb::Error::WouldBlock)
+            }
+        }
+    }
+
+    fn timer() -> PollTimer {
+        PollTimer{polls: 0, started: None}
+    }
+
+    fn would_block() -> Transaction<u8> {
+        Transaction::read_error(nb::Error::WouldBlock)
+    }
+
+    #[test]
+    fn protocol1() {
+        let mut serial = Mock::new(&[
+            // Flush before the instruction
+            Transaction::read(0x42),
+            would_block(),
+            Transaction::write_many(crate::mock::protocol1_packet(1, 0x02, &[36, 2])),
+            Transaction::flush(),
+            would_block(),
+            Transaction::read_many(crate::mock::protocol1_status(1, 0, &[0x00, 0x02])),
+        ]);
+        let mut interface = EmbeddedInterface::new(serial.clone(), timer(), BaudRate::Baud1000000);
+        let mut servo = crate::dynamixel::ax12::AX12::new(crate::protocol1::ServoID::new(1), BaudRate::Baud1000000);
+        assert_eq!(servo.get_position(&mut interface), Ok(Radians(0.0)));
+        assert_eq!(interface.set_baud_rate(BaudRate::Baud57600), Err(CommunicationError::UnsupportedBaud(BaudRate::Baud57600)));
+        serial.done();
+    }
+
+    #[test]
+    fn protocol2() {
+        let mut expectations = vec![would_block()];
+        // The packet is written with one call, so the port is only flushed once
+        for byte in crate::mock::protocol2_packet(1, 0x02, &[132, 0, 4, 0]) {
+            expectations.push(Transaction::write(byte));
+        }
+        expectations.push(Transaction::flush());
+        expectations.push(Transaction::read_many(crate::mock::protocol2_status(1, 0, &[0x00, 0x08, 0x00, 0x00])));
+        let mut serial = Mock::new(&expectations);
+
+        let mut interface = EmbeddedInterface::new(serial.clone(), timer(), BaudRate::Baud57600);
+        let mut servo = crate::protocol2::generic::GenericServo::new(crate::protocol2::ServoID::new(1), BaudRate::Baud57600);
+        assert_eq!(servo.get_position(&mut interface), Ok(Radians(0.0)));
+        serial.done();
+    }
+
+    #[test]
+    fn timeouts() {
+        let mut serial = Mock::new(&[
+            Transaction::read(0x01),
+            would_block(),
+            would_block(),
+            would_block(),
+            Transaction::read_error(nb::Error::Other(MockError::Io(std::io::ErrorKind::Other))),
+        ]);
+        let mut interface = EmbeddedInterface::new(serial.clone(), timer(), BaudRate::Baud1000000);
+
+        let mut data = [0u8; 2];
+        assert_eq!(interface.read_with_timeout(&mut data, Duration::from_millis(5)), Err(CommunicationError::TimedOut));
+        assert_eq!(data[0], 0x01);
+        assert_eq!(interface.read(&mut data), Err(CommunicationError::Other));
+        let (_, timer) = interface.free();
+        assert_eq!(timer.started, Some(Timeouts::default().status));
+        serial.done();
+    }
+}
@@ -0,0 +1,340 @@
+//! Direction control for half-duplex buses
+//!
+//! Dynamixel TTL and RS-485 buses are half-duplex: transmitting and receiving share the same lines.
+//! Boards without automatic direction control have a GPIO switching the transceiver between transmitting and receiving.
+//! `HalfDuplex` wraps an `Interface`, switching a `DirectionPin` to transmit before every write and back to receive afterwards,
+//! so the transceiver is receiving when the servo replies.
+//!
+//! Adapters looping the transmitted bytes back into the receiver can have the echo discarded with `HalfDuplex::with_echo`.
+
+use crate::lib::time::Duration;
+use crate::{BaudRate, CommunicationError, Interface, Timeouts};
+
+/// The direction of a half-duplex transceiver
+pub trait DirectionPin {
+    /// Switch the transceiver to transmitting
+    fn set_transmit(&mut self) -> Result<(), CommunicationError>;
+
+    /// Switch the transceiver to receiving
+    fn set_receive(&mut self) -> Result<(), CommunicationError>;
+}
+
+/// No direction pin, for transceivers switching direction automatically
+///
+/// Useful together with `HalfDuplex::with_echo` for adapters echoing the transmitted bytes.
+impl DirectionPin for () {
+    fn set_transmit(&mut self) -> Result<(), CommunicationError> {
+        Ok(())
+    }
+
+    fn set_receive(&mut self) -> Result<(), CommunicationError> {
+        Ok(())
+    }
+}
+
+/// An `embedded-hal` output pin used as direction pin
+#[cfg(feature="embedded-hal")]
+pub struct OutputPinDirection<P> {
+    pin: P,
+    transmit_high: bool,
+}
+
+#[cfg(feature="embedded-hal")]
+impl<P: embedded_hal::digital::v2::OutputPin> OutputPinDirection<P> {
+    /// A pin that is driven high when transmitting, like the DE/RE pins of most RS-485 transceivers
+    pub fn active_high(pin: P) -> Self {
+        OutputPinDirection{pin, transmit_high: true}
+    }
+
+    /// A pin that is driven low when transmitting
+    pub fn active_low(pin: P) -> Self {
+        OutputPinDirection{pin, transmit_high: false}
+    }
+
+    pub fn into_inner(self) -> P {
+        self.pin
+    }
+
+    fn set(&mut self, high: bool) -> Result<(), CommunicationError> {
+        let result = if high { self.pin.set_high() } else { self.pin.set_low() };
+        result.map_err(|_| CommunicationError::Other)
+    }
+}
+
+#[cfg(feature="embedded-hal")]
+impl<P: embedded_hal::digital::v2::OutputPin> DirectionPin for OutputPinDirection<P> {
+    fn set_transmit(&mut self) -> Result<(), CommunicationError> {
+        self.set(self.transmit_high)
+    }
+
+    fn set_receive(&mut self) -> Result<(), CommunicationError> {
+        self.set(!self.transmit_high)
+    }
+}
+
+/// An `Interface` switching a half-duplex transceiver between transmitting and receiving
+///
+/// The direction pin is set to transmit before every write and to receive when the write has completed.
+/// The wrapped interface's `write` must not return before the bytes have been transmitted, like `EmbeddedInterface`.
+/// Otherwise the end of the transmission has to be awaited by reading back the echo, see `with_echo`,
+/// or, with `std`, by waiting for the transmission time, see `with_transmit_wait`.
+pub struct HalfDuplex<I, P> {
+    interface: I,
+    pin: P,
+    echo: bool,
+    #[cfg(feature="std")]
+    transmit_wait: bool,
+    baud_rate: Option<BaudRate>,
+}
+
+impl<I: Interface, P: DirectionPin> HalfDuplex<I, P> {
+    /// Wrap `interface`, switching direction with `pin`
+    ///
+    /// The pin is set to receive immediately.
+    pub fn new(interface: I, mut pin: P) -> Result<Self, CommunicationError> {
+        pin.set_receive()?;
+        Ok(HalfDuplex{
+            interface,
+            pin,
+            echo: false,
+            #[cfg(feature="std")]
+            transmit_wait: false,
+            baud_rate: None,
+        })
+    }
+
+    /// Read back and discard the bytes echoed by the adapter after every write
+    ///
+    /// An echo differing from the written bytes means another device was transmitting at the same time,
+    /// and the write fails with `CommunicationError::Other`.
+    pub fn with_echo(mut self) -> Self {
+        self.echo = true;
+        self
+    }
+
+    /// Wait for the bytes to be transmitted before switching to receive, computed from the baud rate
+    ///
+    /// For interfaces returning from `write` when the bytes are buffered rather than transmitted, like most OS serial ports.
+    /// The baud rate must have been set through this interface for the transmission time to be known.
+    #[cfg(feature="std")]
+    pub fn with_transmit_wait(mut self) -> Self {
+        self.transmit_wait = true;
+        self
+    }
+
+    pub fn interface(&self) -> &I {
+        &self.interface
+    }
+
+    pub fn interface_mut(&mut self) -> &mut I {
+        &mut self.interface
+    }
+
+    /// Release the wrapped interface and direction pin
+    pub fn free(self) -> (I, P) {
+        (self.interface, self.pin)
+    }
+
+    /// Read the echo of `data` and compare it to `data`
+    fn discard_echo(&mut self, data: &[u8]) -> Result<(), CommunicationError> {
+        let timeout = self.interface.timeouts().status;
+        let mut echo = [0u8; 16];
+        for chunk in data.chunks(echo.len()) {
+            let echo = &mut echo[..chunk.len()];
+            self.interface.read_with_timeout(echo, timeout)?;
+            if echo != chunk {
+                return Err(CommunicationError::Other);
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(feature="std")]
+    fn wait_transmitted(&self, length: usize) {
+        if let (true, Some(baud_rate)) = (self.transmit_wait, self.baud_rate) {
+            std::thread::sleep(transmit_time(baud_rate, length));
+        }
+    }
+
+    #[cfg(not(feature="std"))]
+    fn wait_transmitted(&self, _length: usize) {}
+}
+
+/// The time it takes to transmit `length` bytes, with a start and stop bit each
+#[cfg(feature="std")]
+fn transmit_time(baud_rate: BaudRate, length: usize) -> Duration {
+    Duration::from_nanos(length as u64 * 10 * 1_000_000_000 / u64::from(u32::from(baud_rate)))
+}
+
+impl<I: Interface, P: DirectionPin> Interface for HalfDuplex<I, P> {
+    fn set_baud_rate(&mut self, b: BaudRate) -> Result<(), CommunicationError> {
+        self.interface.set_baud_rate(b)?;
+        self.baud_rate = Some(b);
+        Ok(())
+    }
+
+    fn flush(&mut self) {
+        self.interface.flush()
+    }
+
+    fn read(&mut self, data: &mut [u8]) -> Result<(), CommunicationError> {
+        self.interface.read(data)
+    }
+
+    fn read_with_timeout(&mut self, data: &mut [u8], timeout: Duration) -> Result<(), CommunicationError> {
+        self.interface.read_with_timeout(data, timeout)
+    }
+
+    fn write(&mut self, data: &[u8]) -> Result<(), CommunicationError> {
+        self.pin.set_transmit()?;
+        let written = self.interface.write(data);
+        if written.is_ok() {
+            self.wait_transmitted(data.len());
+        }
+        // Always return to receiving, so a failed write doesn't leave the bus driven
+        self.pin.set_receive()?;
+        written?;
+        if self.echo {
+            self.discard_echo(data)?;
+        }
+        Ok(())
+    }
+
+    fn timeouts(&self) -> Timeouts {
+        self.interface.timeouts()
+    }
+}
+
+#[cfg(all(test, feature="std"))]
+mod tests {
+    use crate::half_duplex::*;
+    use crate::mock::{protocol1_packet, protocol1_status, protocol2_packet, protocol2_status, MockInterface};
+    use crate::units::Radians;
+    use crate::Servo;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    /// A direction pin recording the direction of the transceiver, and every switch
+    #[derive(Clone, Default)]
+    struct Transceiver {
+        transmitting: Rc<RefCell<bool>>,
+        switches: Rc<RefCell<Vec<bool>>>,
+    }
+
+    impl DirectionPin for Transceiver {
+        fn set_transmit(&mut self) -> Result<(), CommunicationError> {
+            *self.transmitting.borrow_mut() = true;
+            self.switches.borrow_mut().push(true);
+            Ok(())
+        }
+
+        fn set_receive(&mut self) -> Result<(), CommunicationError> {
+            *self.transmitting.borrow_mut() = false;
+            self.switches.borrow_mut().push(false);
+            Ok(())
+        }
+    }
+
+    /// Fails writes and reads issued in the wrong direction
+    struct CheckedInterface {
+        mock: MockInterface,
+        transmitting: Rc<RefCell<bool>>,
+    }
+
+    impl Interface for CheckedInterface {
+        fn set_baud_rate(&mut self, b: BaudRate) -> Result<(), CommunicationError> {
+            self.mock.set_baud_rate(b)
+        }
+
+        fn flush(&mut self) {
+            self.mock.flush()
+        }
+
+        fn read(&mut self, data: &mut [u8]) -> Result<(), CommunicationError> {
+            assert!(!*self.transmitting.borrow(), "read while transmitting");
+            self.mock.read(data)
+        }
+
+        fn read_with_timeout(&mut self, data: &mut [u8], timeout: Duration) -> Result<(), CommunicationError> {
+            assert!(!*self.transmitting.borrow(), "read while transmitting");
+            self.mock.read_with_timeout(data, timeout)
+        }
+
+        fn write(&mut self, data: &[u8]) -> Result<(), CommunicationError> {
+            assert!(*self.transmitting.borrow(), "write while receiving");
+            self.mock.write(data)
+        }
+    }
+
+    fn checked(mock: MockInterface, transceiver: &Transceiver) -> CheckedInterface {
+        CheckedInterface{mock, transmitting: transceiver.transmitting.clone()}
+    }
+
+    #[test]
+    fn direction() {
+        let transceiver = Transceiver::default();
+        let mut mock = MockInterface::new();
+        mock.queue_response(&protocol1_status(1, 0, &[0x00, 0x02]));
+        let mut interface = HalfDuplex::new(checked(mock, &transceiver), transceiver.clone()).unwrap();
+
+        let mut servo = crate::dynamixel::ax12::AX12::new(crate::protocol1::ServoID::new(1), BaudRate::Baud1000000);
+        assert_eq!(servo.get_position(&mut interface), Ok(Radians(0.0)));
+        assert_eq!(*transceiver.switches.borrow(), vec![false, true, false]);
+        let (interface, _) = interface.free();
+        interface.mock.assert_sent_exactly(&[&protocol1_packet(1, 0x02, &[36, 2])]);
+    }
+
+    #[test]
+    fn protocol2_direction() {
+        let transceiver = Transceiver::default();
+        let mut mock = MockInterface::new();
+        mock.queue_response(&protocol2_status(1, 0, &[0x00, 0x08, 0x00, 0x00]));
+        mock.queue_response(&protocol2_status(1, 0, &[]));
+        let mut interface = HalfDuplex::new(checked(mock, &transceiver), transceiver.clone()).unwrap().with_transmit_wait();
+
+        // Every packet is transmitted with one switch to transmitting and back
+        let mut servo = crate::protocol2::generic::GenericServo::new(crate::protocol2::ServoID::new(1), BaudRate::Baud57600);
+        assert_eq!(servo.get_position(&mut interface), Ok(Radians(0.0)));
+        servo.set_enable_torque(&mut interface, true).unwrap();
+        assert_eq!(*transceiver.switches.borrow(), vec![false, true, false, true, false]);
+        let (interface, _) = interface.free();
+        interface.mock.assert_sent_exactly(&[
+            &protocol2_packet(1, 0x02, &[132, 0, 4, 0]),
+            &protocol2_packet(1, 0x03, &[64, 0, 1]),
+        ]);
+    }
+
+    #[test]
+    fn echo() {
+        let request = protocol1_packet(1, 0x02, &[36, 2]);
+        let mut mock = MockInterface::new();
+        mock.queue_response(&request);
+        mock.queue_response(&protocol1_status(1, 0, &[0x00, 0x02]));
+        let mut interface = HalfDuplex::new(mock, ()).unwrap().with_echo();
+
+        let mut servo = crate::dynamixel::ax12::AX12::new(crate::protocol1::ServoID::new(1), BaudRate::Baud1000000);
+        assert_eq!(servo.get_position(&mut interface), Ok(Radians(0.0)));
+        interface.interface().assert_all_read();
+
+        // A collision corrupts the echo
+        let mut corrupted = request.clone();
+        corrupted[4] ^= 0x10;
+        interface.interface_mut().queue_response(&corrupted);
+        assert_eq!(interface.write(&request), Err(CommunicationError::Other));
+
+        // A missing echo times out
+        assert_eq!(interface.write(&request), Err(CommunicationError::TimedOut));
+    }
+
+    #[test]
+    fn transmit_wait() {
+        assert_eq!(transmit_time(BaudRate::Baud1000000, 8), Duration::from_micros(80));
+        assert_eq!(transmit_time(BaudRate::Baud57600, 576), Duration::from_millis(100));
+
+        let mut interface = HalfDuplex::new(MockInterface::new(), ()).unwrap().with_transmit_wait();
+        interface.set_baud_rate(BaudRate::Baud57600).unwrap();
+        let start = std::time::Instant::now();
+        interface.write(&[0xff; 58]).unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(10));
+    }
+}
@@ -0,0 +1,342 @@
+//! Calibrated joints
+//!
+//! Servos are often mounted mirrored, rotated or behind gearing, so the position of the servo is not the position of the joint.
+//! A `Joint` wraps a servo and converts between the two using a `JointConfig`.
+//! Since `Joint` implements `Servo` itself, it can be used anywhere a servo can, including wrapping the boxed servo returned by `connect`.
+
+use crate::units::{Amperes, Percent, Radians, RadiansPerSecond};
+use crate::{Error, Interface, LimitPolicy, OperatingMode, Servo};
+
+/// Calibration of a joint
+///
+/// The servo position is `offset + gear_ratio * joint_position`, negated if `inverted` is set.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct JointConfig {
+    /// The servo position when the joint is at 0 radians
+    pub offset: Radians,
+
+    /// Set if the joint moves in the opposite direction of the servo
+    pub inverted: bool,
+
+    /// Servo revolutions per joint revolution, must be finite and non-zero
+    pub gear_ratio: f32,
+
+    /// Soft limits `(min, max)` for the joint position, applied in addition to the position limits of the servo
+    pub limits: Option<(Radians, Radians)>,
+}
+
+impl Default for JointConfig {
+    fn default() -> Self {
+        JointConfig{
+            offset: Radians(0.0),
+            inverted: false,
+            gear_ratio: 1.0,
+            limits: None,
+        }
+    }
+}
+
+impl JointConfig {
+    /// Servo units per joint unit, including direction
+    fn scale(&self) -> f32 {
+        if self.inverted {
+            -self.gear_ratio
+        } else {
+            self.gear_ratio
+        }
+    }
+
+    /// Convert a joint position to a servo position
+    pub fn to_servo(&self, position: Radians) -> Radians {
+        position * self.scale() + self.offset
+    }
+
+    /// Convert a servo position to a joint position
+    pub fn to_joint(&self, position: Radians) -> Radians {
+        (position - self.offset) / self.scale()
+    }
+}
+
+/// A servo with calibration applied
+pub struct Joint<S> {
+    servo: S,
+    config: JointConfig,
+}
+
+impl<S> Joint<S> {
+    /// Wrap `servo` in a joint calibrated by `config`
+    ///
+    /// Returns `Error::InvalidValue` if the gear ratio is zero or not finite, or if the soft limits are not ordered.
+    pub fn new(servo: S, config: JointConfig) -> Result<Self, Error> {
+        if !config.gear_ratio.is_finite() || config.gear_ratio == 0.0 {
+            return Err(Error::InvalidValue);
+        }
+        if let Some((min, max)) = config.limits {
+            if min.value().is_nan() || max.value().is_nan() || min > max {
+                return Err(Error::InvalidValue);
+            }
+        }
+        Ok(Joint{servo, config})
+    }
+
+    pub fn config(&self) -> &JointConfig {
+        &self.config
+    }
+
+    pub fn servo(&self) -> &S {
+        &self.servo
+    }
+
+    pub fn servo_mut(&mut self) -> &mut S {
+        &mut self.servo
+    }
+
+    /// Unwrap the servo, removing the calibration
+    pub fn into_inner(self) -> S {
+        self.servo
+    }
+}
+
+/// Apply `policy` to a goal position outside the soft limits `(min, max)`
+fn apply_soft_limits(policy: LimitPolicy, goal: Radians, (min, max): (Radians, Radians)) -> Result<Radians, Error> {
+    if goal >= min && goal <= max {
+        return Ok(goal);
+    }
+
+    match policy {
+        LimitPolicy::Reject => Err(Error::SoftLimit),
+        LimitPolicy::Clamp => Ok(if goal < min { min } else { max }),
+        LimitPolicy::Wrap => {
+            let revolution = 2.0 * crate::lib::f32::consts::PI;
+            let offset = goal.value() - min.value();
+            let mut wrapped = offset - revolution * ((offset / revolution) as i32) as f32;
+            if wrapped < 0.0 {
+                wrapped += revolution;
+            }
+            let wrapped = min + Radians(wrapped);
+            if wrapped <= max {
+                Ok(wrapped)
+            } else {
+                Err(Error::SoftLimit)
+            }
+        },
+    }
+}
+
+impl<I: Interface, S: Servo<I>> Servo<I> for Joint<S> {
+    fn set_enable_torque(&mut self, interface: &mut I, enable_torque: bool) -> Result<(), Error> {
+        self.servo.set_enable_torque(interface, enable_torque)
+    }
+
+    fn set_position(&mut self, interface: &mut I, value: Radians) -> Result<(), Error> {
+        let goal = match self.config.limits {
+            Some(limits) => apply_soft_limits(self.servo.get_limit_policy(), value, limits)?,
+            None => value,
+        };
+        self.servo.set_position(interface, self.config.to_servo(goal))
+    }
+
+    fn get_position(&mut self, interface: &mut I) -> Result<Radians, Error> {
+        Ok(self.config.to_joint(self.servo.get_position(interface)?))
+    }
+
+    fn set_goal_velocity(&mut self, interface: &mut I, value: RadiansPerSecond) -> Result<(), Error> {
+        self.servo.set_goal_velocity(interface, value * self.config.scale())
+    }
+
+    fn get_velocity(&mut self, interface: &mut I) -> Result<RadiansPerSecond, Error> {
+        Ok(self.servo.get_velocity(interface)? / self.config.scale())
+    }
+
+    fn get_load(&mut self, interface: &mut I) -> Result<Percent, Error> {
+        let load = self.servo.get_load(interface)?;
+        Ok(if self.config.inverted { -load } else { load })
+    }
+
+    fn get_current(&mut self, interface: &mut I) -> Result<Amperes, Error> {
+        self.servo.get_current(interface)
+    }
+
+    fn get_temperature(&mut self, interface: &mut I) -> Result<f32, Error> {
+        self.servo.get_temperature(interface)
+    }
+
+    fn get_voltage(&mut self, interface: &mut I) -> Result<f32, Error> {
+        self.servo.get_voltage(interface)
+    }
+
+    fn set_operating_mode(&mut self, interface: &mut I, mode: OperatingMode) -> Result<(), Error> {
+        self.servo.set_operating_mode(interface, mode)
+    }
+
+    fn get_operating_mode(&mut self, interface: &mut I) -> Result<OperatingMode, Error> {
+        self.servo.get_operating_mode(interface)
+    }
+
+    fn set_limit_policy(&mut self, policy: LimitPolicy) {
+        self.servo.set_limit_policy(policy)
+    }
+
+    fn get_limit_policy(&self) -> LimitPolicy {
+        self.servo.get_limit_policy()
+    }
+
+    fn set_timeouts(&mut self, timeouts: Option<crate::Timeouts>) {
+        self.servo.set_timeouts(timeouts)
+    }
+
+    fn assume_status_return_level(&mut self, level: crate::StatusReturnLevel) {
+        self.servo.assume_status_return_level(level)
+    }
+
+    fn read_status_return_level(&mut self, interface: &mut I) -> Result<crate::StatusReturnLevel, Error> {
+        self.servo.read_status_return_level(interface)
+    }
+
+    fn get_return_delay_time(&mut self, interface: &mut I) -> Result<crate::lib::time::Duration, Error> {
+        self.servo.get_return_delay_time(interface)
+    }
+
+    fn set_return_delay_time(&mut self, interface: &mut I, delay: crate::lib::time::Duration) -> Result<(), Error> {
+        self.servo.set_return_delay_time(interface, delay)
+    }
+
+    fn hardware_error(&mut self, interface: &mut I) -> Result<crate::protocol2::HardwareError, Error> {
+        self.servo.hardware_error(interface)
+    }
+
+    /// The position limits of the servo in joint positions, narrowed by the soft limits
+    fn get_position_limits(&mut self, interface: &mut I) -> Result<(Radians, Radians), Error> {
+        let (servo_min, servo_max) = self.servo.get_position_limits(interface)?;
+        let (a, b) = (self.config.to_joint(servo_min), self.config.to_joint(servo_max));
+        let (mut min, mut max) = if a <= b { (a, b) } else { (b, a) };
+        if let Some((soft_min, soft_max)) = self.config.limits {
+            if soft_min > min {
+                min = soft_min;
+            }
+            if soft_max < max {
+                max = soft_max;
+            }
+        }
+        Ok((min, max))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::joint::*;
+
+    struct NoInterface;
+
+    impl Interface for NoInterface {
+        fn set_baud_rate(&mut self, _b: crate::BaudRate) -> Result<(), crate::CommunicationError> {
+            unreachable!()
+        }
+
+        fn flush(&mut self) {
+            unreachable!()
+        }
+
+        fn read(&mut self, _data: &mut [u8]) -> Result<(), crate::CommunicationError> {
+            unreachable!()
+        }
+
+        fn write(&mut self, _data: &[u8]) -> Result<(), crate::CommunicationError> {
+            unreachable!()
+        }
+    }
+
+    /// A servo that moves instantly, with limits of +-2 radians
+    struct FakeServo {
+        position: Radians,
+        policy: LimitPolicy,
+    }
+
+    impl Servo<NoInterface> for FakeServo {
+        fn set_enable_torque(&mut self, _interface: &mut NoInterface, _enable_torque: bool) -> Result<(), Error> {
+            Ok(())
+        }
+
+        fn set_position(&mut self, _interface: &mut NoInterface, value: Radians) -> Result<(), Error> {
+            self.position = value;
+            Ok(())
+        }
+
+        fn get_position(&mut self, _interface: &mut NoInterface) -> Result<Radians, Error> {
+            Ok(self.position)
+        }
+
+        fn set_operating_mode(&mut self, _interface: &mut NoInterface, _mode: OperatingMode) -> Result<(), Error> {
+            Ok(())
+        }
+
+        fn get_operating_mode(&mut self, _interface: &mut NoInterface) -> Result<OperatingMode, Error> {
+            Ok(OperatingMode::Position)
+        }
+
+        fn set_limit_policy(&mut self, policy: LimitPolicy) {
+            self.policy = policy;
+        }
+
+        fn get_limit_policy(&self) -> LimitPolicy {
+            self.policy
+        }
+
+        fn get_position_limits(&mut self, _interface: &mut NoInterface) -> Result<(Radians, Radians), Error> {
+            Ok((Radians(-2.0), Radians(2.0)))
+        }
+
+        fn set_timeouts(&mut self, _timeouts: Option<crate::Timeouts>) {
+        }
+
+        fn assume_status_return_level(&mut self, _level: crate::StatusReturnLevel) {
+        }
+
+        fn read_status_return_level(&mut self, _interface: &mut NoInterface) -> Result<crate::StatusReturnLevel, Error> {
+            Ok(crate::StatusReturnLevel::All)
+        }
+
+        fn get_return_delay_time(&mut self, _interface: &mut NoInterface) -> Result<crate::lib::time::Duration, Error> {
+            Ok(crate::lib::time::Duration::from_micros(500))
+        }
+
+        fn set_return_delay_time(&mut self, _interface: &mut NoInterface, _delay: crate::lib::time::Duration) -> Result<(), Error> {
+            Ok(())
+        }
+
+        fn hardware_error(&mut self, _interface: &mut NoInterface) -> Result<crate::protocol2::HardwareError, Error> {
+            Ok(crate::protocol2::HardwareError::empty())
+        }
+    }
+
+    fn joint(config: JointConfig) -> Joint<FakeServo> {
+        Joint::new(FakeServo{position: Radians(0.0), policy: LimitPolicy::Reject}, config).unwrap()
+    }
+
+    #[test]
+    fn calibration() {
+        let mut joint = joint(JointConfig{offset: Radians(0.5), inverted: true, gear_ratio: 2.0, limits: None});
+        joint.set_position(&mut NoInterface, Radians(0.25)).unwrap();
+        assert_eq!(joint.servo().position, Radians(0.0));
+        assert_eq!(joint.get_position(&mut NoInterface).unwrap(), Radians(0.25));
+        assert_eq!(joint.get_position_limits(&mut NoInterface).unwrap(), (Radians(-0.75), Radians(1.25)));
+    }
+
+    #[test]
+    fn soft_limits() {
+        let mut joint = joint(JointConfig{limits: Some((Radians(-1.0), Radians(1.0))), ..JointConfig::default()});
+        assert_eq!(joint.set_position(&mut NoInterface, Radians(1.5)), Err(Error::SoftLimit));
+        assert_eq!(joint.get_position_limits(&mut NoInterface).unwrap(), (Radians(-1.0), Radians(1.0)));
+
+        joint.set_limit_policy(LimitPolicy::Clamp);
+        joint.set_position(&mut NoInterface, Radians(1.5)).unwrap();
+        assert_eq!(joint.servo().position, Radians(1.0));
+    }
+
+    #[test]
+    fn invalid_config() {
+        let servo = || FakeServo{position: Radians(0.0), policy: LimitPolicy::Reject};
+        assert!(Joint::new(servo(), JointConfig{gear_ratio: 0.0, ..JointConfig::default()}).is_err());
+        assert!(Joint::new(servo(), JointConfig{limits: Some((Radians(1.0), Radians(-1.0))), ..JointConfig::default()}).is_err());
+    }
+}
@@ -7,10 +7,6 @@ mod lib {
     pub use core::*;
 }
 
-use log::warn;
-
-use bit_field;
-
 #[cfg(feature="serialport")]
 extern crate serialport;
 
@@ -21,9 +17,37 @@ pub mod protocol1;
 pub mod protocol2;
 pub mod pro;
 pub mod dynamixel;
+pub mod registry;
+pub mod units;
+pub mod joint;
+pub mod half_duplex;
+#[cfg(feature="std")]
+pub mod mock;
+#[cfg(feature="std")]
+pub mod sim;
+#[cfg(feature="std")]
+pub mod sniffer;
+#[cfg(feature="std")]
+pub mod recording;
+#[cfg(feature="std")]
+pub mod bus;
+#[cfg(feature="async")]
+pub mod asynchronous;
+mod limits;
+mod tracked_baud_rate;
+mod reply_timing;
+
+pub use crate::tracked_baud_rate::TrackedBaudRate;
+pub use crate::reply_timing::ReplyTiming;
 
 #[cfg(feature="serialport")]
 mod serial_impl;
+#[cfg(feature="serialport")]
+pub use crate::serial_impl::SerialPortInterface;
+#[cfg(feature="embedded-hal")]
+mod embedded_impl;
+#[cfg(feature="embedded-hal")]
+pub use crate::embedded_impl::EmbeddedInterface;
 
 /// The generic servo trait
 ///
@@ -33,40 +57,447 @@ pub trait Servo<I: Interface> {
     /// Enable/Disable torque on the servo.
     fn set_enable_torque(&mut self, interface: &mut I, enable_torque: bool) -> Result<(), Error>;
 
-    /// Set the goal position. Some servos will be put into "position control mode" when this method is called.
+    /// Set the goal position.
     ///
-    /// value is in unit: radians
-    fn set_position(&mut self, interface: &mut I, value: f32) -> Result<(), Error>;
+    /// The servo is not put into position control mode by this method, use `set_operating_mode` for that.
+    ///
+    /// Positions are relative to the center of the servo's range.
+    /// Goals outside the position limits are handled according to the limit policy, see `set_limit_policy`.
+    fn set_position(&mut self, interface: &mut I, value: units::Radians) -> Result<(), Error>;
     
     /// Get the current position.
     ///
-    /// The result is returned in unit: radians 
-    fn get_position(&mut self, interface: &mut I) -> Result<f32, Error>;
+    /// Positions are relative to the center of the servo's range.
+    fn get_position(&mut self, interface: &mut I) -> Result<units::Radians, Error>;
+
+    /// Set the goal velocity.
+    ///
+    /// For servos in velocity (wheel) mode this is the velocity the servo will rotate with.
+    /// For servos in position mode this is, for some servos, the velocity used when moving towards the goal position.
+    fn set_goal_velocity(&mut self, _interface: &mut I, _value: units::RadiansPerSecond) -> Result<(), Error> {
+        Err(Error::Unsupported(Capability::GoalVelocity))
+    }
+
+    /// Get the current velocity.
+    fn get_velocity(&mut self, _interface: &mut I) -> Result<units::RadiansPerSecond, Error> {
+        Err(Error::Unsupported(Capability::Velocity))
+    }
+
+    /// Get the current load.
+    ///
+    /// The result is given in percent of the maximum torque, negative values for clockwise load.
+    fn get_load(&mut self, _interface: &mut I) -> Result<units::Percent, Error> {
+        Err(Error::Unsupported(Capability::Load))
+    }
+
+    /// Get the current drawn by the motor.
+    fn get_current(&mut self, _interface: &mut I) -> Result<units::Amperes, Error> {
+        Err(Error::Unsupported(Capability::Current))
+    }
+
+    /// Get the internal temperature.
+    ///
+    /// The result is returned in unit: degrees Celsius
+    fn get_temperature(&mut self, _interface: &mut I) -> Result<f32, Error> {
+        Err(Error::Unsupported(Capability::Temperature))
+    }
+
+    /// Get the input voltage.
+    ///
+    /// The result is returned in unit: volts
+    fn get_voltage(&mut self, _interface: &mut I) -> Result<f32, Error> {
+        Err(Error::Unsupported(Capability::Voltage))
+    }
+
+    /// Set the operating mode.
+    ///
+    /// Servos that only accept a new operating mode while torque is disabled will have torque disabled while changing mode,
+    /// and then restored to the previous state.
+    /// Returns `Error::Unsupported` if the servo doesn't support `mode`.
+    fn set_operating_mode(&mut self, interface: &mut I, mode: OperatingMode) -> Result<(), Error>;
+
+    /// Get the current operating mode.
+    fn get_operating_mode(&mut self, interface: &mut I) -> Result<OperatingMode, Error>;
+
+    /// Set how `set_position` handles goals outside the position limits.
+    ///
+    /// The default policy is `LimitPolicy::Reject`. Servos without position limits ignore the policy.
+    fn set_limit_policy(&mut self, _policy: LimitPolicy) {}
+
+    /// Get how `set_position` handles goals outside the position limits.
+    fn get_limit_policy(&self) -> LimitPolicy {
+        LimitPolicy::Reject
+    }
+
+    /// Get the position limits as `(min, max)`.
+    ///
+    /// The limits are read from the servo's angle limit (or position limit) registers the first time they're needed,
+    /// and cached until the operating mode is changed with `set_operating_mode`.
+    /// In multi-turn mode the limits are the range of the goal position register.
+    fn get_position_limits(&mut self, _interface: &mut I) -> Result<(units::Radians, units::Radians), Error> {
+        Err(Error::Unsupported(Capability::PositionLimits))
+    }
+
+    /// Set the timeouts used when communicating with this servo.
+    ///
+    /// `None` uses the timeouts of the interface, which is the default. Servos without their own timeouts ignore this.
+    fn set_timeouts(&mut self, _timeouts: Option<Timeouts>) {}
+
+    /// Assume the servo has status return level `level`, without communicating with it.
+    ///
+    /// Writes only wait for a status when the level is `StatusReturnLevel::All`, which is assumed by default.
+    /// Reads fail with `CommunicationError::NoStatus`, without sending anything, when the level is `StatusReturnLevel::Ping`.
+    fn assume_status_return_level(&mut self, _level: StatusReturnLevel) {}
+
+    /// Read the status return level from the servo, and assume it from now on.
+    ///
+    /// A servo not answering reads is pinged, and has `StatusReturnLevel::Ping` if it answers.
+    fn read_status_return_level(&mut self, _interface: &mut I) -> Result<StatusReturnLevel, Error> {
+        Err(Error::Unsupported(Capability::StatusReturnLevel))
+    }
+
+    /// Get the return delay time, the time the servo waits before replying.
+    ///
+    /// The value is read from the servo the first time, and cached afterwards.
+    fn get_return_delay_time(&mut self, _interface: &mut I) -> Result<lib::time::Duration, Error> {
+        Err(Error::Unsupported(Capability::ReturnDelayTime))
+    }
+
+    /// Set the return delay time, rounded down to a multiple of 2 µs.
+    ///
+    /// At most 508 µs can be set, larger values give `Error::InvalidValue`. Zero gives the highest throughput.
+    fn set_return_delay_time(&mut self, _interface: &mut I, _delay: lib::time::Duration) -> Result<(), Error> {
+        Err(Error::Unsupported(Capability::ReturnDelayTime))
+    }
+
+    /// Read the hardware errors of the servo, telling why it raised its alert bit or shut down.
+    ///
+    /// Protocol 1 servos report hardware errors in the error field of every status instead,
+    /// and return `Error::Unsupported`.
+    fn hardware_error(&mut self, _interface: &mut I) -> Result<protocol2::HardwareError, Error> {
+        Err(Error::Unsupported(Capability::HardwareError))
+    }
 }
 
+#[cfg(feature="std")]
+impl<I: Interface, S: Servo<I> + ?Sized> Servo<I> for Box<S> {
+    fn set_enable_torque(&mut self, interface: &mut I, enable_torque: bool) -> Result<(), Error> {
+        (**self).set_enable_torque(interface, enable_torque)
+    }
+
+    fn set_position(&mut self, interface: &mut I, value: units::Radians) -> Result<(), Error> {
+        (**self).set_position(interface, value)
+    }
+
+    fn get_position(&mut self, interface: &mut I) -> Result<units::Radians, Error> {
+        (**self).get_position(interface)
+    }
+
+    fn set_goal_velocity(&mut self, interface: &mut I, value: units::RadiansPerSecond) -> Result<(), Error> {
+        (**self).set_goal_velocity(interface, value)
+    }
+
+    fn get_velocity(&mut self, interface: &mut I) -> Result<units::RadiansPerSecond, Error> {
+        (**self).get_velocity(interface)
+    }
+
+    fn get_load(&mut self, interface: &mut I) -> Result<units::Percent, Error> {
+        (**self).get_load(interface)
+    }
+
+    fn get_current(&mut self, interface: &mut I) -> Result<units::Amperes, Error> {
+        (**self).get_current(interface)
+    }
+
+    fn get_temperature(&mut self, interface: &mut I) -> Result<f32, Error> {
+        (**self).get_temperature(interface)
+    }
+
+    fn get_voltage(&mut self, interface: &mut I) -> Result<f32, Error> {
+        (**self).get_voltage(interface)
+    }
 
-/// An Error originating from an `Interface`
+    fn set_operating_mode(&mut self, interface: &mut I, mode: OperatingMode) -> Result<(), Error> {
+        (**self).set_operating_mode(interface, mode)
+    }
+
+    fn get_operating_mode(&mut self, interface: &mut I) -> Result<OperatingMode, Error> {
+        (**self).get_operating_mode(interface)
+    }
+
+    fn set_limit_policy(&mut self, policy: LimitPolicy) {
+        (**self).set_limit_policy(policy)
+    }
+
+    fn get_limit_policy(&self) -> LimitPolicy {
+        (**self).get_limit_policy()
+    }
+
+    fn get_position_limits(&mut self, interface: &mut I) -> Result<(units::Radians, units::Radians), Error> {
+        (**self).get_position_limits(interface)
+    }
+
+    fn set_timeouts(&mut self, timeouts: Option<Timeouts>) {
+        (**self).set_timeouts(timeouts)
+    }
+
+    fn assume_status_return_level(&mut self, level: StatusReturnLevel) {
+        (**self).assume_status_return_level(level)
+    }
+
+    fn read_status_return_level(&mut self, interface: &mut I) -> Result<StatusReturnLevel, Error> {
+        (**self).read_status_return_level(interface)
+    }
+
+    fn get_return_delay_time(&mut self, interface: &mut I) -> Result<lib::time::Duration, Error> {
+        (**self).get_return_delay_time(interface)
+    }
+
+    fn set_return_delay_time(&mut self, interface: &mut I, delay: lib::time::Duration) -> Result<(), Error> {
+        (**self).set_return_delay_time(interface, delay)
+    }
+
+    fn hardware_error(&mut self, interface: &mut I) -> Result<protocol2::HardwareError, Error> {
+        (**self).hardware_error(interface)
+    }
+}
+
+/// The operating modes a servo can be in
+///
+/// Not every servo support every mode, setting an unsupported mode returns `Error::Unsupported`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum OperatingMode {
+    /// Control position within a single revolution (or the angle limits).
+    Position,
+
+    /// Control position over multiple revolutions, also called multi-turn mode.
+    ExtendedPosition,
+
+    /// Control velocity. Protocol 1 servos call this wheel mode, and enter it by setting both angle limits to zero.
+    Velocity,
+
+    /// Control current, and thus torque.
+    Current,
+
+    /// Control position, with the current limited by the goal current.
+    CurrentBasedPosition,
+
+    /// Control the PWM duty cycle directly.
+    Pwm,
+}
+
+impl lib::fmt::Display for OperatingMode {
+    fn fmt(&self, f: &mut lib::fmt::Formatter) -> lib::fmt::Result {
+        match self {
+            OperatingMode::Position => write!(f, "position"),
+            OperatingMode::ExtendedPosition => write!(f, "extended position"),
+            OperatingMode::Velocity => write!(f, "velocity"),
+            OperatingMode::Current => write!(f, "current"),
+            OperatingMode::CurrentBasedPosition => write!(f, "current based position"),
+            OperatingMode::Pwm => write!(f, "PWM"),
+        }
+    }
+}
+
+/// How goal positions outside the position limits are handled
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum LimitPolicy {
+    /// Return `Error::OutOfRange` without moving the servo.
+    Reject,
+
+    /// Move to the closest limit.
+    Clamp,
+
+    /// Add or subtract whole revolutions to bring the goal within the limits, returning `Error::OutOfRange` if that's not possible.
+    Wrap,
+}
+
+/// Functionality of the `Servo` trait that not all servos support
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Capability {
+    GoalVelocity,
+    Velocity,
+    Load,
+    Current,
+    Temperature,
+    Voltage,
+    OperatingMode(OperatingMode),
+    PositionLimits,
+    StatusReturnLevel,
+    ReturnDelayTime,
+    HardwareError,
+}
+
+impl lib::fmt::Display for Capability {
+    fn fmt(&self, f: &mut lib::fmt::Formatter) -> lib::fmt::Result {
+        match self {
+            Capability::GoalVelocity => write!(f, "goal velocity"),
+            Capability::Velocity => write!(f, "velocity"),
+            Capability::Load => write!(f, "load"),
+            Capability::Current => write!(f, "current"),
+            Capability::Temperature => write!(f, "temperature"),
+            Capability::Voltage => write!(f, "voltage"),
+            Capability::OperatingMode(mode) => write!(f, "{} mode", mode),
+            Capability::PositionLimits => write!(f, "position limits"),
+            Capability::StatusReturnLevel => write!(f, "status return level"),
+            Capability::ReturnDelayTime => write!(f, "return delay time"),
+            Capability::HardwareError => write!(f, "hardware error status"),
+        }
+    }
+}
+
+
+
+/// An Error originating from an `Interface`, or from waiting for a status that won't be sent
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum CommunicationError {
     TimedOut,
     UnsupportedBaud(BaudRate),
     Other,
+
+    /// The servo is assumed not to answer the instruction at its `StatusReturnLevel`, so nothing was sent
+    NoStatus,
+}
+
+impl lib::fmt::Display for CommunicationError {
+    fn fmt(&self, f: &mut lib::fmt::Formatter) -> lib::fmt::Result {
+        match self {
+            CommunicationError::TimedOut => write!(f, "timed out"),
+            CommunicationError::UnsupportedBaud(b) => write!(f, "baud rate {} is not supported by the interface", u32::from(*b)),
+            CommunicationError::Other => write!(f, "the interface failed"),
+            CommunicationError::NoStatus => write!(f, "the servo does not answer the instruction at its status return level"),
+        }
+    }
 }
 
+#[cfg(feature="std")]
+impl std::error::Error for CommunicationError {}
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum Error {
     Unfinished,
     
-    /// An Error originating from the `Interface`
-    Communication(CommunicationError),
-    Format,
-    Processing,
+    /// An Error originating from the `Interface`, communicating with the servo with ID `id` if it came from a servo method
+    Communication {
+        id: Option<u8>,
+        error: CommunicationError,
+    },
+
+    /// A status could not be decoded, or held a value without meaning, from the servo with ID `id` if it came from a servo method
+    Format {
+        id: Option<u8>,
+        error: FormatError,
+    },
+
+    /// The servo with ID `id` reported an error processing the instruction
+    Processing {
+        id: u8,
+        error: ProcessingError,
+
+        /// Whether a protocol 2 servo also set the alert bit, meaning it has a hardware error. Always false for protocol 1.
+        alert: bool,
+    },
+
+    /// The model number is not recognized, and no servo type could be chosen
+    UnknownModel {
+        model_number: u16,
+        fw_version: u8,
+    },
+
+    /// The servo does not support the requested functionality
+    Unsupported(Capability),
+
+    /// A value could not be converted to a register value, e.g. because it was NaN or too large
+    InvalidValue,
+
+    /// The goal position is outside the position limits, all values are in raw position steps
+    OutOfRange {
+        goal: i32,
+        min: i32,
+        max: i32,
+    },
+
+    /// The goal position is outside the soft limits of a `joint::Joint`
+    SoftLimit,
+}
+
+impl lib::fmt::Display for Error {
+    fn fmt(&self, f: &mut lib::fmt::Formatter) -> lib::fmt::Result {
+        match self {
+            Error::Unfinished => write!(f, "the status was not finished"),
+            Error::Communication{id: Some(id), ..} => write!(f, "communication with servo {} failed", id),
+            Error::Communication{id: None, ..} => write!(f, "communication with the servo failed"),
+            Error::Format{id: Some(id), ..} => write!(f, "invalid status from servo {}", id),
+            Error::Format{id: None, ..} => write!(f, "invalid status"),
+            Error::Processing{id, alert: false, ..} => write!(f, "servo {} reported an error", id),
+            Error::Processing{id, alert: true, ..} => write!(f, "servo {} reported an error and a hardware error", id),
+            Error::UnknownModel{model_number, fw_version} => write!(f, "unknown model {:#06x} (firmware version {})", model_number, fw_version),
+            Error::Unsupported(capability) => write!(f, "{} is not supported by the servo", capability),
+            Error::InvalidValue => write!(f, "the value can't be converted to a register value"),
+            Error::OutOfRange{goal, min, max} => write!(f, "goal position {} is outside the position limits {}..={}", goal, min, max),
+            Error::SoftLimit => write!(f, "goal position is outside the soft limits of the joint"),
+        }
+    }
 }
 
+#[cfg(feature="std")]
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Communication{error, ..} => Some(error),
+            Error::Format{error, ..} => Some(error),
+            Error::Processing{error, ..} => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// Why a status could not be decoded, the source of `Error::Format`
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum FormatError {
+    Protocol1(protocol1::FormatError),
+    Protocol2(protocol2::FormatError),
+
+    /// A register held a value without meaning, e.g. an unknown operating mode
+    RegisterValue,
+}
+
+impl lib::fmt::Display for FormatError {
+    fn fmt(&self, f: &mut lib::fmt::Formatter) -> lib::fmt::Result {
+        match self {
+            FormatError::Protocol1(e) => write!(f, "{}", e),
+            FormatError::Protocol2(e) => write!(f, "{}", e),
+            FormatError::RegisterValue => write!(f, "invalid register value"),
+        }
+    }
+}
+
+#[cfg(feature="std")]
+impl std::error::Error for FormatError {}
+
+/// The error a servo reported in its status, the source of `Error::Processing`
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ProcessingError {
+    Protocol1(protocol1::ProcessingError),
+    Protocol2(protocol2::ProcessingError),
+}
+
+impl lib::fmt::Display for ProcessingError {
+    fn fmt(&self, f: &mut lib::fmt::Formatter) -> lib::fmt::Result {
+        match self {
+            ProcessingError::Protocol1(e) => write!(f, "{}", e),
+            ProcessingError::Protocol2(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+#[cfg(feature="std")]
+impl std::error::Error for ProcessingError {}
+
 /// Baud rates the interface should support
 ///
 /// May be extended and must not be matched against exhaustively.
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
 pub enum BaudRate {
     /// Baud rate of 9600
     Baud9600,
@@ -133,6 +564,7 @@ impl From<BaudRate> for u32 {
 }
 
 impl BaudRate {
+    #[cfg(feature="std")]
     fn variants() -> &'static [Self] {
         &[BaudRate::Baud9600,
           BaudRate::Baud19200,
@@ -152,6 +584,84 @@ impl BaudRate {
     }
 }
 
+/// How long to wait for replies, for the different kinds of operations
+///
+/// Every timeout is the time between bytes before a read times out, including the time before the first byte.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Timeouts {
+    /// Waiting for the status of a read, or a write to RAM.
+    pub status: lib::time::Duration,
+
+    /// Waiting for pongs, when pinging or enumerating.
+    ///
+    /// A short timeout makes enumerating faster, since every baud rate waits this long for servos that might not exist.
+    pub ping: lib::time::Duration,
+
+    /// Waiting for the status of a write to EEPROM, which can take longer since the servo has to store the value.
+    pub eeprom_write: lib::time::Duration,
+}
+
+impl Default for Timeouts {
+    /// 100 ms for every operation
+    fn default() -> Self {
+        Timeouts{
+            status: lib::time::Duration::from_millis(100),
+            ping: lib::time::Duration::from_millis(100),
+            eeprom_write: lib::time::Duration::from_millis(100),
+        }
+    }
+}
+
+/// The instructions a servo answers with a status, configured by its Status Return Level register
+///
+/// Broadcast instructions are never answered.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum StatusReturnLevel {
+    /// Only ping instructions are answered.
+    Ping,
+
+    /// Ping and read instructions are answered.
+    Read,
+
+    /// Every instruction is answered, the factory default.
+    #[default]
+    All,
+}
+
+impl StatusReturnLevel {
+    /// The level for a value of the Status Return Level register
+    pub fn from_register(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(StatusReturnLevel::Ping),
+            1 => Some(StatusReturnLevel::Read),
+            2 => Some(StatusReturnLevel::All),
+            _ => None,
+        }
+    }
+
+    /// Whether writes are answered with a status
+    pub fn replies_to_writes(self) -> bool {
+        self == StatusReturnLevel::All
+    }
+
+    /// Whether reads are answered with a status
+    ///
+    /// Reading from a servo assumed not to answer reads gives `CommunicationError::NoStatus`, without sending anything.
+    pub fn replies_to_reads(self) -> bool {
+        self != StatusReturnLevel::Ping
+    }
+}
+
+impl From<StatusReturnLevel> for u8 {
+    fn from(level: StatusReturnLevel) -> u8 {
+        match level {
+            StatusReturnLevel::Ping => 0,
+            StatusReturnLevel::Read => 1,
+            StatusReturnLevel::All => 2,
+        }
+    }
+}
+
 /// The interface for communicating with dynamixel servos.
 pub trait Interface {
     /// Set the baud rate of the interface
@@ -173,10 +683,28 @@ pub trait Interface {
     ///
     /// If bytes are not received for a given time, a timeout should occur.
     /// A timeout is signaled by returning `Err(Error::Timeout)`.
-    /// The time between bytes before a timeout occur should be 100ms or more.
+    /// The time between bytes before a timeout occur should be the `status` timeout of `timeouts`.
     /// If the timeout is not implemented, a "dead" servo can cause the code to "freeze".
     fn read(&mut self, data: &mut [u8]) -> Result<(), CommunicationError>;
 
+    /// A blocking/spinning read with a custom timeout.
+    ///
+    /// Behaves like `read`, except that the time between bytes before a timeout occurs is given by `timeout`.
+    /// Servos and enumeration read through this function, with the timeout for the operation from `Timeouts`.
+    ///
+    /// The default implementation ignores `timeout` and calls `read`.
+    fn read_with_timeout(&mut self, data: &mut [u8], timeout: lib::time::Duration) -> Result<(), CommunicationError> {
+        let _ = timeout;
+        self.read(data)
+    }
+
+    /// The timeouts used by servos and enumeration on this interface, unless a servo has its own.
+    ///
+    /// The default implementation returns `Timeouts::default()`.
+    fn timeouts(&self) -> Timeouts {
+        Timeouts::default()
+    }
+
     /// A blocking/spinning write.
     ///
     /// This function should either:
@@ -206,6 +734,25 @@ pub enum ServoProtocol {
     Protocol2(protocol2::ServoInfo),
 }
 
+impl ServoProtocol {
+    /// Look up the model of the servo in the `registry`
+    pub fn model(&self) -> Option<registry::Model> {
+        match self {
+            ServoProtocol::Protocol1(si) => si.model(),
+            ServoProtocol::Protocol2(si) => si.model(),
+        }
+    }
+}
+
+impl lib::fmt::Display for ServoProtocol {
+    fn fmt(&self, f: &mut lib::fmt::Formatter) -> lib::fmt::Result {
+        match self {
+            ServoProtocol::Protocol1(si) => write!(f, "{} (protocol 1)", si),
+            ServoProtocol::Protocol2(si) => write!(f, "{} (protocol 2)", si),
+        }
+    }
+}
+
 /// Enumerate all servos connected to the interface
 ///
 /// This functions returns a Vec and thus requires the `std` feature.
@@ -216,28 +763,194 @@ pub fn enumerate<I: Interface>(interface: &mut I) -> Result<Vec<ServoProtocol>,
     let servos_protocol1 = protocol1::enumerate(interface)?;
     // let servos_protocol2 = protocol2::enumerate(interface)?;
 
-    servos.append(&mut servos_protocol1.into_iter().map(|x| ServoProtocol::Protocol1(x)).collect());
+    servos.append(&mut servos_protocol1.into_iter().map(ServoProtocol::Protocol1).collect());
     // servos.append(&mut servos_protocol2.into_iter().map(|x| ServoProtocol::Protocol2(x)).collect());
 
     Ok(servos)
 }
 
+/// Options for scanning a range of IDs by pinging them one at a time
+///
+/// Unlike `enumerate`, scanning never sends a broadcast ping and will not suffer from reply collisions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScanOptions {
+    /// The baud rate to scan at.
+    pub baud_rate: BaudRate,
+
+    /// The (inclusive) range of IDs to ping.
+    pub ids: lib::ops::RangeInclusive<u8>,
+
+    /// How long to wait for each ID to answer before moving on.
+    pub timeout: lib::time::Duration,
+
+    /// Stop scanning after this many servos have been found.
+    pub max_servos: Option<usize>,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        ScanOptions {
+            baud_rate: BaudRate::Baud1000000,
+            ids: 0..=253,
+            timeout: lib::time::Duration::from_millis(10),
+            max_servos: None,
+        }
+    }
+}
+
+/// Scan for protocol 1 servos by pinging every ID in a range
+///
+/// This functions returns a Vec and thus requires the `std` feature.
+#[cfg(feature="std")]
+pub fn scan<I: Interface>(interface: &mut I, options: &ScanOptions) -> Result<Vec<ServoProtocol>, CommunicationError> {
+    Ok(protocol1::scan(interface, options)?.into_iter().map(ServoProtocol::Protocol1).collect())
+}
+
 /// Connect genericly to a servo
 ///
 /// Only offers basic functionality. If you need more functionality use the connect method of the correct servo type instead.
+/// Returns `Error::UnknownModel` if the model number is not recognized, see `connect_or_generic` for a fallback.
 /// This functions returns a Boxed trait and this requires the `std` feature.
 #[cfg(feature="std")]
-pub fn connect<I: Interface + 'static>(interface: &mut I, info: ServoProtocol) -> Result<Box<dyn Servo<I>>, CommunicationError> {
+pub fn connect<I: Interface + 'static>(interface: &mut I, info: ServoProtocol) -> Result<Box<dyn Servo<I> + Send>, Error> {
     match info {
         ServoProtocol::Protocol1(si) => protocol1::connect(interface, si),
         ServoProtocol::Protocol2(si) => protocol2::connect(interface, si),
     }
 }
 
+/// Connect genericly to a servo, falling back to a generic servo type for unknown models
+///
+/// Servos with unknown model numbers are connected to as `protocol1::generic::GenericServo` or `protocol2::generic::GenericServo`.
+/// These use a control table shared by most servos of that protocol, and might not behave correctly for every model.
+/// This functions returns a Boxed trait and this requires the `std` feature.
+#[cfg(feature="std")]
+pub fn connect_or_generic<I: Interface + 'static>(interface: &mut I, info: ServoProtocol) -> Result<Box<dyn Servo<I> + Send>, Error> {
+    match connect(interface, info.clone()) {
+        Err(Error::UnknownModel{..}) => match info {
+            ServoProtocol::Protocol1(si) => Ok(protocol1::connect_generic(interface, si)),
+            ServoProtocol::Protocol2(si) => Ok(protocol2::connect_generic(interface, si)),
+        },
+        x => x,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
     fn it_works() {
         assert_eq!(2 + 2, 4);
     }
+
+    #[cfg(feature="std")]
+    struct UnusedInterface;
+
+    #[cfg(feature="std")]
+    impl crate::Interface for UnusedInterface {
+        fn set_baud_rate(&mut self, _b: crate::BaudRate) -> Result<(), crate::CommunicationError> {
+            unreachable!()
+        }
+
+        fn flush(&mut self) {
+            unreachable!()
+        }
+
+        fn read(&mut self, _data: &mut [u8]) -> Result<(), crate::CommunicationError> {
+            unreachable!()
+        }
+
+        fn write(&mut self, _data: &[u8]) -> Result<(), crate::CommunicationError> {
+            unreachable!()
+        }
+    }
+
+    #[cfg(feature="std")]
+    #[test]
+    fn connect_unknown_model() {
+        let info = crate::ServoProtocol::Protocol2(crate::protocol2::ServoInfo{
+            baud_rate: crate::BaudRate::Baud1000000,
+            model_number: 0x1234,
+            fw_version: 0x26,
+            id: crate::protocol2::ServoID::new(1),
+        });
+
+        match crate::connect(&mut UnusedInterface, info.clone()) {
+            Err(e) => assert_eq!(e, crate::Error::UnknownModel{model_number: 0x1234, fw_version: 0x26}),
+            Ok(_) => panic!("connected to unknown model"),
+        }
+        assert!(crate::connect_or_generic(&mut UnusedInterface, info).is_ok());
+    }
+
+    #[cfg(feature="std")]
+    #[test]
+    fn display_servo_info() {
+        let info = crate::ServoProtocol::Protocol2(crate::protocol2::ServoInfo{
+            baud_rate: crate::BaudRate::Baud1000000,
+            model_number: 1020,
+            fw_version: 0x26,
+            id: crate::protocol2::ServoID::new(1),
+        });
+
+        assert_eq!(format!("{}", info), "XM430-W350 with id: 1 at baud: 1000000 (firmware version: 38) (protocol 2)");
+        assert!(crate::connect(&mut UnusedInterface, info).is_ok());
+    }
+
+    /// A servo implementing only the required methods of `Servo`
+    #[cfg(feature="std")]
+    struct MinimalServo;
+
+    #[cfg(feature="std")]
+    impl crate::Servo<UnusedInterface> for MinimalServo {
+        fn set_enable_torque(&mut self, _interface: &mut UnusedInterface, _enable_torque: bool) -> Result<(), crate::Error> {
+            Ok(())
+        }
+
+        fn set_position(&mut self, _interface: &mut UnusedInterface, _value: crate::units::Radians) -> Result<(), crate::Error> {
+            Ok(())
+        }
+
+        fn get_position(&mut self, _interface: &mut UnusedInterface) -> Result<crate::units::Radians, crate::Error> {
+            Ok(crate::units::Radians(0.0))
+        }
+
+        fn set_operating_mode(&mut self, _interface: &mut UnusedInterface, mode: crate::OperatingMode) -> Result<(), crate::Error> {
+            Err(crate::Error::Unsupported(crate::Capability::OperatingMode(mode)))
+        }
+
+        fn get_operating_mode(&mut self, _interface: &mut UnusedInterface) -> Result<crate::OperatingMode, crate::Error> {
+            Ok(crate::OperatingMode::Position)
+        }
+    }
+
+    #[cfg(feature="std")]
+    #[test]
+    fn servo_defaults() {
+        use crate::{Capability, Error, Servo};
+
+        let mut servo = MinimalServo;
+        servo.set_limit_policy(crate::LimitPolicy::Clamp);
+        assert_eq!(servo.get_limit_policy(), crate::LimitPolicy::Reject);
+        servo.set_timeouts(None);
+        servo.assume_status_return_level(crate::StatusReturnLevel::Ping);
+
+        let interface = &mut UnusedInterface;
+        assert_eq!(servo.get_position_limits(interface), Err(Error::Unsupported(Capability::PositionLimits)));
+        assert_eq!(servo.read_status_return_level(interface), Err(Error::Unsupported(Capability::StatusReturnLevel)));
+        assert_eq!(servo.get_return_delay_time(interface), Err(Error::Unsupported(Capability::ReturnDelayTime)));
+        assert_eq!(servo.set_return_delay_time(interface, crate::lib::time::Duration::ZERO), Err(Error::Unsupported(Capability::ReturnDelayTime)));
+        assert_eq!(servo.hardware_error(interface), Err(Error::Unsupported(Capability::HardwareError)));
+    }
+
+    #[cfg(feature="std")]
+    #[test]
+    fn display_error() {
+        let error = crate::Error::Communication{id: Some(1), error: crate::CommunicationError::TimedOut};
+        assert_eq!(format!("{}", error), "communication with servo 1 failed");
+        let error = crate::Error::Format{id: None, error: crate::FormatError::RegisterValue};
+        assert_eq!(format!("{}", error), "invalid status");
+
+        let error = crate::Error::Unsupported(crate::Capability::OperatingMode(crate::OperatingMode::Velocity));
+        assert_eq!(format!("{}", error), "velocity mode is not supported by the servo");
+        assert_eq!(format!("{}", crate::Error::Unsupported(crate::Capability::Current)), "current is not supported by the servo");
+    }
 }
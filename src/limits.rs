@@ -0,0 +1,88 @@
+//! Range checking of goal positions
+//!
+//! Every servo keeps a `PositionLimits`, holding its `LimitPolicy` and the position limits read from the servo.
+//! The limits are read the first time they are needed, and cached until the operating mode is changed.
+
+use crate::units::{Encoder, Radians};
+use crate::{Error, LimitPolicy};
+
+/// The limit policy of a servo and its cached position limits, in raw position steps
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct PositionLimits {
+    pub(crate) policy: LimitPolicy,
+    pub(crate) cached: Option<(i32, i32)>,
+}
+
+impl PositionLimits {
+    pub(crate) const fn new() -> Self {
+        PositionLimits{
+            policy: LimitPolicy::Reject,
+            cached: None,
+        }
+    }
+
+    /// Convert `goal` to raw position steps, applying the limit policy against `limits`
+    pub(crate) fn apply(&self, encoder: &Encoder, goal: Radians, limits: (i32, i32)) -> Result<i32, Error> {
+        let (min, max) = limits;
+        let steps = encoder.to_steps(goal).ok_or(Error::InvalidValue)?;
+        let out_of_range = Error::OutOfRange{goal: steps, min, max};
+
+        match self.policy {
+            LimitPolicy::Reject => {
+                if steps < min || steps > max {
+                    Err(out_of_range)
+                } else {
+                    Ok(steps)
+                }
+            },
+            LimitPolicy::Clamp => Ok(steps.clamp(min, max)),
+            LimitPolicy::Wrap => {
+                let revolution = encoder.steps_per_revolution();
+                let offset = f64::from(steps) - f64::from(min);
+                let mut wrapped = offset - revolution * ((offset / revolution) as i64) as f64;
+                if wrapped < 0.0 {
+                    wrapped += revolution;
+                }
+                match crate::units::to_steps(f64::from(min) + wrapped) {
+                    Some(wrapped) if wrapped <= max => Ok(wrapped),
+                    _ => Err(out_of_range),
+                }
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::limits::*;
+
+    const ENCODER: Encoder = Encoder{resolution: 4096, zero_offset: 2048, range: Radians(2.0 * crate::lib::f32::consts::PI)};
+
+    fn limits(policy: LimitPolicy) -> PositionLimits {
+        PositionLimits{policy, cached: None}
+    }
+
+    #[test]
+    fn reject() {
+        let limits = limits(LimitPolicy::Reject);
+        assert_eq!(limits.apply(&ENCODER, Radians(0.0), (1024, 3072)), Ok(2048));
+        assert_eq!(limits.apply(&ENCODER, Radians(2.0), (1024, 3072)), Err(Error::OutOfRange{goal: 3352, min: 1024, max: 3072}));
+        assert_eq!(limits.apply(&ENCODER, Radians(f32::NAN), (1024, 3072)), Err(Error::InvalidValue));
+    }
+
+    #[test]
+    fn clamp() {
+        let limits = limits(LimitPolicy::Clamp);
+        assert_eq!(limits.apply(&ENCODER, Radians(2.0), (1024, 3072)), Ok(3072));
+        assert_eq!(limits.apply(&ENCODER, Radians(-2.0), (1024, 3072)), Ok(1024));
+    }
+
+    #[test]
+    fn wrap() {
+        let limits = limits(LimitPolicy::Wrap);
+        let turn = 2.0 * crate::lib::f32::consts::PI;
+        assert_eq!(limits.apply(&ENCODER, Radians(turn), (0, 4095)), Ok(2048));
+        assert_eq!(limits.apply(&ENCODER, Radians(-turn - 1.0), (0, 4095)), Ok(1396));
+        assert_eq!(limits.apply(&ENCODER, Radians(2.0), (1024, 3072)), Err(Error::OutOfRange{goal: 3352, min: 1024, max: 3072}));
+    }
+}
@@ -0,0 +1,363 @@
+//! A mock `Interface` for testing without hardware
+//!
+//! `MockInterface` records every byte written to it, and answers reads with scripted responses.
+//! Timeouts, corrupted checksums and partial reads can be scripted to test error handling.
+//! The `protocol1_*` and `protocol2_*` functions build packets for queueing responses and asserting what was sent.
+//!
+//! This module requires the `std` feature.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::{BaudRate, CommunicationError, Interface, Timeouts};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Scripted {
+    Byte(u8),
+    Timeout,
+}
+
+/// An `Interface` that records writes and replays queued responses
+///
+/// Reads consume queued bytes in order. A read that reaches a queued timeout, or runs out of queued bytes,
+/// returns `CommunicationError::TimedOut`, like a bus where no servo answers.
+/// Flushing does not discard queued bytes, since they are responses to packets that have not been written yet.
+#[derive(Debug, Clone, Default)]
+pub struct MockInterface {
+    reads: VecDeque<Scripted>,
+    written: Vec<u8>,
+    baud_rates: Vec<BaudRate>,
+    flushes: usize,
+    timeouts: Timeouts,
+    read_timeouts: Vec<Option<Duration>>,
+}
+
+impl MockInterface {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue bytes to be read
+    pub fn queue_response(&mut self, response: &[u8]) {
+        self.reads.extend(response.iter().map(|b| Scripted::Byte(*b)));
+    }
+
+    /// Queue a timeout, the read reaching it will fail with `CommunicationError::TimedOut`
+    pub fn queue_timeout(&mut self) {
+        self.reads.push_back(Scripted::Timeout);
+    }
+
+    /// Queue the first `received` bytes of `response`, followed by a timeout
+    pub fn queue_partial_response(&mut self, response: &[u8], received: usize) {
+        self.queue_response(&response[..received.min(response.len())]);
+        self.queue_timeout();
+    }
+
+    /// Queue `response` with its last byte (the checksum, or the high byte of the CRC) corrupted
+    pub fn queue_corrupt_response(&mut self, response: &[u8]) {
+        let mut response = response.to_vec();
+        if let Some(last) = response.last_mut() {
+            *last ^= 0xff;
+        }
+        self.queue_response(&response);
+    }
+
+    /// Every byte written since creation, or the last `take_written`
+    pub fn written(&self) -> &[u8] {
+        &self.written
+    }
+
+    /// Take the written bytes, so following assertions only consider bytes written after this call
+    pub fn take_written(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.written)
+    }
+
+    /// Every baud rate that has been set, in order
+    pub fn baud_rates(&self) -> &[BaudRate] {
+        &self.baud_rates
+    }
+
+    /// The number of times `flush` has been called
+    pub fn flushes(&self) -> usize {
+        self.flushes
+    }
+
+    /// Set the timeouts returned by `Interface::timeouts`
+    pub fn set_timeouts(&mut self, timeouts: Timeouts) {
+        self.timeouts = timeouts;
+    }
+
+    /// The timeout of every read, in order, `None` for reads without a timeout
+    pub fn read_timeouts(&self) -> &[Option<Duration>] {
+        &self.read_timeouts
+    }
+
+    /// The number of queued bytes and timeouts that have not been read
+    pub fn unread(&self) -> usize {
+        self.reads.len()
+    }
+
+    /// Panic unless `packet` has been written
+    pub fn assert_sent(&self, packet: &[u8]) {
+        assert!(
+            !packet.is_empty() && self.written.windows(packet.len()).any(|w| w == packet),
+            "expected packet {:02x?} to be sent, but the written bytes were {:02x?}", packet, self.written,
+        );
+    }
+
+    /// Panic unless exactly `packets` have been written, in order
+    pub fn assert_sent_exactly(&self, packets: &[&[u8]]) {
+        let expected = packets.concat();
+        assert!(
+            self.written == expected,
+            "expected packets {:02x?} to be sent, but the written bytes were {:02x?}", packets, self.written,
+        );
+    }
+
+    /// Panic unless every queued response has been read
+    pub fn assert_all_read(&self) {
+        assert!(self.reads.is_empty(), "{} queued bytes/timeouts were not read", self.reads.len());
+    }
+}
+
+impl Interface for MockInterface {
+    fn set_baud_rate(&mut self, b: BaudRate) -> Result<(), CommunicationError> {
+        self.baud_rates.push(b);
+        Ok(())
+    }
+
+    fn flush(&mut self) {
+        self.flushes += 1;
+    }
+
+    fn read(&mut self, data: &mut [u8]) -> Result<(), CommunicationError> {
+        self.read_timeouts.push(None);
+        self.read_scripted(data)
+    }
+
+    fn read_with_timeout(&mut self, data: &mut [u8], timeout: Duration) -> Result<(), CommunicationError> {
+        self.read_timeouts.push(Some(timeout));
+        self.read_scripted(data)
+    }
+
+    fn write(&mut self, data: &[u8]) -> Result<(), CommunicationError> {
+        self.written.extend_from_slice(data);
+        Ok(())
+    }
+
+    fn timeouts(&self) -> Timeouts {
+        self.timeouts
+    }
+}
+
+/// The same scripted interface, for testing the async servo API
+#[cfg(feature="async")]
+impl crate::asynchronous::AsyncInterface for MockInterface {
+    fn set_baud_rate(&mut self, b: BaudRate) -> Result<(), CommunicationError> {
+        Interface::set_baud_rate(self, b)
+    }
+
+    fn flush(&mut self) {
+        Interface::flush(self)
+    }
+
+    async fn read_with_timeout(&mut self, data: &mut [u8], timeout: Duration) -> Result<(), CommunicationError> {
+        Interface::read_with_timeout(self, data, timeout)
+    }
+
+    async fn write(&mut self, data: &[u8]) -> Result<(), CommunicationError> {
+        Interface::write(self, data)
+    }
+
+    fn timeouts(&self) -> Timeouts {
+        self.timeouts
+    }
+}
+
+impl MockInterface {
+    fn read_scripted(&mut self, data: &mut [u8]) -> Result<(), CommunicationError> {
+        for byte in data.iter_mut() {
+            match self.reads.pop_front() {
+                Some(Scripted::Byte(b)) => *byte = b,
+                Some(Scripted::Timeout) | None => return Err(CommunicationError::TimedOut),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Build a protocol 1 packet, `instruction` is the error byte for status packets
+pub fn protocol1_packet(id: u8, instruction: u8, parameters: &[u8]) -> Vec<u8> {
+    let length = parameters.len() as u8 + 2;
+    let mut packet = vec![0xff, 0xff, id, length, instruction];
+    packet.extend_from_slice(parameters);
+    let sum = packet[2..].iter().fold(0u8, |sum, b| sum.wrapping_add(*b));
+    packet.push(!sum);
+    packet
+}
+
+/// Build a protocol 1 status packet
+pub fn protocol1_status(id: u8, error: u8, parameters: &[u8]) -> Vec<u8> {
+    protocol1_packet(id, error, parameters)
+}
+
+/// Build a protocol 2 packet, with byte stuffing and CRC
+pub fn protocol2_packet(id: u8, instruction: u8, parameters: &[u8]) -> Vec<u8> {
+    let mut body = vec![instruction];
+    for b in parameters {
+        body.push(*b);
+        if body.ends_with(&[0xff, 0xff, 0xfd]) {
+            body.push(0xfd);
+        }
+    }
+
+    let length = body.len() as u16 + 2;
+    let mut packet = vec![0xff, 0xff, 0xfd, 0x00, id, length as u8, (length >> 8) as u8];
+    packet.extend_from_slice(&body);
+    let crc = u16::from(crate::protocol2::crc::Crc::calc(&packet));
+    packet.push(crc as u8);
+    packet.push((crc >> 8) as u8);
+    packet
+}
+
+/// Build a protocol 2 status packet
+pub fn protocol2_status(id: u8, error: u8, parameters: &[u8]) -> Vec<u8> {
+    let mut status_parameters = vec![error];
+    status_parameters.extend_from_slice(parameters);
+    protocol2_packet(id, 0x55, &status_parameters)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mock::*;
+    use crate::Servo;
+    use crate::units::Radians;
+
+    #[test]
+    fn packets() {
+        // Examples from the Robotis e-manual
+        assert_eq!(protocol1_packet(1, 0x01, &[]), vec![0xff, 0xff, 0x01, 0x02, 0x01, 0xfb]);
+        assert_eq!(protocol2_packet(1, 0x01, &[]), vec![0xff, 0xff, 0xfd, 0x00, 0x01, 0x03, 0x00, 0x01, 0x19, 0x4e]);
+        assert_eq!(protocol2_packet(1, 0x03, &[0xff, 0xff, 0xfd])[8..12], [0xff, 0xff, 0xfd, 0xfd]);
+    }
+
+    #[test]
+    fn scripted_reads() {
+        let mut mock = MockInterface::new();
+        mock.queue_partial_response(&[1, 2, 3], 2);
+
+        let mut data = [0u8; 3];
+        assert_eq!(mock.read(&mut data), Err(CommunicationError::TimedOut));
+        mock.assert_all_read();
+        assert_eq!(mock.read(&mut data[..1]), Err(CommunicationError::TimedOut));
+    }
+
+    #[test]
+    fn protocol1_write_data() {
+        let mut mock = MockInterface::new();
+        mock.queue_response(&protocol1_status(1, 0, &[]));
+
+        let mut servo = crate::dynamixel::ax12::AX12::new(crate::protocol1::ServoID::new(1), BaudRate::Baud1000000);
+        servo.set_enable_torque(&mut mock, true).unwrap();
+
+        mock.assert_sent_exactly(&[&protocol1_packet(1, 0x03, &[24, 1])]);
+        assert_eq!(mock.baud_rates(), &[BaudRate::Baud1000000]);
+        mock.assert_all_read();
+    }
+
+    #[test]
+    fn protocol1_read_data() {
+        let mut mock = MockInterface::new();
+        mock.queue_response(&protocol1_status(1, 0, &[0x00, 0x02]));
+
+        let mut servo = crate::dynamixel::ax12::AX12::new(crate::protocol1::ServoID::new(1), BaudRate::Baud1000000);
+        assert_eq!(servo.get_position(&mut mock), Ok(Radians(0.0)));
+        mock.assert_sent(&protocol1_packet(1, 0x02, &[36, 2]));
+    }
+
+    #[test]
+    fn protocol1_errors() {
+        let mut servo = crate::dynamixel::ax12::AX12::new(crate::protocol1::ServoID::new(1), BaudRate::Baud1000000);
+
+        let mut mock = MockInterface::new();
+        mock.queue_timeout();
+        assert_eq!(servo.get_position(&mut mock), Err(crate::Error::Communication{id: Some(1), error: CommunicationError::TimedOut}));
+
+        let mut mock = MockInterface::new();
+        mock.queue_corrupt_response(&protocol1_status(1, 0, &[0x00, 0x02]));
+        assert_eq!(servo.get_position(&mut mock), Err(crate::Error::Format{id: Some(1), error: crate::FormatError::Protocol1(crate::protocol1::FormatError::CRC)}));
+
+        let mut mock = MockInterface::new();
+        mock.queue_response(&protocol1_status(1, 0x20, &[0x00, 0x02]));
+        assert_eq!(servo.get_position(&mut mock), Err(crate::Error::Processing{id: 1, error: crate::ProcessingError::Protocol1(crate::protocol1::ProcessingError::OVERLOAD), alert: false}));
+
+        // The source of the error is kept
+        let mut mock = MockInterface::new();
+        mock.queue_response(&protocol1_status(1, 0x24, &[0x00, 0x02]));
+        let error = servo.get_position(&mut mock).unwrap_err();
+        assert_eq!(error.to_string(), "servo 1 reported an error");
+        let source = std::error::Error::source(&error).unwrap();
+        assert_eq!(source.to_string(), "overheating error, overload error");
+    }
+
+    #[test]
+    fn protocol1_enumerate() {
+        let mut mock = MockInterface::new();
+        mock.queue_response(&protocol1_status(3, 0, &[]));
+        mock.queue_timeout();
+        mock.queue_response(&protocol1_status(3, 0, &[12, 0, 0x18]));
+
+        let servos = crate::protocol1::enumerate(&mut mock).unwrap();
+        assert_eq!(servos.len(), 1);
+        assert_eq!(servos[0].id, crate::protocol1::ServoID::new(3));
+        assert_eq!(servos[0].model_number, 12);
+        assert_eq!(servos[0].fw_version, 0x18);
+        mock.assert_sent(&protocol1_packet(0xfe, 0x01, &[]));
+        mock.assert_sent(&protocol1_packet(3, 0x02, &[0, 3]));
+    }
+
+    #[test]
+    fn protocol2_write_and_read() {
+        let mut mock = MockInterface::new();
+        mock.queue_response(&protocol2_status(1, 0, &[]));
+        mock.queue_response(&protocol2_status(1, 0, &[0x00, 0x08, 0x00, 0x00]));
+
+        let mut servo = crate::protocol2::generic::GenericServo::new(crate::protocol2::ServoID::new(1), BaudRate::Baud57600);
+        servo.set_enable_torque(&mut mock, true).unwrap();
+        assert_eq!(servo.get_position(&mut mock), Ok(Radians(0.0)));
+
+        mock.assert_sent_exactly(&[
+            &protocol2_packet(1, 0x03, &[64, 0, 1]),
+            &protocol2_packet(1, 0x02, &[132, 0, 4, 0]),
+        ]);
+        mock.assert_all_read();
+    }
+
+    #[test]
+    fn protocol2_errors() {
+        let mut servo = crate::protocol2::generic::GenericServo::new(crate::protocol2::ServoID::new(1), BaudRate::Baud57600);
+
+        let mut mock = MockInterface::new();
+        mock.queue_partial_response(&protocol2_status(1, 0, &[0x00, 0x08, 0x00, 0x00]), 10);
+        assert_eq!(servo.get_position(&mut mock), Err(crate::Error::Communication{id: Some(1), error: CommunicationError::TimedOut}));
+
+        let mut mock = MockInterface::new();
+        mock.queue_corrupt_response(&protocol2_status(1, 0, &[0x00, 0x08, 0x00, 0x00]));
+        assert_eq!(servo.get_position(&mut mock), Err(crate::Error::Format{id: Some(1), error: crate::FormatError::Protocol2(crate::protocol2::FormatError::CRC)}));
+    }
+
+    #[test]
+    fn protocol2_enumerate() {
+        let mut mock = MockInterface::new();
+        mock.queue_response(&protocol2_status(2, 0, &[0xfc, 0x03, 0x26]));
+
+        let servos = crate::protocol2::enumerate(&mut mock).unwrap();
+        assert_eq!(servos.len(), 1);
+        assert_eq!(servos[0].id, crate::protocol2::ServoID::new(2));
+        assert_eq!(servos[0].model_number, 1020);
+        assert_eq!(servos[0].fw_version, 0x26);
+        assert_eq!(servos[0].baud_rate, mock.baud_rates()[0]);
+        assert_eq!(mock.baud_rates().len(), BaudRate::variants().len());
+        mock.assert_sent(&protocol2_packet(0xfe, 0x01, &[]));
+    }
+}
@@ -3,15 +3,20 @@ pub trait ReadRegister: crate::protocol2::ReadRegister {}
 pub trait WriteRegister: crate::protocol2::WriteRegister {}
 
 rw_reg2!(OperatingMode, u8, 11);
+rw_reg2!(MaxPositionLimit, i32, 36);
+rw_reg2!(MinPositionLimit, i32, 40);
 rw_reg2!(TorqueEnable, bool, 562);
 rw_reg2!(LedRed, u8, 563);
 rw_reg2!(LedGreen, u8, 564);
 rw_reg2!(LedBlue, u8, 565);
 rw_reg2!(GoalPosition, i32, 596);
+rw_reg2!(GoalVelocity, i32, 600);
 rw_reg2!(GoalTorque, i16, 604);
 r_reg2!(PresentPosition, i32, 611);
 r_reg2!(PresentVelocity, i32, 615);
 r_reg2!(PresentCurrent, i16, 621);
+r_reg2!(PresentInputVoltage, u16, 623);
+r_reg2!(PresentTemperature, u8, 625);
 
 
 
@@ -2,28 +2,104 @@
 //!
 //! Documentation can be found in: http://support.robotis.com/en/product/actuator/dynamixel_pro.html
 
-use crate::pro;
 use crate::Interface;
 use crate::Servo;
+use crate::units::{Amperes, Radians, RadiansPerSecond, Rpm};
 
 pub mod control_table;
 
-protocol2_servo!(M4210S260R, control_table::WriteRegister, control_table::ReadRegister, 0xA918);
+protocol2_servo!(M4210S260R, control_table::WriteRegister, control_table::ReadRegister, 562, 891, 892, 0xA918);
 
-impl<I: Interface> Servo<I> for M4210S260R<I> {
+/// The position encoder of the M42-10-S260-R, 263187 steps per revolution with 0 radians in the center.
+pub const ENCODER: crate::units::Encoder = crate::units::Encoder{resolution: 263187, zero_offset: 0, range: crate::units::Radians(2.0 * crate::lib::f32::consts::PI)};
+
+/// Unit of goal velocity and present velocity, in rpm.
+const VELOCITY_UNIT: f32 = 0.00389076;
+
+/// Unit of present current.
+pub(crate) const CURRENT_UNIT: Amperes = Amperes(33.0 / 2048.0);
+
+/// The value of the operating mode register for `mode`
+fn encode_operating_mode(mode: crate::OperatingMode) -> Option<u8> {
+    match mode {
+        crate::OperatingMode::Current => Some(0),
+        crate::OperatingMode::Velocity => Some(1),
+        crate::OperatingMode::Position => Some(3),
+        crate::OperatingMode::ExtendedPosition => Some(4),
+        _ => None,
+    }
+}
+
+/// The operating mode for a value of the operating mode register
+fn decode_operating_mode(value: u8) -> Option<crate::OperatingMode> {
+    match value {
+        0 => Some(crate::OperatingMode::Current),
+        1 => Some(crate::OperatingMode::Velocity),
+        3 => Some(crate::OperatingMode::Position),
+        4 => Some(crate::OperatingMode::ExtendedPosition),
+        _ => None,
+    }
+}
+
+impl<I: Interface> M4210S260R<I> {
+    fn read_position_limits(&mut self, interface: &mut I) -> Result<(i32, i32), crate::Error> {
+        // The position limit registers are ignored in extended position mode
+        if self.get_operating_mode(interface)? == crate::OperatingMode::ExtendedPosition {
+            return Ok((i32::MIN, i32::MAX));
+        }
+        let min = i32::from(self.read::<control_table::MinPositionLimit>(interface)?);
+        let max = i32::from(self.read::<control_table::MaxPositionLimit>(interface)?);
+        Ok((min, max))
+    }
+}
+
+protocol2_servo!(impl Servo for M4210S260R with ENCODER {
     fn set_enable_torque(&mut self, interface: &mut I, enable_torque: bool) -> Result<(), crate::Error> {
         self.write(interface, control_table::TorqueEnable::new(enable_torque))?;
         Ok(())
     }
 
-    fn set_position(&mut self, interface: &mut I, value: f32) -> Result<(), crate::Error> {
-        let goal_position = ((value * 500.0) as i32) * 131593 / 1571;
+    fn set_position(&mut self, interface: &mut I, value: Radians) -> Result<(), crate::Error> {
+        let limits = self.position_limits(interface, Self::read_position_limits)?;
+        let goal_position = self.limits.apply(&ENCODER, value, limits)?;
         Ok(self.write(interface, control_table::GoalPosition::new(goal_position))?)
     }
     
-    fn get_position(&mut self, interface: &mut I) -> Result<f32, crate::Error> {
+    fn get_position(&mut self, interface: &mut I) -> Result<Radians, crate::Error> {
         let pos_fixed = i32::from(self.read::<control_table::PresentPosition>(interface)?);
-        let pos_rad = (pos_fixed as f32 * 1571.0)/(131593.0 * 500.0);
-        Ok(pos_rad)
+        Ok(ENCODER.to_radians(pos_fixed))
     }
-}
+
+    fn set_goal_velocity(&mut self, interface: &mut I, value: RadiansPerSecond) -> Result<(), crate::Error> {
+        let goal_velocity = crate::units::to_steps(f64::from(Rpm::from(value).value()) / f64::from(VELOCITY_UNIT)).ok_or(crate::Error::InvalidValue)?;
+        Ok(self.write(interface, control_table::GoalVelocity::new(goal_velocity))?)
+    }
+
+    fn get_velocity(&mut self, interface: &mut I) -> Result<RadiansPerSecond, crate::Error> {
+        let velocity = i32::from(self.read::<control_table::PresentVelocity>(interface)?);
+        Ok(RadiansPerSecond::from(Rpm(velocity as f32 * VELOCITY_UNIT)))
+    }
+
+    fn get_current(&mut self, interface: &mut I) -> Result<Amperes, crate::Error> {
+        let current = i16::from(self.read::<control_table::PresentCurrent>(interface)?);
+        Ok(Amperes(f32::from(current) * CURRENT_UNIT.value()))
+    }
+
+    fn get_temperature(&mut self, interface: &mut I) -> Result<f32, crate::Error> {
+        Ok(f32::from(u8::from(self.read::<control_table::PresentTemperature>(interface)?)))
+    }
+
+    fn get_voltage(&mut self, interface: &mut I) -> Result<f32, crate::Error> {
+        Ok(f32::from(u16::from(self.read::<control_table::PresentInputVoltage>(interface)?)) / 10.0)
+    }
+
+    fn set_operating_mode(&mut self, interface: &mut I, mode: crate::OperatingMode) -> Result<(), crate::Error> {
+        let value = encode_operating_mode(mode).ok_or(crate::Error::Unsupported(crate::Capability::OperatingMode(mode)))?;
+        self.write_operating_mode(interface, value)
+    }
+
+    fn get_operating_mode(&mut self, interface: &mut I) -> Result<crate::OperatingMode, crate::Error> {
+        let value = u8::from(self.read::<control_table::OperatingMode>(interface)?);
+        decode_operating_mode(value).ok_or(crate::Error::Format{id: Some(u8::from(self.id)), error: crate::FormatError::RegisterValue})
+    }
+});
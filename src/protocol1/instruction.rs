@@ -7,7 +7,7 @@ pub(crate) struct Ping {
 
 impl Ping {
     pub fn new(id: PacketID) -> Self {
-        Ping{id: id}
+        Ping{id}
     }
 }
 
@@ -33,7 +33,7 @@ impl Status for Pong {
 
     fn deserialize_parameters(id: ServoID, parameters: &[u8]) -> Self {
         assert_eq!(parameters.len(), 0);
-        Pong {id: id}
+        Pong {id}
     }
 }
 
@@ -45,7 +45,7 @@ pub(crate) struct WriteData<T: WriteRegister> {
 
 impl<T: WriteRegister> WriteData<T> {
     pub fn new(id: PacketID, data: T) -> Self {
-        WriteData{id: id, data: data}
+        WriteData{id, data}
     }
 }
 
@@ -58,14 +58,13 @@ impl<T: WriteRegister> Instruction for WriteData<T>{
     fn serialize(&self) -> [u8; 11] {
         let mut array = [0xff, 0xff, u8::from(self.id), Self::LENGTH, Self::INSTRUCTION_VALUE, T::ADDRESS, 0x00, 0x00, 0x00, 0x00, 0x00];
         let data = self.data.serialize();
-        for i in 0..T::SIZE as usize {
-            array[6+i] = data[i];
-        }
+        array[6..(6+T::SIZE as usize)].copy_from_slice(&data[..T::SIZE as usize]);
         array[6+T::SIZE as usize] = u8::from(checksum::Checksum::calc(&array[2..(6+T::SIZE) as usize]));
         array
     }
 }
 
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub(crate) struct WriteDataResponse {
     pub id: ServoID,
 }
@@ -75,7 +74,34 @@ impl Status for WriteDataResponse {
     
     fn deserialize_parameters(id: ServoID, parameters: &[u8]) -> Self {
         assert_eq!(parameters.len(), 0);
-        WriteDataResponse {id: id}
+        WriteDataResponse {id}
+    }
+}
+
+pub(crate) struct WriteDataRaw<const N: usize> {
+    pub id: PacketID,
+    pub address: u8,
+    pub data: [u8; N],
+}
+
+impl<const N: usize> WriteDataRaw<N> {
+    pub fn new(id: PacketID, address: u8, data: [u8; N]) -> Self {
+        assert!(N <= 4);
+        WriteDataRaw{id, address, data}
+    }
+}
+
+impl<const N: usize> Instruction for WriteDataRaw<N>{
+    // Use max size (4) untill const generics can be used in array lengths
+    type Array = [u8; 11];
+    const LENGTH: u8 = 3 + N as u8;
+    const INSTRUCTION_VALUE: u8 = 0x03;
+
+    fn serialize(&self) -> [u8; 11] {
+        let mut array = [0xff, 0xff, u8::from(self.id), Self::LENGTH, Self::INSTRUCTION_VALUE, self.address, 0x00, 0x00, 0x00, 0x00, 0x00];
+        array[6..(6+N)].copy_from_slice(&self.data);
+        array[6+N] = u8::from(checksum::Checksum::calc(&array[2..(6+N)]));
+        array
     }
 }
 
@@ -87,7 +113,7 @@ pub(crate) struct ReadData<T: ReadRegister> {
 impl<T: ReadRegister> ReadData<T> {
     pub(crate) fn new(id: PacketID) -> Self {
         ReadData {
-            id: id,
+            id,
             reg: crate::lib::marker::PhantomData{},
         }
     }
@@ -106,6 +132,7 @@ impl<T: ReadRegister> Instruction for ReadData<T>{
 }
 
 
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub(crate) struct ReadDataResponse<T: ReadRegister> {
     pub id: ServoID,
     pub data: T,
@@ -117,13 +144,74 @@ impl<T: ReadRegister> Status for ReadDataResponse<T> {
     
     fn deserialize_parameters(id: ServoID, parameters: &[u8]) -> Self {
         assert_eq!(parameters.len(), T::SIZE as usize);
-        ReadDataResponse {id: id, data: T::deserialize(parameters)}
+        ReadDataResponse {id, data: T::deserialize(parameters)}
     }
 }
 
 
 
+pub(crate) struct ReadDataRaw<const N: usize> {
+    pub id: PacketID,
+    pub address: u8,
+}
 
+impl<const N: usize> ReadDataRaw<N> {
+    pub(crate) fn new(id: PacketID, address: u8) -> Self {
+        assert!(N <= 4);
+        ReadDataRaw{id, address}
+    }
+}
+
+impl<const N: usize> Instruction for ReadDataRaw<N>{
+    type Array = [u8; 8];
+    const LENGTH: u8 = 4;
+    const INSTRUCTION_VALUE: u8 = 0x02;
+
+    fn serialize(&self) -> [u8; 8] {
+        let mut array = [0xff, 0xff, u8::from(self.id), Self::LENGTH, Self::INSTRUCTION_VALUE, self.address, N as u8, 0x00];
+        array[7] = u8::from(checksum::Checksum::calc(&array[2..7]));
+        array
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub(crate) struct ReadDataRawResponse<const N: usize> {
+    pub id: ServoID,
+    pub data: [u8; N],
+}
+
+impl<const N: usize> Status for ReadDataRawResponse<N> {
+    const LENGTH: u8 = 2 + N as u8;
+
+    fn deserialize_parameters(id: ServoID, parameters: &[u8]) -> Self {
+        let mut data = [0u8; N];
+        data.copy_from_slice(parameters);
+        ReadDataRawResponse {id, data}
+    }
+}
+
+/// Read `length` bytes from several servos, each starting at its own address
+///
+/// The number of servos varies, so the packet is built in a `Vec`.
+#[cfg(feature="std")]
+pub(crate) struct BulkRead<'a> {
+    pub length: u8,
+    pub reads: &'a [(ServoID, u8)],
+}
+
+#[cfg(feature="std")]
+impl<'a> BulkRead<'a> {
+    pub const INSTRUCTION_VALUE: u8 = 0x92;
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut packet = vec![0xff, 0xff, u8::from(PacketID::Broadcast), 3 + 3 * self.reads.len() as u8, Self::INSTRUCTION_VALUE, 0x00];
+        for (id, address) in self.reads {
+            packet.extend_from_slice(&[self.length, u8::from(*id), *address]);
+        }
+        packet.push(u8::from(checksum::Checksum::calc(&packet[2..])));
+        packet
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -140,9 +228,10 @@ mod tests {
     
     #[test]
     fn test_pong() {
-        assert_eq!(Pong::deserialize(&[0xff, 0xff, 0x01, 0x02, 0x00, 0x00]), //todo: fix checksum
+        assert_eq!(Pong::deserialize(&[0xff, 0xff, 0x01, 0x02, 0x00, 0xfc]),
                    Ok(Pong{id: ServoID::new(1)})
         );
+        assert_eq!(Pong::deserialize(&[0xff, 0xff, 0x01, 0x02, 0x00, 0x00]), Err(Error::Format{id: None, error: FormatError::CRC}));
     }
     
     #[test]
@@ -156,4 +245,13 @@ mod tests {
         assert_eq!(ReadData::<crate::dynamixel::ax12::control_table::PresentPosition>::new(PacketID::unicast(1)).serialize(), [0xff, 0xff, 0x01, 0x04, 0x02, 36, 0x2, 210]);
         assert_eq!(ReadData::<crate::dynamixel::ax12::control_table::PresentPosition>::new(PacketID::broadcast()).serialize(), [0xff, 0xff, 0xfe, 0x04, 0x02, 36, 0x2, 213]);
     }
+
+    #[test]
+    fn test_raw() {
+        assert_eq!(WriteDataRaw::new(PacketID::unicast(1), 30, [0x23, 0x01]).serialize(), [0xff, 0xff, 0x01, 0x05, 0x03, 30, 0x23, 0x01, 180, 0x00, 0x00]);
+        assert_eq!(ReadDataRaw::<2>::new(PacketID::unicast(1), 36).serialize(), [0xff, 0xff, 0x01, 0x04, 0x02, 36, 0x2, 210]);
+        assert_eq!(ReadDataRawResponse::<2>::deserialize(&[0xff, 0xff, 0x01, 0x04, 0x00, 0x23, 0x01, 0xd6]),
+                   Ok(ReadDataRawResponse{id: ServoID::new(1), data: [0x23, 0x01]})
+        );
+    }
 }
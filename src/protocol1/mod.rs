@@ -2,16 +2,19 @@
 //!
 //! Documentation can be found in: http://support.robotis.com/en/product/actuator/dynamixel/dxl_communication.html
 
+#[cfg(feature="std")]
 use crate::BaudRate;
+#[cfg(feature="std")]
 use crate::CommunicationError;
-use log::warn;
+#[cfg(feature="std")]
+use log::{info, warn};
 
 use bit_field::BitField;
 
 #[macro_use]
 mod control_table;
 pub(crate) mod instruction;
-mod checksum;
+pub(crate) mod checksum;
 
 /// Enumerate all protocol 1 servos connected to the interface
 /// This functions returns a Vec and thus requires the `std` feature.
@@ -19,9 +22,9 @@ mod checksum;
 pub fn enumerate<I: crate::Interface>(interface: &mut I) -> Result<Vec<ServoInfo>, CommunicationError> {
     let mut servos = Vec::new();
 
-    for b in vec![&BaudRate::Baud1000000] {
+    for b in &[BaudRate::Baud1000000] {
 
-        if let Err(_) = interface.set_baud_rate(*b) {
+        if interface.set_baud_rate(*b).is_err() {
             warn!(target: "protocol1", "not able to enumerate devices on baudrate: {}", u32::from(*b));
         }
 
@@ -29,136 +32,547 @@ pub fn enumerate<I: crate::Interface>(interface: &mut I) -> Result<Vec<ServoInfo
         let ping = crate::protocol1::instruction::Ping::new(PacketID::Broadcast);
         interface.write(&crate::protocol1::Instruction::serialize(&ping))?;
 
+        // Collect every pong before reading model info, since reading flushes the remaining pongs
+        let mut ids = Vec::new();
         loop {
             let mut received_data = [0u8; 6];
 
-            match interface.read(&mut received_data) {
+            match interface.read_with_timeout(&mut received_data, interface.timeouts().ping) {
                 Ok(_) => (),
                 Err(CommunicationError::TimedOut) => break,
                 Err(e) => return Err(e),
             };
 
             match <crate::protocol1::instruction::Pong as crate::protocol1::Status>::deserialize(&received_data) {
-                Ok(pong) => {
-                    interface.flush();
-                    let read_model = crate::protocol1::instruction::ReadData::<GenericModelNumber>::new(crate::protocol1::PacketID::from(pong.id));
-                    interface.write(&crate::protocol1::Instruction::serialize(&read_model))?;
-                    let mut received_data_model = [0u8; 8];
-                    interface.read(&mut received_data_model)?;
-
-                    let model_number = match <crate::protocol1::instruction::ReadDataResponse<GenericModelNumber> as crate::protocol1::Status>::deserialize(&received_data_model) {
-                        Ok(response) => response.data.value(),
-                        Err(e) => {
-                            warn!(target: "protocol1", "Found servo with baud: {} and id: {}. Could not resolve model number due to error: {:?}", u32::from(*b), u8::from(pong.id), e);
-                            continue;
-                        },
-                    };
-                                            
-                    servos.push(
-                        ServoInfo{
-                            baud_rate: *b,
-                            model_number: model_number,
-                            id: pong.id,
-                        });
-                },
+                Ok(pong) => ids.push(pong.id),
                 Err(e) => {
                     warn!(target: "protocol1", "received error: {:?} when waiting for enumeration on baud: {}", e, u32::from(*b));
                     continue;
                 },
             };
-        }   
+        }
+
+        for id in ids {
+            let model_info = match read_model_info(interface, id)? {
+                Ok(model_info) => model_info,
+                Err(e) => {
+                    warn!(target: "protocol1", "Found servo with baud: {} and id: {}. Could not resolve model number due to error: {:?}", u32::from(*b), u8::from(id), e);
+                    continue;
+                },
+            };
+
+            let info = ServoInfo{
+                baud_rate: *b,
+                model_number: model_info.model_number,
+                fw_version: model_info.fw_version,
+                id,
+            };
+            info!(target: "protocol1", "found {}", info);
+            servos.push(info);
+        }
+    }
+    Ok(servos)
+}
+
+/// Scan for protocol 1 servos by pinging every ID in `options.ids`
+///
+/// Each ID gets `options.timeout` to answer the ping before the scan moves on to the next one.
+/// This functions returns a Vec and thus requires the `std` feature.
+#[cfg(feature="std")]
+pub fn scan<I: crate::Interface>(interface: &mut I, options: &crate::ScanOptions) -> Result<Vec<ServoInfo>, CommunicationError> {
+    let mut servos = Vec::new();
+
+    if interface.set_baud_rate(options.baud_rate).is_err() {
+        warn!(target: "protocol1", "not able to scan for devices on baudrate: {}", u32::from(options.baud_rate));
+    }
+
+    for id in options.ids.clone().filter(|id| *id <= 253) {
+        if let Some(max_servos) = options.max_servos {
+            if servos.len() >= max_servos {
+                break;
+            }
+        }
+
+        interface.flush();
+        let ping = crate::protocol1::instruction::Ping::new(PacketID::unicast(id));
+        interface.write(&crate::protocol1::Instruction::serialize(&ping))?;
+
+        let mut received_data = [0u8; 6];
+        match interface.read_with_timeout(&mut received_data, options.timeout) {
+            Ok(_) => (),
+            Err(CommunicationError::TimedOut) => continue,
+            Err(e) => return Err(e),
+        };
+
+        if let Err(e) = <crate::protocol1::instruction::Pong as crate::protocol1::Status>::deserialize(&received_data) {
+            warn!(target: "protocol1", "received error: {:?} when pinging id: {} on baud: {}", e, id, u32::from(options.baud_rate));
+            continue;
+        }
+
+        match read_model_info(interface, ServoID::new(id))? {
+            Ok(model_info) => {
+                let info = ServoInfo{
+                    baud_rate: options.baud_rate,
+                    model_number: model_info.model_number,
+                    fw_version: model_info.fw_version,
+                    id: ServoID::new(id),
+                };
+                info!(target: "protocol1", "found {}", info);
+                servos.push(info);
+            },
+            Err(e) => warn!(target: "protocol1", "Found servo with baud: {} and id: {}. Could not resolve model number due to error: {:?}", u32::from(options.baud_rate), id, e),
+        }
     }
     Ok(servos)
 }
 
-/// Connect genericly to a protocol 2 servo
+/// Read the model number and firmware version of a servo that is known to exist
+///
+/// Communication errors are returned in the outer `Result`, while errors in the returned status are returned in the inner.
+#[cfg(feature="std")]
+fn read_model_info<I: crate::Interface>(interface: &mut I, id: ServoID) -> Result<Result<GenericModelInfo, Error>, CommunicationError> {
+    interface.flush();
+    let read_model = crate::protocol1::instruction::ReadData::<GenericModelInfo>::new(crate::protocol1::PacketID::from(id));
+    interface.write(&crate::protocol1::Instruction::serialize(&read_model))?;
+    let mut received_data_model = [0u8; 9];
+    interface.read_with_timeout(&mut received_data_model, interface.timeouts().status)?;
+
+    Ok(<crate::protocol1::instruction::ReadDataResponse<GenericModelInfo> as crate::protocol1::Status>::deserialize(&received_data_model)
+       .map(|response| response.data))
+}
+
+/// Connect genericly to a protocol 1 servo
 ///
 /// Only offers basic functionality. If you need more functionality use the connect method of the correct servo type instead.
+/// The servo type is decided by looking up the model number in the `registry`, `Error::UnknownModel` is returned if it is not found.
 /// This functions returns a Boxed trait and this requires the `std` feature.
 #[cfg(feature="std")]
-pub(crate) fn connect<I: crate::Interface + 'static>(_interface: &mut I, info: ServoInfo) -> Result<Box<dyn (crate::Servo<I>)>, CommunicationError>{
-    match info.model_number {
-        crate::dynamixel::mx28::MX28::<I>::MODEL_NUMBER => Ok(Box::new(crate::dynamixel::mx28::MX28::<I>::new(info.id, info.baud_rate))),
-        crate::dynamixel::ax12::AX12::<I>::MODEL_NUMBER => Ok(Box::new(crate::dynamixel::ax12::AX12::<I>::new(info.id, info.baud_rate))),
-        _ => unimplemented!(),
+pub(crate) fn connect<I: crate::Interface + 'static>(interface: &mut I, info: ServoInfo) -> Result<Box<dyn crate::Servo<I> + Send>, crate::Error>{
+    match info.model().map(|model| model.control_table) {
+        Some(crate::registry::ControlTable::MX28) => Ok(Box::new(crate::dynamixel::mx28::MX28::<I>::new(info.id, info.baud_rate))),
+        Some(crate::registry::ControlTable::AX12) => Ok(Box::new(crate::dynamixel::ax12::AX12::<I>::new(info.id, info.baud_rate))),
+        Some(crate::registry::ControlTable::Protocol1Generic) => Ok(connect_generic(interface, info)),
+        _ => Err(crate::Error::UnknownModel{model_number: info.model_number, fw_version: info.fw_version}),
     }
 }
 
+/// Connect to a protocol 1 servo using the generic control table
+///
+/// This functions returns a Boxed trait and this requires the `std` feature.
+#[cfg(feature="std")]
+pub(crate) fn connect_generic<I: crate::Interface + 'static>(_interface: &mut I, info: ServoInfo) -> Box<dyn crate::Servo<I> + Send> {
+    Box::new(generic::GenericServo::<I>::new(info.id, info.baud_rate))
+}
+
+/// Write the given data `register` to every servo on the interface at `baud_rate`.
+///
+/// Broadcast instructions are never answered, so this returns as soon as the instruction is written.
+pub fn broadcast_write_data<I: crate::Interface, W: WriteRegister>(interface: &mut I, baud_rate: crate::BaudRate, register: W) -> Result<(), Error> {
+    interface.set_baud_rate(baud_rate)?;
+    let write = crate::protocol1::instruction::WriteData::new(PacketID::broadcast(), register);
+    interface.write(&crate::protocol1::Instruction::serialize(&write)[0..<crate::protocol1::instruction::WriteData<W> as crate::protocol1::Instruction>::LENGTH as usize + 4])?;
+    Ok(())
+}
+
+/// Write `N` raw bytes to the control table of every servo on the interface at `baud_rate`, starting at `address`.
+///
+/// At most 4 bytes can be written at a time. Broadcast instructions are never answered.
+pub fn broadcast_write_data_raw<I: crate::Interface, const N: usize>(interface: &mut I, baud_rate: crate::BaudRate, address: u8, data: [u8; N]) -> Result<(), Error> {
+    interface.set_baud_rate(baud_rate)?;
+    let write = crate::protocol1::instruction::WriteDataRaw::new(PacketID::broadcast(), address, data);
+    interface.write(&crate::protocol1::Instruction::serialize(&write)[0..<crate::protocol1::instruction::WriteDataRaw<N> as crate::protocol1::Instruction>::LENGTH as usize + 4])?;
+    Ok(())
+}
 
+/// Read `N` raw bytes from several servos with one bulk read instruction
+///
+/// `reads` holds the ID, start address and return delay time of every servo. Each reply gets its own deadline from `timing`,
+/// so a missing servo only costs its own deadline, and replies are matched to the servos by ID. Errors writing the instruction are returned in the outer `Result`,
+/// and the result of every read in the inner, in the order of `reads`.
+/// This functions returns a Vec and thus requires the `std` feature.
+#[cfg(feature="std")]
+pub fn bulk_read_raw<I: crate::Interface, const N: usize>(interface: &mut I, timing: &crate::ReplyTiming, reads: &[(ServoID, u8, crate::lib::time::Duration)]) -> Result<Vec<Result<[u8; N], Error>>, CommunicationError> {
+    interface.set_baud_rate(timing.baud_rate)?;
+    interface.flush();
+
+    let targets: Vec<_> = reads.iter().map(|(id, address, _)| (*id, *address)).collect();
+    let packet = crate::protocol1::instruction::BulkRead{length: N as u8, reads: &targets}.serialize();
+    interface.write(&packet)?;
+
+    // The first reply also waits for the instruction to be transmitted
+    let mut instruction_time = timing.transmission_time(packet.len());
+    let ids: Vec<_> = reads.iter().map(|(id, _, _)| u8::from(*id)).collect();
+    Ok(crate::reply_timing::collect_replies(&ids, |index| {
+        let timeout = timing.deadline(reads[index].2, 6 + N) + crate::lib::mem::take(&mut instruction_time);
+        let response = read_bulk_reply::<I, N>(interface, timeout)?;
+        Ok((u8::from(response.id), response.data))
+    }))
+}
+
+/// Read a reply to a bulk read
+#[cfg(feature="std")]
+fn read_bulk_reply<I: crate::Interface, const N: usize>(interface: &mut I, timeout: crate::lib::time::Duration) -> Result<crate::protocol1::instruction::ReadDataRawResponse<N>, Error> {
+    let mut received_data = vec![0u8; 4];
+    interface.read_with_timeout(&mut received_data, timeout)?;
+    let length = received_data[3] as usize;
+    received_data.resize(4 + length, 0);
+    interface.read_with_timeout(&mut received_data[4..], timeout)?;
+
+    <crate::protocol1::instruction::ReadDataRawResponse<N> as crate::protocol1::Status>::deserialize(&received_data)
+}
+
+/// Create a protocol 1 servo type, or with `impl Servo for $name with $encoder { .. }` implement `Servo` for it
+///
+/// Only the model specific methods are given to the `impl Servo` form, the rest are generated.
+/// The model must have a `read_position_limits` method, and `$encoder` converts its positions.
 macro_rules! protocol1_servo {
-    ($name:ident, $write:path, $read:path, $model_number:expr) => {
+    (impl Servo for $name:ident with $encoder:path { $($model_specific:tt)* }) => {
+        impl<I: crate::Interface> crate::Servo<I> for $name<I> {
+            $($model_specific)*
+
+            fn set_limit_policy(&mut self, policy: crate::LimitPolicy) {
+                self.limits.policy = policy;
+            }
+
+            fn get_limit_policy(&self) -> crate::LimitPolicy {
+                self.limits.policy
+            }
+
+            fn get_position_limits(&mut self, interface: &mut I) -> Result<(crate::units::Radians, crate::units::Radians), crate::Error> {
+                let (min, max) = self.position_limits(interface, Self::read_position_limits)?;
+                Ok(($encoder.to_radians(min), $encoder.to_radians(max)))
+            }
+
+            fn set_timeouts(&mut self, timeouts: Option<crate::Timeouts>) {
+                self.timeouts = timeouts;
+            }
+
+            fn assume_status_return_level(&mut self, level: crate::StatusReturnLevel) {
+                self.status_return_level = level;
+            }
+
+            fn read_status_return_level(&mut self, interface: &mut I) -> Result<crate::StatusReturnLevel, crate::Error> {
+                self.read_status_return_level(interface)
+            }
+
+            fn get_return_delay_time(&mut self, interface: &mut I) -> Result<crate::lib::time::Duration, crate::Error> {
+                self.return_delay_time(interface)
+            }
+
+            fn set_return_delay_time(&mut self, interface: &mut I, delay: crate::lib::time::Duration) -> Result<(), crate::Error> {
+                self.write_return_delay_time(interface, delay)
+            }
+        }
+    };
+    ($name:ident, $write:path, $read:path, $eeprom_end:expr, $status_return_level:expr, $model_number:expr) => {
+        protocol1_servo!($name, $write, $read, $eeprom_end, $status_return_level);
+
+        impl<I: crate::Interface> $name<I> {
+            pub const MODEL_NUMBER: u16 = $model_number;
+        }
+    };
+    ($name:ident, $write:path, $read:path, $eeprom_end:expr, $status_return_level:expr) => {
         pub struct $name<I: crate::Interface> {
             id: crate::protocol1::ServoID,
             baudrate: crate::BaudRate,
-            interface: crate::lib::marker::PhantomData<I>,
+            limits: crate::limits::PositionLimits,
+            timeouts: Option<crate::Timeouts>,
+            status_return_level: crate::StatusReturnLevel,
+            return_delay: Option<crate::lib::time::Duration>,
+            // Servos don't own an interface, so they are `Send` and `Sync` regardless of `I`
+            interface: crate::lib::marker::PhantomData<fn() -> I>,
         }
         
         impl<I: crate::Interface> $name<I> {
-            pub const MODEL_NUMBER: u16 = $model_number;
+            /// The first address after the EEPROM area of the control table
+            pub const EEPROM_END: u8 = $eeprom_end;
+
+            /// The address of the Status Return Level register
+            const STATUS_RETURN_LEVEL: u8 = $status_return_level;
+
+            /// The address of the Return Delay Time register, the same for every model
+            const RETURN_DELAY_TIME: u8 = 5;
 
             /// Creates a new servo without `ping`ing or taking any other measure to make sure it exists.
             pub fn new(id: crate::protocol1::ServoID, baudrate: crate::BaudRate) -> Self {
                 $name{
-                    id: id,
-                    baudrate: baudrate,
+                    id,
+                    baudrate,
+                    limits: crate::limits::PositionLimits::new(),
+                    timeouts: None,
+                    status_return_level: crate::StatusReturnLevel::All,
+                    return_delay: None,
                     interface: crate::lib::marker::PhantomData{},
                 }
             }
+
+            /// The timeouts of this servo, or the interface if not set
+            fn timeouts(&self, interface: &I) -> crate::Timeouts {
+                self.timeouts.unwrap_or_else(|| interface.timeouts())
+            }
+
+            /// The timeout for the status of a write starting at `address`
+            fn write_timeout(&self, interface: &I, address: u8) -> crate::lib::time::Duration {
+                let timeouts = self.timeouts(interface);
+                if address < Self::EEPROM_END {
+                    timeouts.eeprom_write
+                } else {
+                    timeouts.status
+                }
+            }
+
+            /// Forget the cached position limits, so they are read from the servo when next needed.
+            ///
+            /// Needed after changing the limit registers without using `set_operating_mode`.
+            pub fn clear_position_limits(&mut self) {
+                self.limits.cached = None;
+            }
+
+            /// The position limits in raw steps, using `read` to read them from the servo if they're not cached
+            fn position_limits(&mut self, interface: &mut I, read: fn(&mut Self, &mut I) -> Result<(i32, i32), crate::Error>) -> Result<(i32, i32), crate::Error> {
+                if let Some(limits) = self.limits.cached {
+                    return Ok(limits);
+                }
+                let limits = read(self, interface)?;
+                self.limits.cached = Some(limits);
+                Ok(limits)
+            }
             
-            fn read_response(&mut self, interface: &mut I, data: &mut [u8]) -> Result<usize, crate::CommunicationError> {
+            fn read_response(&mut self, interface: &mut I, data: &mut [u8], timeout: crate::lib::time::Duration) -> Result<usize, crate::protocol1::Error> {
                 // first read header
-                interface.read(&mut data[..4])?;
+                interface.read_with_timeout(&mut data[..4], timeout)?;
 
                 // then read rest of message depending on header length
                 let length = data[3] as usize;
-                interface.read(&mut data[4..4+length])?;
+                if length > data.len() - 4 {
+                    return Err(crate::protocol1::Error::Format{id: None, error: crate::protocol1::FormatError::Length});
+                }
+                interface.read_with_timeout(&mut data[4..4+length], timeout)?;
                 Ok(4+length)
             }
 
+            /// The status return level the servo is assumed to have, `StatusReturnLevel::All` unless told otherwise.
+            pub fn status_return_level(&self) -> crate::StatusReturnLevel {
+                self.status_return_level
+            }
+
+            /// Assume the servo has status return level `level`, without communicating with it.
+            ///
+            /// Writes only wait for a status when the level is `StatusReturnLevel::All`.
+            /// Reads fail with `CommunicationError::NoStatus`, without sending anything, when the level is `StatusReturnLevel::Ping`.
+            pub fn assume_status_return_level(&mut self, level: crate::StatusReturnLevel) {
+                self.status_return_level = level;
+            }
+
+            /// Read the status return level from the servo, and assume it from now on.
+            ///
+            /// A servo not answering reads is pinged, and has `StatusReturnLevel::Ping` if it answers.
+            pub fn read_status_return_level(&mut self, interface: &mut I) -> Result<crate::StatusReturnLevel, crate::Error> {
+                let level = match self.read_data_raw_unchecked::<1>(interface, Self::STATUS_RETURN_LEVEL) {
+                    Ok([value]) => crate::StatusReturnLevel::from_register(value).ok_or(crate::Error::Format{id: Some(u8::from(self.id)), error: crate::FormatError::RegisterValue})?,
+                    Err(crate::protocol1::Error::Communication{error: crate::CommunicationError::TimedOut, ..}) => {
+                        self.ping(interface)?;
+                        crate::StatusReturnLevel::Ping
+                    },
+                    Err(e) => return Err(e.into()),
+                };
+                self.status_return_level = level;
+                Ok(level)
+            }
+
+            /// Write the status return level to the servo, and assume it from now on.
+            ///
+            /// The write is answered according to the new level.
+            pub fn write_status_return_level(&mut self, interface: &mut I, level: crate::StatusReturnLevel) -> Result<(), crate::protocol1::Error> {
+                self.status_return_level = level;
+                self.write_data_raw(interface, Self::STATUS_RETURN_LEVEL, [u8::from(level)])
+            }
+
+            /// The return delay time of the servo, read from the servo the first time and cached afterwards.
+            pub fn return_delay_time(&mut self, interface: &mut I) -> Result<crate::lib::time::Duration, crate::Error> {
+                if let Some(delay) = self.return_delay {
+                    return Ok(delay);
+                }
+                let [value] = self.read_data_raw::<1>(interface, Self::RETURN_DELAY_TIME)?;
+                let delay = crate::reply_timing::return_delay_from_register(value);
+                self.return_delay = Some(delay);
+                Ok(delay)
+            }
+
+            /// Write the return delay time, rounded down to a multiple of 2 µs.
+            ///
+            /// At most 508 µs can be written, larger values give `Error::InvalidValue`.
+            pub fn write_return_delay_time(&mut self, interface: &mut I, delay: crate::lib::time::Duration) -> Result<(), crate::Error> {
+                let value = crate::reply_timing::return_delay_to_register(delay)?;
+                self.return_delay = None;
+                self.write_data_raw(interface, Self::RETURN_DELAY_TIME, [value])?;
+                self.return_delay = Some(crate::reply_timing::return_delay_from_register(value));
+                Ok(())
+            }
+
             /// Ping the servo, returning `Ok(())` if it exists.
             pub fn ping(&mut self, interface: &mut I) -> Result<(), crate::protocol1::Error> {
-                interface.set_baud_rate(self.baudrate)?;
-                interface.flush();
-
-                let ping = crate::protocol1::instruction::Ping::new(crate::protocol1::PacketID::from(self.id));
-                interface.write(&crate::protocol1::Instruction::serialize(&ping))?;
-                let mut received_data = [0u8; 6];
-                self.read_response(interface, &mut received_data)?;
-                <crate::protocol1::instruction::Pong as crate::protocol1::Status>::deserialize(&received_data)?;
-                Ok(())
+                self.transaction(interface, |servo, interface| {
+                    interface.set_baud_rate(servo.baudrate)?;
+                    interface.flush();
+
+                    let ping = crate::protocol1::instruction::Ping::new(crate::protocol1::PacketID::from(servo.id));
+                    interface.write(&crate::protocol1::Instruction::serialize(&ping))?;
+                    let mut received_data = [0u8; 6];
+                    let timeout = servo.timeouts(interface).ping;
+                    servo.read_response(interface, &mut received_data, timeout)?;
+                    <crate::protocol1::instruction::Pong as crate::protocol1::Status>::deserialize(&received_data)?;
+                    Ok(())
+                })
             }
             
             /// Write the given data `register` to the servo.
             pub fn write_data<W: $write>(&mut self, interface: &mut I, register: W) -> Result<(), crate::protocol1::Error> {
-                interface.set_baud_rate(self.baudrate)?;
-                interface.flush();
-                let write = crate::protocol1::instruction::WriteData::new(crate::protocol1::PacketID::from(self.id), register);
-                interface.write(&crate::protocol1::Instruction::serialize(&write)[0..<crate::protocol1::instruction::WriteData<W> as crate::protocol1::Instruction>::LENGTH as usize + 4])?;
-                let mut received_data = [0u8; 11];
-                let length = self.read_response(interface, &mut received_data)?;
-                match <crate::protocol1::instruction::WriteDataResponse as crate::protocol1::Status>::deserialize(&received_data[0..length]) {
-                    Ok(crate::protocol1::instruction::WriteDataResponse{id: _}) => Ok(()),
-                    Err(e) => Err(e),
-                }
+                self.transaction(interface, |servo, interface| {
+                    interface.set_baud_rate(servo.baudrate)?;
+                    interface.flush();
+                    let write = crate::protocol1::instruction::WriteData::new(crate::protocol1::PacketID::from(servo.id), register);
+                    interface.write(&crate::protocol1::Instruction::serialize(&write)[0..<crate::protocol1::instruction::WriteData<W> as crate::protocol1::Instruction>::LENGTH as usize + 4])?;
+                    if !servo.status_return_level.replies_to_writes() {
+                        return Ok(());
+                    }
+                    let mut received_data = [0u8; 11];
+                    let timeout = servo.write_timeout(interface, <W as crate::protocol1::Register>::ADDRESS);
+                    let length = servo.read_response(interface, &mut received_data, timeout)?;
+                    match <crate::protocol1::instruction::WriteDataResponse as crate::protocol1::Status>::deserialize(&received_data[0..length]) {
+                        Ok(crate::protocol1::instruction::WriteDataResponse{id: _}) => Ok(()),
+                        Err(e) => Err(e),
+                    }
+                })
             }
             
             pub fn read_data<R: $read>(&mut self, interface: &mut I) -> Result<R, crate::protocol1::Error> {
-                interface.set_baud_rate(self.baudrate)?;
-                interface.flush();
-
-                let read = crate::protocol1::instruction::ReadData::<R>::new(crate::protocol1::PacketID::from(self.id));
-                interface.write(&crate::protocol1::Instruction::serialize(&read))?;
-                let mut received_data = [0u8; 20];
-                let length = self.read_response(interface, &mut received_data)?;
-                match <crate::protocol1::instruction::ReadDataResponse<R> as crate::protocol1::Status>::deserialize(&received_data[0..length]) {
-                    Ok(r) => Ok(r.data),
-                    Err(e) => Err(e),
+                self.transaction(interface, |servo, interface| {
+                    if !servo.status_return_level.replies_to_reads() {
+                        return Err(crate::CommunicationError::NoStatus.into());
+                    }
+                    interface.set_baud_rate(servo.baudrate)?;
+                    interface.flush();
+
+                    let read = crate::protocol1::instruction::ReadData::<R>::new(crate::protocol1::PacketID::from(servo.id));
+                    interface.write(&crate::protocol1::Instruction::serialize(&read))?;
+                    let mut received_data = [0u8; 20];
+                    let timeout = servo.timeouts(interface).status;
+                    let length = servo.read_response(interface, &mut received_data, timeout)?;
+                    match <crate::protocol1::instruction::ReadDataResponse<R> as crate::protocol1::Status>::deserialize(&received_data[0..length]) {
+                        Ok(r) => Ok(r.data),
+                        Err(e) => Err(e),
+                    }
+                })
+            }
+
+            /// Write `N` raw bytes to the control table, starting at `address`.
+            ///
+            /// At most 4 bytes can be written at a time.
+            pub fn write_data_raw<const N: usize>(&mut self, interface: &mut I, address: u8, data: [u8; N]) -> Result<(), crate::protocol1::Error> {
+                self.transaction(interface, |servo, interface| {
+                    interface.set_baud_rate(servo.baudrate)?;
+                    interface.flush();
+                    let write = crate::protocol1::instruction::WriteDataRaw::new(crate::protocol1::PacketID::from(servo.id), address, data);
+                    interface.write(&crate::protocol1::Instruction::serialize(&write)[0..<crate::protocol1::instruction::WriteDataRaw<N> as crate::protocol1::Instruction>::LENGTH as usize + 4])?;
+                    if !servo.status_return_level.replies_to_writes() {
+                        return Ok(());
+                    }
+                    let mut received_data = [0u8; 11];
+                    let timeout = servo.write_timeout(interface, address);
+                    let length = servo.read_response(interface, &mut received_data, timeout)?;
+                    <crate::protocol1::instruction::WriteDataResponse as crate::protocol1::Status>::deserialize(&received_data[0..length])?;
+                    Ok(())
+                })
+            }
+
+            /// Read `N` raw bytes from the control table, starting at `address`.
+            ///
+            /// At most 4 bytes can be read at a time.
+            pub fn read_data_raw<const N: usize>(&mut self, interface: &mut I, address: u8) -> Result<[u8; N], crate::protocol1::Error> {
+                if !self.status_return_level.replies_to_reads() {
+                    return Err(crate::protocol1::Error::from(crate::CommunicationError::NoStatus).with_id(self.id));
                 }
+                self.read_data_raw_unchecked(interface, address)
+            }
+
+            /// Read `N` raw bytes, even if the servo is assumed not to answer reads
+            fn read_data_raw_unchecked<const N: usize>(&mut self, interface: &mut I, address: u8) -> Result<[u8; N], crate::protocol1::Error> {
+                self.transaction(interface, |servo, interface| {
+                    interface.set_baud_rate(servo.baudrate)?;
+                    interface.flush();
+
+                    let read = crate::protocol1::instruction::ReadDataRaw::<N>::new(crate::protocol1::PacketID::from(servo.id), address);
+                    interface.write(&crate::protocol1::Instruction::serialize(&read))?;
+                    let mut received_data = [0u8; 20];
+                    let timeout = servo.timeouts(interface).status;
+                    let length = servo.read_response(interface, &mut received_data, timeout)?;
+                    Ok(<crate::protocol1::instruction::ReadDataRawResponse<N> as crate::protocol1::Status>::deserialize(&received_data[0..length])?.data)
+                })
+            }
+
+            /// Run `f`, attaching the ID of the servo to its communication and format errors
+            fn transaction<T>(&mut self, interface: &mut I, f: impl FnOnce(&mut Self, &mut I) -> Result<T, crate::protocol1::Error>) -> Result<T, crate::protocol1::Error> {
+                let id = self.id;
+                f(self, interface).map_err(|e| e.with_id(id))
             }
         }
     };
 }
 
+pub mod generic;
+
+
+/// Decode values where bit 10 gives the direction and the lower bits the magnitude, like present speed and load.
+pub(crate) fn decode_direction_bit(value: u16) -> i32 {
+    let magnitude = i32::from(value.get_bits(0..10));
+    if value.get_bit(10) {
+        -magnitude
+    } else {
+        magnitude
+    }
+}
+
+/// Encode values where bit 10 gives the direction and the lower bits the magnitude, like moving speed.
+pub(crate) fn encode_direction_bit(value: i32) -> u16 {
+    let mut encoded = value.unsigned_abs().min(1023) as u16;
+    encoded.set_bit(10, value < 0);
+    encoded
+}
+
+/// The (cw, ccw) angle limits that put a protocol 1 servo in `mode`
+///
+/// `max_position` is the highest position value of the servo, which is also used for multi-turn mode if `multi_turn` is set.
+pub(crate) fn encode_angle_limits(mode: crate::OperatingMode, max_position: u16, multi_turn: bool) -> Option<(u16, u16)> {
+    match mode {
+        crate::OperatingMode::Position => Some((0, max_position)),
+        crate::OperatingMode::Velocity => Some((0, 0)),
+        crate::OperatingMode::ExtendedPosition if multi_turn => Some((max_position, max_position)),
+        _ => None,
+    }
+}
+
+/// The operating mode of a protocol 1 servo with the given (cw, ccw) angle limits
+pub(crate) fn decode_angle_limits(cw: u16, ccw: u16, max_position: u16, multi_turn: bool) -> crate::OperatingMode {
+    if cw == 0 && ccw == 0 {
+        crate::OperatingMode::Velocity
+    } else if multi_turn && cw == max_position && ccw == max_position {
+        crate::OperatingMode::ExtendedPosition
+    } else {
+        crate::OperatingMode::Position
+    }
+}
+
+/// The (min, max) position limits, in raw steps, of a protocol 1 servo with the given (cw, ccw) angle limits
+///
+/// In wheel mode the full position range is used. In multi-turn mode the limits are the range of the goal position register.
+pub(crate) fn decode_position_limits(cw: u16, ccw: u16, max_position: u16, multi_turn: bool) -> (i32, i32) {
+    match decode_angle_limits(cw, ccw, max_position, multi_turn) {
+        crate::OperatingMode::Velocity => (0, i32::from(max_position)),
+        crate::OperatingMode::ExtendedPosition => (-28672, 28672),
+        _ => (i32::from(cw), i32::from(ccw)),
+    }
+}
 
 pub trait Register {
     const SIZE: u8;
@@ -193,19 +607,36 @@ pub(crate) trait Status {
     fn deserialize(data: &[u8]) -> Result<Self, Error>
         where Self: Sized {
         // check for formating error stuff
+        if data.len() < 6 || data[0] != 0xff || data[1] != 0xff {
+            return Err(Error::Format{id: None, error: FormatError::Header});
+        }
+
+        let end = 3 + data[3] as usize;
+        if end >= data.len() {
+            return Err(Error::Format{id: None, error: FormatError::Length});
+        }
+
+        let checksum = !data[2..end].iter().fold(0u8, |sum, b| sum.wrapping_add(*b));
+        if checksum != data[end] {
+            return Err(Error::Format{id: None, error: FormatError::CRC});
+        }
         
+        // 254 is the broadcast ID and 255 is not an ID, neither is sent by a servo
+        if data[2] > 253 {
+            return Err(Error::Format{id: None, error: FormatError::ID});
+        }
+        let id = ServoID::new(data[2]);
+
         // check for processing errors
-        if let Some(error) = ProcessingError::decode(data[4]).map_err(|()| Error::Format(FormatError::InvalidError))? {
-            return Err(Error::Processing(error));
+        if let Some(error) = ProcessingError::decode(data[4]).map_err(|()| Error::Format{id: None, error: FormatError::InvalidError})? {
+            return Err(Error::Processing{id, error});
         }
         
         let length = data[3];
         if length != Self::LENGTH {
-            return Err(Error::Format(FormatError::Length));
+            return Err(Error::Format{id: None, error: FormatError::Length});
         }
 
-        let id = ServoID::new(data[2]);
-        
         let parameters_range = 5..(5 + Self::LENGTH as usize - 2);
         Ok( Self::deserialize_parameters(id, &data[parameters_range]) )
     }
@@ -214,32 +645,98 @@ pub(crate) trait Status {
 /// All information needed to connect to a protocol 1 servo
 #[derive(Debug, Clone)]
 pub struct ServoInfo {
-    baud_rate: crate::BaudRate,
-    model_number: u16,
-    id: ServoID,
+    pub baud_rate: crate::BaudRate,
+    pub model_number: u16,
+    pub fw_version: u8,
+    pub id: ServoID,
+}
+
+impl ServoInfo {
+    /// Look up the model of the servo in the `registry`
+    pub fn model(&self) -> Option<crate::registry::Model> {
+        crate::registry::lookup(crate::registry::Protocol::Protocol1, self.model_number)
+    }
+}
+
+impl crate::lib::fmt::Display for ServoInfo {
+    fn fmt(&self, f: &mut crate::lib::fmt::Formatter) -> crate::lib::fmt::Result {
+        match self.model() {
+            Some(model) => write!(f, "{}", model.name)?,
+            None => write!(f, "unknown model {:#06x}", self.model_number)?,
+        }
+        write!(f, " with id: {} at baud: {} (firmware version: {})", self.id.0, u32::from(self.baud_rate), self.fw_version)
+    }
 }
 
    
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum Error {
-    Communication(crate::CommunicationError),
-    Format(FormatError),
-    Processing(ProcessingError),
+    /// An Error originating from the `Interface`, communicating with the servo with ID `id` if it came from a servo method
+    Communication {
+        id: Option<ServoID>,
+        error: crate::CommunicationError,
+    },
+
+    /// A status could not be decoded, from the servo with ID `id` if it came from a servo method
+    Format {
+        id: Option<ServoID>,
+        error: FormatError,
+    },
+
+    /// The servo with ID `id` reported errors processing the instruction
+    Processing {
+        id: ServoID,
+        error: ProcessingError,
+    },
+}
+
+impl Error {
+    /// Attach `id` to communication and format errors without one
+    pub(crate) fn with_id(self, id: ServoID) -> Self {
+        match self {
+            Error::Communication{id: None, error} => Error::Communication{id: Some(id), error},
+            Error::Format{id: None, error} => Error::Format{id: Some(id), error},
+            e => e,
+        }
+    }
 }
 
 impl From<crate::protocol1::Error> for crate::Error {
     fn from(e: crate::protocol1::Error) -> crate::Error {
         match e {
-            crate::protocol1::Error::Communication(ce) => crate::Error::Communication(ce),
-            crate::protocol1::Error::Format(_) => crate::Error::Format,
-            crate::protocol1::Error::Processing(_) => crate::Error::Processing,
+            crate::protocol1::Error::Communication{id, error} => crate::Error::Communication{id: id.map(u8::from), error},
+            crate::protocol1::Error::Format{id, error} => crate::Error::Format{id: id.map(u8::from), error: crate::FormatError::Protocol1(error)},
+            crate::protocol1::Error::Processing{id, error} => crate::Error::Processing{id: u8::from(id), error: crate::ProcessingError::Protocol1(error), alert: false},
         }
     }
 }
 
 impl From<crate::CommunicationError> for Error {
     fn from(e: crate::CommunicationError) -> Error {
-        Error::Communication(e)
+        Error::Communication{id: None, error: e}
+    }
+}
+
+impl crate::lib::fmt::Display for Error {
+    fn fmt(&self, f: &mut crate::lib::fmt::Formatter) -> crate::lib::fmt::Result {
+        match self {
+            Error::Communication{id: Some(id), ..} => write!(f, "communication with servo {} failed", id.0),
+            Error::Communication{id: None, ..} => write!(f, "communication with the servo failed"),
+            Error::Format{id: Some(id), ..} => write!(f, "invalid status from servo {}", id.0),
+            Error::Format{id: None, ..} => write!(f, "invalid status"),
+            Error::Processing{id, ..} => write!(f, "servo {} reported an error", id.0),
+        }
+    }
+}
+
+#[cfg(feature="std")]
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Communication{error, ..} => Some(error),
+            Error::Format{error, ..} => Some(error),
+            Error::Processing{error, ..} => Some(error),
+        }
     }
 }
 
@@ -252,6 +749,24 @@ pub enum FormatError {
     InvalidError,
 }
 
+impl crate::lib::fmt::Display for FormatError {
+    fn fmt(&self, f: &mut crate::lib::fmt::Formatter) -> crate::lib::fmt::Result {
+        match self {
+            FormatError::ID => write!(f, "unexpected servo ID"),
+            FormatError::Header => write!(f, "invalid header"),
+            FormatError::CRC => write!(f, "invalid checksum"),
+            FormatError::Length => write!(f, "unexpected length"),
+            FormatError::InvalidError => write!(f, "invalid error field"),
+        }
+    }
+}
+
+#[cfg(feature="std")]
+impl std::error::Error for FormatError {}
+
+/// The error flags of a protocol 1 status
+///
+/// Several errors can be reported at once, use `contains` or the methods for single flags to check for them.
 #[derive(PartialEq, Eq, Clone, Copy)]
 pub struct ProcessingError(u8);
 
@@ -261,7 +776,42 @@ impl From<ProcessingError> for u8 {
     }
 }
 
+impl crate::lib::ops::BitOr for ProcessingError {
+    type Output = ProcessingError;
+
+    fn bitor(self, other: ProcessingError) -> ProcessingError {
+        ProcessingError(self.0 | other.0)
+    }
+}
+
 impl ProcessingError {
+    /// The input voltage is out of the range of the voltage limits
+    pub const INPUT_VOLTAGE: ProcessingError = ProcessingError(1 << 0);
+    /// The goal position is outside the angle limits
+    pub const ANGLE_LIMIT: ProcessingError = ProcessingError(1 << 1);
+    /// The internal temperature is above the temperature limit
+    pub const OVERHEATING: ProcessingError = ProcessingError(1 << 2);
+    /// An instruction parameter is out of range
+    pub const RANGE: ProcessingError = ProcessingError(1 << 3);
+    /// The checksum of the instruction was wrong
+    pub const CHECKSUM: ProcessingError = ProcessingError(1 << 4);
+    /// The load can't be controlled with the maximum torque
+    pub const OVERLOAD: ProcessingError = ProcessingError(1 << 5);
+    /// The instruction is undefined, or an action was sent without a registered write
+    pub const INSTRUCTION: ProcessingError = ProcessingError(1 << 6);
+
+    /// Every flag, with its name
+    const FLAGS: [(ProcessingError, &'static str); 7] = [
+        (ProcessingError::INPUT_VOLTAGE, "input voltage error"),
+        (ProcessingError::ANGLE_LIMIT, "angle limit error"),
+        (ProcessingError::OVERHEATING, "overheating error"),
+        (ProcessingError::RANGE, "range error"),
+        (ProcessingError::CHECKSUM, "checksum error"),
+        (ProcessingError::OVERLOAD, "overload error"),
+        (ProcessingError::INSTRUCTION, "instruction error"),
+    ];
+
+    #[allow(clippy::result_unit_err)]
     pub fn decode(v: u8) -> Result<Option<Self>, ()> {
         if v == 0 {
             Ok(None)
@@ -271,50 +821,73 @@ impl ProcessingError {
             Ok(Some(ProcessingError(v)))
         }
     }
+
+    pub fn bits(self) -> u8 {
+        self.0
+    }
+
+    /// Whether every flag of `other` is set
+    pub fn contains(self, other: ProcessingError) -> bool {
+        self.0 & other.0 == other.0
+    }
     
     pub fn instruction_error(&self) -> bool {
-        self.0.get_bit(6)
+        self.contains(ProcessingError::INSTRUCTION)
     }
 
     pub fn overload_error(&self) -> bool {
-        self.0.get_bit(5)
+        self.contains(ProcessingError::OVERLOAD)
     }
 
     pub fn checksum_error(&self) -> bool {
-        self.0.get_bit(4)
+        self.contains(ProcessingError::CHECKSUM)
     }
 
     pub fn range_error(&self) -> bool {
-        self.0.get_bit(3)
+        self.contains(ProcessingError::RANGE)
     }
 
     pub fn overheating_error(&self) -> bool {
-        self.0.get_bit(3)
+        self.contains(ProcessingError::OVERHEATING)
     }
 
     pub fn angle_limit_error(&self) -> bool {
-        self.0.get_bit(1)
+        self.contains(ProcessingError::ANGLE_LIMIT)
     }
 
     pub fn input_voltage_error(&self) -> bool {
-        self.0.get_bit(0)
+        self.contains(ProcessingError::INPUT_VOLTAGE)
+    }
+
+    /// The names of the flags that are set
+    fn names(self) -> impl Iterator<Item = &'static str> {
+        ProcessingError::FLAGS.iter().filter(move |(flag, _)| self.contains(*flag)).map(|(_, name)| *name)
     }
 }
 
 impl crate::lib::fmt::Debug for ProcessingError {
     fn fmt(&self, f: &mut crate::lib::fmt::Formatter) -> crate::lib::fmt::Result {
-        write!(f, "The current ProcessingError, {:?}, decodes to the following errors: [", self.0)?;
-        if self.instruction_error() {write!(f, "instruction_error")?;}
-        if self.overload_error() {write!(f, "overload_error")?;}
-        if self.checksum_error() {write!(f, "checksum_error")?;}
-        if self.range_error() {write!(f, "range_error")?;}
-        if self.overheating_error() {write!(f, "overheating_error")?;}
-        if self.angle_limit_error() {write!(f, "angle_limit_error")?;}
-        if self.input_voltage_error() {write!(f, "input_voltage_error")?;}
-        write!(f, "]")
+        write!(f, "ProcessingError(")?;
+        f.debug_list().entries(self.names()).finish()?;
+        write!(f, ")")
     }
 }
 
+impl crate::lib::fmt::Display for ProcessingError {
+    fn fmt(&self, f: &mut crate::lib::fmt::Formatter) -> crate::lib::fmt::Result {
+        for (i, name) in self.names().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", name)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature="std")]
+impl std::error::Error for ProcessingError {}
+
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
 pub struct ServoID(u8);
 
@@ -326,7 +899,7 @@ impl ServoID {
 }
 
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
-pub(crate) enum PacketID {
+pub enum PacketID {
     Unicast(ServoID),
     Broadcast,
 }
@@ -363,23 +936,229 @@ impl From<ServoID> for u8 {
     }
 }
 
-/// This is the model number register for all protocol 1 servos.
-struct GenericModelNumber(u16);
-
-impl GenericModelNumber {
-    fn value(&self) -> u16 {
-        self.0
-    }
+/// The model number and firmware version registers, they are located at the same address for all protocol 1 servos.
+#[cfg(feature="std")]
+pub(crate) struct GenericModelInfo {
+    pub model_number: u16,
+    pub fw_version: u8,
 }
-                      
-impl Register for GenericModelNumber {
-    const SIZE: u8 =  2;
+
+#[cfg(feature="std")]
+impl Register for GenericModelInfo {
+    const SIZE: u8 = 3;
     const ADDRESS: u8 = 0x00;
 }
-    
-impl ReadRegister for GenericModelNumber {
+
+#[cfg(feature="std")]
+impl ReadRegister for GenericModelInfo {
     fn deserialize(bytes: &[u8]) -> Self {
-        assert_eq!(bytes.len(), 2);
-        GenericModelNumber(bytes[0] as u16 | (bytes[1] as u16) << 8)        
+        assert_eq!(bytes.len(), 3);
+        GenericModelInfo {
+            model_number: bytes[0] as u16 | (bytes[1] as u16) << 8,
+            fw_version: bytes[2],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::protocol1::*;
+
+    #[test]
+    fn direction_bit() {
+        assert_eq!(decode_direction_bit(0x0123), 0x123);
+        assert_eq!(decode_direction_bit(0x0523), -0x123);
+        assert_eq!(encode_direction_bit(0x123), 0x0123);
+        assert_eq!(encode_direction_bit(-0x123), 0x0523);
+        assert_eq!(encode_direction_bit(-5000), 0x07ff);
+    }
+
+    #[test]
+    fn angle_limits() {
+        for mode in [crate::OperatingMode::Position, crate::OperatingMode::Velocity, crate::OperatingMode::ExtendedPosition].iter() {
+            let (cw, ccw) = encode_angle_limits(*mode, 4095, true).unwrap();
+            assert_eq!(decode_angle_limits(cw, ccw, 4095, true), *mode);
+        }
+        assert_eq!(encode_angle_limits(crate::OperatingMode::ExtendedPosition, 1023, false), None);
+        assert_eq!(encode_angle_limits(crate::OperatingMode::Pwm, 1023, false), None);
+        assert_eq!(decode_angle_limits(1023, 1023, 1023, false), crate::OperatingMode::Position);
+    }
+
+    #[test]
+    fn processing_error_flags() {
+        let error = ProcessingError::decode(0x04).unwrap().unwrap();
+        assert!(error.overheating_error());
+        assert!(!error.range_error());
+        assert_eq!(error, ProcessingError::OVERHEATING);
+
+        let error = ProcessingError::decode(0x28).unwrap().unwrap();
+        assert_eq!(error, ProcessingError::RANGE | ProcessingError::OVERLOAD);
+        assert!(error.contains(ProcessingError::OVERLOAD) && !error.overheating_error());
+        assert_eq!(ProcessingError::decode(0x80), Err(()));
+    }
+
+    #[test]
+    fn position_limits() {
+        assert_eq!(decode_position_limits(100, 900, 1023, false), (100, 900));
+        assert_eq!(decode_position_limits(0, 0, 1023, false), (0, 1023));
+        assert_eq!(decode_position_limits(4095, 4095, 4095, true), (-28672, 28672));
+    }
+
+    #[cfg(feature="std")]
+    #[test]
+    fn scan_ids() {
+        use crate::mock::*;
+
+        let mut mock = MockInterface::new();
+        mock.queue_timeout();
+        mock.queue_response(&protocol1_status(2, 0, &[]));
+        mock.queue_response(&protocol1_status(2, 0, &[12, 0, 0x18]));
+        mock.queue_response(&protocol1_status(3, 0, &[]));
+        mock.queue_response(&protocol1_status(3, 0, &[29, 0, 0x24]));
+
+        let options = crate::ScanOptions{
+            baud_rate: crate::BaudRate::Baud57600,
+            ids: 1..=5,
+            timeout: crate::lib::time::Duration::from_millis(2),
+            max_servos: Some(2),
+        };
+        let servos = crate::scan(&mut mock, &options).unwrap();
+
+        // ID 1 timed out, and the scan stopped after finding two servos
+        assert_eq!(servos.len(), 2);
+        match (&servos[0], &servos[1]) {
+            (crate::ServoProtocol::Protocol1(first), crate::ServoProtocol::Protocol1(second)) => {
+                assert_eq!((first.id, first.model_number, first.fw_version), (ServoID::new(2), 12, 0x18));
+                assert_eq!((second.id, second.model_number, second.fw_version), (ServoID::new(3), 29, 0x24));
+                assert_eq!(first.baud_rate, crate::BaudRate::Baud57600);
+            },
+            _ => panic!("expected protocol 1 servos"),
+        }
+        mock.assert_sent_exactly(&[
+            &protocol1_packet(1, 0x01, &[]),
+            &protocol1_packet(2, 0x01, &[]),
+            &protocol1_packet(2, 0x02, &[0, 3]),
+            &protocol1_packet(3, 0x01, &[]),
+            &protocol1_packet(3, 0x02, &[0, 3]),
+        ]);
+        assert_eq!(mock.read_timeouts()[0], Some(options.timeout));
+        assert_eq!(mock.baud_rates(), &[crate::BaudRate::Baud57600]);
+        mock.assert_all_read();
+    }
+
+    #[cfg(feature="std")]
+    #[test]
+    fn scan_skips_broadcast_ids() {
+        use crate::mock::*;
+
+        let mut mock = MockInterface::new();
+        let options = crate::ScanOptions{ids: 252..=255, ..crate::ScanOptions::default()};
+        assert!(crate::protocol1::scan(&mut mock, &options).unwrap().is_empty());
+        mock.assert_sent_exactly(&[&protocol1_packet(252, 0x01, &[]), &protocol1_packet(253, 0x01, &[])]);
+    }
+
+    #[cfg(feature="std")]
+    #[test]
+    fn malformed_status() {
+        use crate::mock::*;
+        use crate::Servo;
+
+        // The broadcast ID is rejected even with a valid checksum
+        assert_eq!(<instruction::Pong as Status>::deserialize(&protocol1_status(0xfe, 0, &[])), Err(Error::Format{id: None, error: FormatError::ID}));
+        assert_eq!(<instruction::Pong as Status>::deserialize(&protocol1_status(0xfe, 0x20, &[])), Err(Error::Format{id: None, error: FormatError::ID}));
+
+        // A length longer than any expected status is rejected before reading the rest
+        let mut mock = MockInterface::new();
+        mock.queue_response(&[0xff, 0xff, 0x01, 0xff]);
+        let mut servo = crate::dynamixel::ax12::AX12::new(ServoID::new(1), crate::BaudRate::Baud1000000);
+        assert_eq!(servo.get_position(&mut mock), Err(crate::Error::Format{id: Some(1), error: crate::FormatError::Protocol1(FormatError::Length)}));
+        mock.assert_all_read();
+    }
+
+    #[cfg(feature="std")]
+    #[test]
+    fn enumerate_timeouts() {
+        use crate::mock::*;
+        use crate::lib::time::Duration;
+
+        let timeouts = crate::Timeouts{
+            status: Duration::from_millis(20),
+            ping: Duration::from_millis(5),
+            eeprom_write: Duration::from_millis(300),
+        };
+        let mut mock = MockInterface::new();
+        mock.set_timeouts(timeouts);
+        mock.queue_response(&protocol1_status(3, 0, &[]));
+        mock.queue_timeout();
+        mock.queue_response(&protocol1_status(3, 0, &[12, 0, 0x18]));
+
+        // Pongs are waited for with the ping timeout, and the model information with the status timeout
+        assert_eq!(enumerate(&mut mock).unwrap().len(), 1);
+        assert_eq!(mock.read_timeouts(), &[Some(timeouts.ping), Some(timeouts.ping), Some(timeouts.status)]);
+    }
+
+    #[cfg(feature="std")]
+    #[test]
+    fn status_return_level() {
+        use crate::mock::*;
+        use crate::BaudRate;
+        use crate::Servo;
+
+        let mut servo = crate::dynamixel::ax12::AX12::new(crate::protocol1::ServoID::new(1), BaudRate::Baud1000000);
+
+        // Reads time out at level 0, the pong reveals the servo is there
+        let mut mock = MockInterface::new();
+        mock.queue_timeout();
+        mock.queue_response(&protocol1_status(1, 0, &[]));
+        assert_eq!(servo.read_status_return_level(&mut mock), Ok(crate::StatusReturnLevel::Ping));
+        mock.assert_sent_exactly(&[&protocol1_packet(1, 0x02, &[16, 1]), &protocol1_packet(1, 0x01, &[])]);
+
+        // Reads fail without sending anything at level 0
+        let mut mock = MockInterface::new();
+        assert_eq!(servo.get_position(&mut mock), Err(crate::Error::Communication{id: Some(1), error: CommunicationError::NoStatus}));
+        mock.assert_sent_exactly(&[]);
+
+        // Writes are not answered, so no status is read
+        let mut mock = MockInterface::new();
+        servo.set_enable_torque(&mut mock, true).unwrap();
+        servo.assume_status_return_level(crate::StatusReturnLevel::Read);
+        servo.set_enable_torque(&mut mock, false).unwrap();
+        assert!(mock.read_timeouts().is_empty());
+
+        let mut mock = MockInterface::new();
+        mock.queue_response(&protocol1_status(1, 0, &[2]));
+        assert_eq!(servo.read_status_return_level(&mut mock), Ok(crate::StatusReturnLevel::All));
+        assert_eq!(servo.status_return_level(), crate::StatusReturnLevel::All);
+
+        let mut mock = MockInterface::new();
+        crate::protocol1::broadcast_write_data_raw(&mut mock, BaudRate::Baud1000000, 24, [0]).unwrap();
+        mock.assert_sent_exactly(&[&protocol1_packet(0xfe, 0x03, &[24, 0])]);
+        assert!(mock.read_timeouts().is_empty());
+    }
+
+    #[cfg(feature="std")]
+    #[test]
+    fn bulk_read() {
+        use crate::mock::*;
+        use crate::BaudRate;
+        use crate::lib::time::Duration;
+
+        let mut mock = MockInterface::new();
+        mock.queue_response(&protocol1_status(1, 0, &[0x00, 0x02]));
+        mock.queue_timeout();
+        mock.queue_response(&protocol1_status(3, 0, &[0xff, 0x03]));
+
+        let timing = crate::ReplyTiming::new(BaudRate::Baud1000000, Duration::from_millis(1));
+        let (fast, slow) = (Duration::ZERO, Duration::from_micros(500));
+        let reads = [(crate::protocol1::ServoID::new(1), 36, slow), (crate::protocol1::ServoID::new(2), 36, slow), (crate::protocol1::ServoID::new(3), 36, fast)];
+        let replies = crate::protocol1::bulk_read_raw::<_, 2>(&mut mock, &timing, &reads).unwrap();
+
+        assert_eq!(replies, vec![Ok([0x00, 0x02]), Err(crate::protocol1::Error::Communication{id: None, error: CommunicationError::TimedOut}), Ok([0xff, 0x03])]);
+        let packet = protocol1_packet(0xfe, 0x92, &[0x00, 2, 1, 36, 2, 2, 36, 2, 3, 36]);
+        mock.assert_sent_exactly(&[&packet]);
+
+        // Every reply gets a deadline from the return delay time of its servo
+        let first = timing.deadline(slow, 8) + timing.transmission_time(packet.len());
+        assert_eq!(mock.read_timeouts(), &[Some(first), Some(first), Some(timing.deadline(slow, 8)), Some(timing.deadline(fast, 8)), Some(timing.deadline(fast, 8))]);
     }
 }
@@ -61,6 +61,24 @@ macro_rules! register_impl2{
             const ADDRESS: u16 = $address;
         }
     };
+    ($name:ident, u16, $address:expr) => {
+        #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+        pub struct $name(u16);
+        
+        impl crate::protocol2::Register for $name {
+            const SIZE: u16 = 2;
+            const ADDRESS: u16 = $address;
+        }
+    };
+    ($name:ident, u32, $address:expr) => {
+        #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+        pub struct $name(u32);
+        
+        impl crate::protocol2::Register for $name {
+            const SIZE: u16 = 4;
+            const ADDRESS: u16 = $address;
+        }
+    };
     ($name:ident, i32, $address:expr) => {
         #[derive(Debug, PartialEq, Eq, Clone, Copy)]
         pub struct $name(i32);
@@ -114,6 +132,16 @@ macro_rules! read_register_impl2{
             }
         }
     };
+    ($name:ident, u16) => {
+        impl ReadRegister for $name {}
+        
+        impl crate::protocol2::ReadRegister for $name {
+            fn deserialize(data: &[u8]) -> Self {
+                assert_eq!(data.len(), 2);
+                $name(data[0] as u16 | ((data[1] as u16) << 8))
+            }
+        }
+    };
     ($name:ident, u32) => {
         impl ReadRegister for $name {}
 
@@ -173,6 +201,15 @@ macro_rules! write_register_impl2{
             }    
         }
     };
+    ($name:ident, u16) => {
+        impl WriteRegister for $name {}
+        
+        impl crate::protocol2::WriteRegister for $name {
+            fn serialize(&self) -> [u8; 4] {
+                [self.0 as u8, (self.0 >> 8) as u8, 0, 0]
+            }    
+        }
+    };
     ($name:ident, u32) => {
         impl WriteRegister for $name {}
         
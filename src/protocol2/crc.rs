@@ -1,7 +1,7 @@
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
-pub(crate) struct CRC(u16);
+pub(crate) struct Crc(u16);
 
-impl CRC {
+impl Crc {
     const CRC_TABLE: [u16; 256] = [
         0x0000, 0x8005, 0x800F, 0x000A, 0x801B, 0x001E, 0x0014, 0x8011,
         0x8033, 0x0036, 0x003C, 0x8039, 0x0028, 0x802D, 0x8027, 0x0022,
@@ -38,11 +38,12 @@ impl CRC {
     ];
 
     pub fn new() -> Self {
-        CRC(0)
+        Crc(0)
     }
     
+    #[cfg(any(test, feature="std"))]
     pub fn calc(data: &[u8]) -> Self {
-        let mut crc = CRC::new();
+        let mut crc = Crc::new();
         crc.add(data);
         crc
     }
@@ -55,8 +56,8 @@ impl CRC {
     }
 }
 
-impl From<CRC> for u16 {
-    fn from(crc: CRC) -> u16 {
+impl From<Crc> for u16 {
+    fn from(crc: Crc) -> u16 {
         crc.0
     }
 }
@@ -69,7 +70,7 @@ mod tests {
     fn calculate_checksum_raw() {
         let data = [0xFF, 0xFF, 0xFD, 0x00, 0x01, 0x03, 0x00, 0x01];
         
-        let crc = CRC::calc(&data);
+        let crc = Crc::calc(&data);
         assert_eq!(u16::from(crc), 0x4E19);
     }
 }
@@ -0,0 +1,16 @@
+pub trait Register: crate::protocol2::Register {}
+pub trait ReadRegister: crate::protocol2::ReadRegister {}
+pub trait WriteRegister: crate::protocol2::WriteRegister {}
+
+rw_reg2!(OperatingMode, u8, 11);
+rw_reg2!(MaxPositionLimit, i32, 48);
+rw_reg2!(MinPositionLimit, i32, 52);
+rw_reg2!(TorqueEnable, bool, 64);
+rw_reg2!(Led, bool, 65);
+rw_reg2!(GoalVelocity, i32, 104);
+rw_reg2!(GoalPosition, i32, 116);
+r_reg2!(PresentCurrent, i16, 126);
+r_reg2!(PresentVelocity, i32, 128);
+r_reg2!(PresentPosition, i32, 132);
+r_reg2!(PresentInputVoltage, u16, 144);
+r_reg2!(PresentTemperature, u8, 146);
@@ -0,0 +1,69 @@
+//! A generic protocol 2 servo
+//!
+//! Used for servos with a model number that is not recognized.
+//! The control table is the one used by the X series (e.g. XM430 and XH430), which is shared by most protocol 2 servos,
+//! and positions are converted with `ENCODER`, assuming a resolution of 4096 steps per revolution.
+//! Servos reporting load instead of current, like the XL430, have their own type in `dynamixel::xl430`.
+//! The unit of Present Current differs between models, so `get_current` needs a servo created with `from_info` from a model with a known `current_unit`.
+//! Use `read_raw` and `write_raw` to access the rest of the control table.
+
+pub mod control_table;
+
+use crate::Interface;
+use crate::units::Amperes;
+
+protocol2_servo!(GenericServo, control_table::WriteRegister, control_table::ReadRegister, 64, 68, 70);
+
+/// The position encoder, 4096 steps per revolution with 0 radians in the center.
+pub const ENCODER: crate::units::Encoder = crate::units::Encoder{resolution: 4096, zero_offset: 2048, range: crate::units::Radians(2.0 * crate::lib::f32::consts::PI)};
+
+/// Unit of goal velocity and present velocity, in rpm.
+pub(crate) const VELOCITY_UNIT: f32 = 0.229;
+
+/// The operating modes and their values of the operating mode register
+const OPERATING_MODES: &[(crate::OperatingMode, u8)] = &[
+    (crate::OperatingMode::Current, 0),
+    (crate::OperatingMode::Velocity, 1),
+    (crate::OperatingMode::Position, 3),
+    (crate::OperatingMode::ExtendedPosition, 4),
+    (crate::OperatingMode::CurrentBasedPosition, 5),
+    (crate::OperatingMode::Pwm, 16),
+];
+
+x_series_servo!(impl Servo for GenericServo with control_table, OPERATING_MODES {
+    fn get_current(&mut self, interface: &mut I) -> Result<Amperes, crate::Error> {
+        // The unit differs between models, and is only known for servos created from a registered model
+        let unit = self.model.and_then(|model| model.current_unit).ok_or(crate::Error::Unsupported(crate::Capability::Current))?;
+        let current = i16::from(self.read::<control_table::PresentCurrent>(interface)?);
+        Ok(Amperes(f32::from(current) * unit.value()))
+    }
+});
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature="std")]
+    #[test]
+    fn current_unit_of_model() {
+        use crate::mock::*;
+        use crate::Servo;
+
+        let info = crate::protocol2::ServoInfo{
+            baud_rate: crate::BaudRate::Baud57600,
+            model_number: 1000,
+            fw_version: 0x26,
+            id: crate::protocol2::ServoID::new(1),
+        };
+        let mut mock = MockInterface::new();
+        mock.queue_response(&protocol2_status(1, 0, &[0x64, 0x00]));
+
+        let mut servo = crate::protocol2::generic::GenericServo::from_info(&info);
+        assert_eq!(servo.model().unwrap().name, "XH430-W350");
+        assert_eq!(servo.get_current(&mut mock), Ok(crate::units::Amperes(0.134)));
+        mock.assert_sent_exactly(&[&protocol2_packet(1, 0x02, &[126, 0, 2, 0])]);
+
+        // Without a model the unit is unknown
+        let mut servo = crate::protocol2::generic::GenericServo::new(crate::protocol2::ServoID::new(1), crate::BaudRate::Baud57600);
+        assert_eq!(servo.get_current(&mut mock), Err(crate::Error::Unsupported(crate::Capability::Current)));
+        mock.assert_all_read();
+    }
+}
@@ -8,7 +8,7 @@ pub(crate) struct Ping {
 
 impl Ping {
     pub fn new(id: PacketID) -> Self {
-        Ping{id: id}
+        Ping{id}
     }
 }
 
@@ -38,7 +38,7 @@ impl Status for Pong {
     fn deserialize(id: ServoID, parameters: &[u8]) -> Self {
         assert_eq!(parameters.len(), 3);
         Pong {
-            id: id,
+            id,
             model_number: (parameters[0] as u16) | (parameters[1] as u16) << 8,
             fw_version: parameters[2],
         }
@@ -53,7 +53,7 @@ pub(crate) struct Read<T: ReadRegister> {
 
 impl<T: ReadRegister> Read<T> {
     pub fn new(id: PacketID) -> Self {
-        Read{id: id, phantom: crate::lib::marker::PhantomData}
+        Read{id, phantom: crate::lib::marker::PhantomData}
     }
 }
 
@@ -87,7 +87,7 @@ impl<T: ReadRegister> Status for ReadResponse<T> {
 
     fn deserialize(id: ServoID, parameters: &[u8]) -> Self{
         ReadResponse{
-            id: id,
+            id,
             value: T::deserialize(parameters)
         }
     }
@@ -102,7 +102,7 @@ pub(crate) struct Write<T: WriteRegister> {
 
 impl<T: WriteRegister> Write<T> {
     pub fn new(id: PacketID, data: T) -> Self {
-        Write{id: id, data: data}
+        Write{id, data}
     }
 }
 
@@ -127,6 +127,86 @@ impl<T: WriteRegister> Instruction for Write<T>{
     }
 }
 
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub(crate) struct ReadRaw<const N: usize> {
+    id: PacketID,
+    address: u16,
+}
+
+impl<const N: usize> ReadRaw<N> {
+    pub fn new(id: PacketID, address: u16) -> Self {
+        assert!(N <= 4);
+        ReadRaw{id, address}
+    }
+}
+
+impl<const N: usize> Instruction for ReadRaw<N> {
+    const PARAMETERS: u16 = 4;
+    const INSTRUCTION_VALUE: u8 = 0x02;
+
+    fn id(&self) -> PacketID {
+        self.id
+    }
+
+    fn parameter(&self, index: usize) -> u8 {
+        match index {
+            0 => self.address as u8,
+            1 => (self.address >> 8) as u8,
+            2 => N as u8,
+            3 => (N >> 8) as u8,
+            x => panic!("Read instruction parameter indexed with {}, only 4 parameters exists", x),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub(crate) struct ReadRawResponse<const N: usize> {
+    pub id: ServoID,
+    pub data: [u8; N],
+}
+
+impl<const N: usize> Status for ReadRawResponse<N> {
+    const PARAMETERS: u16 = N as u16;
+
+    fn deserialize(id: ServoID, parameters: &[u8]) -> Self{
+        let mut data = [0u8; N];
+        data.copy_from_slice(parameters);
+        ReadRawResponse{id, data}
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub(crate) struct WriteRaw<const N: usize> {
+    id: PacketID,
+    address: u16,
+    data: [u8; N],
+}
+
+impl<const N: usize> WriteRaw<N> {
+    pub fn new(id: PacketID, address: u16, data: [u8; N]) -> Self {
+        assert!(N <= 4);
+        WriteRaw{id, address, data}
+    }
+}
+
+impl<const N: usize> Instruction for WriteRaw<N>{
+    const PARAMETERS: u16 = 2 + N as u16;
+    const INSTRUCTION_VALUE: u8 = 0x03;
+
+    fn id(&self) -> PacketID {
+        self.id
+    }
+
+    fn parameter(&self, index: usize) -> u8 {
+        match index {
+            0 => self.address as u8,
+            1 => (self.address >> 8) as u8,
+            x if x < 2 + N => self.data[x-2],
+            x => panic!("Write instruction parameter indexed with {}, only {} parameters exists", x, 2 + N),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub(crate) struct WriteResponse {
     pub id: ServoID,
@@ -137,7 +217,43 @@ impl Status for WriteResponse {
     
     fn deserialize(id: ServoID, parameters: &[u8]) -> Self {
         assert_eq!(parameters.len(), 0);
-        WriteResponse {id: id}
+        WriteResponse {id}
+    }
+}
+
+/// Read `length` bytes starting at `address` from several servos
+///
+/// The number of servos varies, so the packet is built in a `Vec`.
+#[cfg(feature="std")]
+pub(crate) struct SyncRead<'a> {
+    pub address: u16,
+    pub length: u16,
+    pub ids: &'a [ServoID],
+}
+
+#[cfg(feature="std")]
+impl<'a> SyncRead<'a> {
+    pub const INSTRUCTION_VALUE: u8 = 0x82;
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut parameters = vec![self.address as u8, (self.address >> 8) as u8, self.length as u8, (self.length >> 8) as u8];
+        parameters.extend(self.ids.iter().map(|id| u8::from(*id)));
+
+        // Stuff every 0xfd following 0xff 0xff, the header pattern
+        let mut body = vec![Self::INSTRUCTION_VALUE];
+        for b in parameters {
+            body.push(b);
+            if body.ends_with(&[0xff, 0xff, 0xfd]) {
+                body.push(0xfd);
+            }
+        }
+
+        let length = body.len() as u16 + 2;
+        let mut packet = vec![0xff, 0xff, 0xfd, 0x00, u8::from(PacketID::Broadcast), length as u8, (length >> 8) as u8];
+        packet.extend_from_slice(&body);
+        let crc = u16::from(crc::Crc::calc(&packet));
+        packet.extend_from_slice(&[crc as u8, (crc >> 8) as u8]);
+        packet
     }
 }
 
@@ -148,7 +264,6 @@ mod tests {
     
     use crate::protocol2::*;
     use crate::protocol2::instruction::*;
-    use crate::pro;
 
     #[test]
     fn test_ping() {
@@ -235,6 +350,19 @@ mod tests {
 
     }
 
+    #[test]
+    fn test_write_raw() {
+        let mut array = [0u8; 16];
+        let write = WriteRaw::new(PacketID::unicast(1), 596, [0xcd, 0xab, 0x00, 0x00]);
+        for (i, b) in write.serialize().enumerate() {
+            array[i] = b;
+        }
+        assert_eq!(
+            array,
+            [0xff, 0xff, 0xfd, 0x00, 0x01, 0x09, 0x00, 0x03, 0x54, 0x02, 0xcd, 0xab, 0x00, 0x00, 0x0d, 0xe5]
+        );
+    }
+
     #[test]
     fn test_write_response_byte() {
         let mut deserializer = Deserializer::<WriteResponse>::new()
@@ -285,6 +413,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_read_raw() {
+        let mut array = [0u8; 14];
+        let read = ReadRaw::<4>::new(PacketID::unicast(1), 611);
+        for (i, b) in read.serialize().enumerate() {
+            array[i] = b;
+        }
+        assert_eq!(
+            array,
+            [0xff, 0xff, 0xfd, 0x00, 0x01, 0x07, 0x00, 0x02, 611u16 as u8, (611u16 >> 8) as u8, 0x04, 0x00, 27, 249]
+        );
+
+        let mut deserializer = Deserializer::<ReadRawResponse<4>>::new()
+            .deserialize_header([0xff, 0xff, 0xfd, 0x00, 0x01, 0x08, 0x00, 0x55, 0x00])
+            .unwrap();
+
+        assert_eq!(deserializer.deserialize(&[0xa6, 0x00, 0x00, 0x00, 0x8c, 0xc0]), Ok(DeserializationStatus::Finished));
+        assert_eq!(deserializer.build(),
+                   Ok(ReadRawResponse{
+                       id: ServoID::new(0x01),
+                       data: [0xa6, 0x00, 0x00, 0x00],
+                   })
+        );
+    }
+
     #[test]
     fn test_read_response_slice() {
         let mut deserializer = Deserializer::<ReadResponse<crate::pro::control_table::GoalPosition>>::new()
@@ -6,10 +6,11 @@
 pub(crate) mod instruction;
 #[macro_use]
 mod control_table;
-mod crc;
-mod bit_stuffer;
+pub(crate) mod crc;
+pub(crate) mod bit_stuffer;
 
-use log::warn;
+#[cfg(feature="std")]
+use log::{info, warn};
 
 use crate::Interface;
 use crate::CommunicationError;
@@ -19,19 +20,36 @@ use bit_field::BitField;
 use self::bit_stuffer::BitStuffer;
 
 /// Write the instruction on the interface
+///
+/// The packet is collected in a buffer and written with one call, instead of one call per byte.
+/// Packets longer than the buffer are written in buffer sized chunks.
 pub(crate) fn write_instruction<I: Interface, T: Instruction>(interface: &mut I, instruction: T) -> Result<(), CommunicationError> {
+    let mut buffer = [0u8; 32];
+    let mut length = 0;
     for b in instruction.serialize() {
-        interface.write(&[b])?
+        if length == buffer.len() {
+            interface.write(&buffer)?;
+            length = 0;
+        }
+        buffer[length] = b;
+        length += 1;
     }
-    Ok(())
+    interface.write(&buffer[..length])
 }
 
-/// Read a status from the interface
+/// A status, or the error the servo reported in it, and whether the servo set the alert bit to report a hardware error
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub(crate) struct Response<T> {
+    pub status: Result<T, Error>,
+    pub alert: bool,
+}
+
+/// Read a status from the interface, keeping the alert bit
 ///
 /// If no instructions have been sent, there will not be any status to read
-pub(crate) fn read_status<I: Interface, T: Status>(interface: &mut I) -> Result<T, Error> {
+pub(crate) fn read_response<I: Interface, T: Status>(interface: &mut I, timeout: crate::lib::time::Duration) -> Result<Response<T>, Error> {
     let mut header = [0u8; 9];
-    interface.read(&mut header)?;
+    interface.read_with_timeout(&mut header, timeout)?;
     
     let mut deserializer = Deserializer::<T>::new()
         .deserialize_header(header)?;
@@ -41,16 +59,16 @@ pub(crate) fn read_status<I: Interface, T: Status>(interface: &mut I) -> Result<
     loop {
         let remaining_bytes = deserializer.remaining_bytes() as usize;
         if remaining_bytes > 10 {
-            interface.read(&mut body)?;
+            interface.read_with_timeout(&mut body, timeout)?;
             deserializer.deserialize(&body)?;
         } else {
-            interface.read(&mut body[..remaining_bytes])?;
+            interface.read_with_timeout(&mut body[..remaining_bytes], timeout)?;
             deserializer.deserialize(&body[..remaining_bytes])?;
             break;
         }
     }
     
-    Ok(deserializer.build()?)
+    Ok(Response{alert: deserializer.alert(), status: deserializer.build()})
 }
 
 /// Enumerate all protocol 2 servos connected to the interface
@@ -62,7 +80,7 @@ pub fn enumerate<I: Interface>(interface: &mut I) -> Result<Vec<ServoInfo>, Comm
 
     for b in crate::BaudRate::variants() {
 
-        if let Err(_) = interface.set_baud_rate(*b) {
+        if interface.set_baud_rate(*b).is_err() {
             warn!(target: "protocol2", "not able to enumerate devices on baudrate: {}", u32::from(*b));
         }
 
@@ -71,16 +89,18 @@ pub fn enumerate<I: Interface>(interface: &mut I) -> Result<Vec<ServoInfo>, Comm
         write_instruction(interface, ping)?;
 
         loop {
-            match read_status::<I, instruction::Pong>(interface) {
-                Ok(pong) => servos.push(
-                    ServoInfo{
+            match read_response::<I, instruction::Pong>(interface, interface.timeouts().ping).and_then(|response| response.status) {
+                Ok(pong) => {
+                    let info = ServoInfo{
                         baud_rate: *b,
                         model_number: pong.model_number,
                         fw_version: pong.fw_version,
                         id: pong.id,
-                    }
-                ),
-                Err(Error::Communication(CommunicationError::TimedOut)) => break,
+                    };
+                    info!(target: "protocol2", "found {}", info);
+                    servos.push(info);
+                },
+                Err(Error::Communication{error: CommunicationError::TimedOut, ..}) => break,
                 Err(e) => {
                     warn!(target: "protocol2", "received error: {:?} when waiting for enumeration on baud: {}", e, u32::from(*b));
                     break;
@@ -94,74 +114,489 @@ pub fn enumerate<I: Interface>(interface: &mut I) -> Result<Vec<ServoInfo>, Comm
 /// Connect genericly to a protocol 2 servo
 ///
 /// Only offers basic functionality. If you need more functionality use the connect method of the correct servo type instead.
+/// The servo type is decided by looking up the model number in the `registry`, `Error::UnknownModel` is returned if it is not found.
 #[cfg(feature="std")]
-pub fn connect<I: Interface + 'static>(_interface: &mut I, info: ServoInfo) -> Result<Box<dyn (crate::Servo<I>)>, CommunicationError>{
-    match info.model_number {
-        crate::pro::M4210S260R::<I>::MODEL_NUMBER => Ok(Box::new(crate::pro::M4210S260R::<I>::new(info.id, info.baud_rate))),
-        _ => unimplemented!(),
+pub fn connect<I: Interface + 'static>(interface: &mut I, info: ServoInfo) -> Result<Box<dyn crate::Servo<I> + Send>, crate::Error>{
+    match info.model().map(|model| model.control_table) {
+        Some(crate::registry::ControlTable::XL430) => Ok(Box::new(crate::dynamixel::xl430::XL430::<I>::from_info(&info))),
+        Some(crate::registry::ControlTable::Pro) => Ok(Box::new(crate::pro::M4210S260R::<I>::from_info(&info))),
+        Some(crate::registry::ControlTable::Protocol2Generic) => Ok(connect_generic(interface, info)),
+        _ => Err(crate::Error::UnknownModel{model_number: info.model_number, fw_version: info.fw_version}),
     }
 }
 
+/// Connect to a protocol 2 servo using the generic control table
+#[cfg(feature="std")]
+pub fn connect_generic<I: Interface + 'static>(_interface: &mut I, info: ServoInfo) -> Box<dyn crate::Servo<I> + Send> {
+    Box::new(generic::GenericServo::<I>::from_info(&info))
+}
+
+/// Write the given data `register` to every servo on the interface at `baud_rate`.
+///
+/// Broadcast instructions are never answered, so this returns as soon as the instruction is written.
+pub fn broadcast_write<I: Interface, W: WriteRegister>(interface: &mut I, baud_rate: crate::BaudRate, register: W) -> Result<(), Error> {
+    interface.set_baud_rate(baud_rate)?;
+    write_instruction(interface, instruction::Write::new(PacketID::Broadcast, register))?;
+    Ok(())
+}
+
+/// Write `N` raw bytes to the control table of every servo on the interface at `baud_rate`, starting at `address`.
+///
+/// At most 4 bytes can be written at a time. Broadcast instructions are never answered.
+pub fn broadcast_write_raw<I: Interface, const N: usize>(interface: &mut I, baud_rate: crate::BaudRate, address: u16, data: [u8; N]) -> Result<(), Error> {
+    interface.set_baud_rate(baud_rate)?;
+    write_instruction(interface, instruction::WriteRaw::new(PacketID::Broadcast, address, data))?;
+    Ok(())
+}
+
+/// Read `N` raw bytes starting at `address` from several servos with one sync read instruction
+///
+/// `servos` holds the ID and return delay time of every servo. Each reply gets its own deadline from `timing`,
+/// so a missing servo only costs its own deadline, and replies are matched to the servos by ID. Errors writing the instruction are returned in the outer `Result`,
+/// and the result of every read in the inner, in the order of `servos`.
+/// At most 4 bytes can be read at a time.
+/// This functions returns a Vec and thus requires the `std` feature.
+#[cfg(feature="std")]
+pub fn sync_read_raw<I: Interface, const N: usize>(interface: &mut I, timing: &crate::ReplyTiming, address: u16, servos: &[(ServoID, crate::lib::time::Duration)]) -> Result<Vec<Result<[u8; N], Error>>, CommunicationError> {
+    assert!(N <= 4);
+    interface.set_baud_rate(timing.baud_rate)?;
+    interface.flush();
+
+    let ids: Vec<_> = servos.iter().map(|(id, _)| *id).collect();
+    let packet = instruction::SyncRead{address, length: N as u16, ids: &ids}.serialize();
+    interface.write(&packet)?;
+
+    // The first reply also waits for the instruction to be transmitted
+    let mut instruction_time = timing.transmission_time(packet.len());
+    let ids: Vec<_> = servos.iter().map(|(id, _)| u8::from(*id)).collect();
+    Ok(crate::reply_timing::collect_replies(&ids, |index| {
+        let timeout = timing.deadline(servos[index].1, 11 + N) + crate::lib::mem::take(&mut instruction_time);
+        let response = read_response::<I, instruction::ReadRawResponse<N>>(interface, timeout)?.status?;
+        Ok((u8::from(response.id), response.data))
+    }))
+}
+
+/// Create a protocol 2 servo type, or with `impl Servo for $name with $encoder { .. }` implement `Servo` for it
+///
+/// Only the model specific methods are given to the `impl Servo` form, the rest are generated.
+/// The model must have a `read_position_limits` method, and `$encoder` converts its positions.
 macro_rules! protocol2_servo {
-    ($name:ident, $write:path, $read:path, $model_number:expr) => {
+    (impl Servo for $name:ident with $encoder:path { $($model_specific:tt)* }) => {
+        impl<I: crate::Interface> crate::Servo<I> for $name<I> {
+            $($model_specific)*
+
+            fn set_limit_policy(&mut self, policy: crate::LimitPolicy) {
+                self.limits.policy = policy;
+            }
+
+            fn get_limit_policy(&self) -> crate::LimitPolicy {
+                self.limits.policy
+            }
+
+            fn get_position_limits(&mut self, interface: &mut I) -> Result<(crate::units::Radians, crate::units::Radians), crate::Error> {
+                let (min, max) = self.position_limits(interface, Self::read_position_limits)?;
+                Ok(($encoder.to_radians(min), $encoder.to_radians(max)))
+            }
+
+            fn set_timeouts(&mut self, timeouts: Option<crate::Timeouts>) {
+                self.timeouts = timeouts;
+            }
+
+            fn assume_status_return_level(&mut self, level: crate::StatusReturnLevel) {
+                self.status_return_level = level;
+            }
+
+            fn read_status_return_level(&mut self, interface: &mut I) -> Result<crate::StatusReturnLevel, crate::Error> {
+                self.read_status_return_level(interface)
+            }
+
+            fn get_return_delay_time(&mut self, interface: &mut I) -> Result<crate::lib::time::Duration, crate::Error> {
+                self.return_delay_time(interface)
+            }
+
+            fn set_return_delay_time(&mut self, interface: &mut I, delay: crate::lib::time::Duration) -> Result<(), crate::Error> {
+                self.write_return_delay_time(interface, delay)
+            }
+
+            fn hardware_error(&mut self, interface: &mut I) -> Result<crate::protocol2::HardwareError, crate::Error> {
+                Ok(self.hardware_error(interface)?)
+            }
+        }
+    };
+    ($name:ident, $write:path, $read:path, $eeprom_end:expr, $status_return_level:expr, $hardware_error_status:expr, $model_number:expr) => {
+        protocol2_servo!($name, $write, $read, $eeprom_end, $status_return_level, $hardware_error_status);
+
+        impl<I: Interface> $name<I> {
+            pub const MODEL_NUMBER: u16 = $model_number;
+        }
+    };
+    ($name:ident, $write:path, $read:path, $eeprom_end:expr, $status_return_level:expr, $hardware_error_status:expr) => {
         pub struct $name<I: Interface> {
             id: crate::protocol2::ServoID,
             baudrate: crate::BaudRate,
-            interface: crate::lib::marker::PhantomData<I>,
+            limits: crate::limits::PositionLimits,
+            timeouts: Option<crate::Timeouts>,
+            status_return_level: crate::StatusReturnLevel,
+            return_delay: Option<crate::lib::time::Duration>,
+            alert: bool,
+            model: Option<crate::registry::Model>,
+            // Servos don't own an interface, so they are `Send` and `Sync` regardless of `I`
+            interface: crate::lib::marker::PhantomData<fn() -> I>,
         }
 
         impl<I: Interface> $name<I> {
-            pub const MODEL_NUMBER: u16 = $model_number;
+            /// The first address after the EEPROM area of the control table
+            pub const EEPROM_END: u16 = $eeprom_end;
+
+            /// The address of the Status Return Level register
+            const STATUS_RETURN_LEVEL: u16 = $status_return_level;
+
+            /// The address of the Return Delay Time register, the same for every model
+            const RETURN_DELAY_TIME: u16 = 9;
+
+            /// The address of the Hardware Error Status register
+            const HARDWARE_ERROR_STATUS: u16 = $hardware_error_status;
+
+            /// The address of the Operating Mode register, the same for every model
+            const OPERATING_MODE: u16 = 11;
+
+            /// The address of the Torque Enable register, the first after the EEPROM area
+            const TORQUE_ENABLE: u16 = $eeprom_end;
 
             /// Create a new servo without `ping`ing or taking any other measure to make sure it exists.
             pub fn new(id: crate::protocol2::ServoID, baudrate: $crate::BaudRate) -> Self {
                 $name{
-                    id: id,
-                    baudrate: baudrate,
+                    id,
+                    baudrate,
+                    limits: crate::limits::PositionLimits::new(),
+                    timeouts: None,
+                    status_return_level: crate::StatusReturnLevel::All,
+                    return_delay: None,
+                    alert: false,
+                    model: None,
                     interface: crate::lib::marker::PhantomData{},
                 }
             }
-            
+
+            /// Create a servo found by `enumerate` or `scan`, remembering its model from the `registry`
+            pub fn from_info(info: &crate::protocol2::ServoInfo) -> Self {
+                let mut servo = Self::new(info.id, info.baud_rate);
+                servo.model = info.model();
+                servo
+            }
+
+            /// The model of the servo in the `registry`, if created with `from_info`
+            pub fn model(&self) -> Option<crate::registry::Model> {
+                self.model
+            }
+
+            /// The timeouts of this servo, or the interface if not set
+            fn timeouts(&self, interface: &I) -> crate::Timeouts {
+                self.timeouts.unwrap_or_else(|| interface.timeouts())
+            }
+
+            /// The timeout for the status of a write starting at `address`
+            fn write_timeout(&self, interface: &I, address: u16) -> crate::lib::time::Duration {
+                let timeouts = self.timeouts(interface);
+                if address < Self::EEPROM_END {
+                    timeouts.eeprom_write
+                } else {
+                    timeouts.status
+                }
+            }
+
+            /// Forget the cached position limits, so they are read from the servo when next needed.
+            ///
+            /// Needed after changing the limit registers without using `set_operating_mode`.
+            pub fn clear_position_limits(&mut self) {
+                self.limits.cached = None;
+            }
+
+            /// The position limits in raw steps, using `read` to read them from the servo if they're not cached
+            fn position_limits(&mut self, interface: &mut I, read: fn(&mut Self, &mut I) -> Result<(i32, i32), crate::Error>) -> Result<(i32, i32), crate::Error> {
+                if let Some(limits) = self.limits.cached {
+                    return Ok(limits);
+                }
+                let limits = read(self, interface)?;
+                self.limits.cached = Some(limits);
+                Ok(limits)
+            }
+
+            /// Write `value` to the Operating Mode register, forgetting the cached position limits.
+            ///
+            /// The operating mode can only be changed while torque is disabled, so enabled torque is disabled during the write.
+            /// Torque is enabled again also if the write fails, and the error of the write is returned.
+            fn write_operating_mode(&mut self, interface: &mut I, value: u8) -> Result<(), crate::Error> {
+                self.limits.cached = None;
+
+                let [torque_enabled] = self.read_raw::<1>(interface, Self::TORQUE_ENABLE)?;
+                if torque_enabled == 0 {
+                    return Ok(self.write_raw(interface, Self::OPERATING_MODE, [value])?);
+                }
+                self.write_raw(interface, Self::TORQUE_ENABLE, [0])?;
+                let written = self.write_raw(interface, Self::OPERATING_MODE, [value]);
+                let restored = self.write_raw(interface, Self::TORQUE_ENABLE, [1]);
+                written?;
+                Ok(restored?)
+            }
+
+            /// Read a status from the servo, remembering its alert bit
+            fn read_status<T: crate::protocol2::Status>(&mut self, interface: &mut I, timeout: crate::lib::time::Duration) -> Result<T, crate::protocol2::Error> {
+                let response = crate::protocol2::read_response::<I, T>(interface, timeout)?;
+                self.alert = response.alert;
+                response.status
+            }
+
+            /// Whether the alert bit was set in the last status from the servo, meaning it has a hardware error.
+            ///
+            /// Use `hardware_error` to find out which.
+            pub fn alert(&self) -> bool {
+                self.alert
+            }
+
+            /// Read the Hardware Error Status register, telling why the servo raised its alert bit or shut down.
+            pub fn hardware_error(&mut self, interface: &mut I) -> Result<crate::protocol2::HardwareError, crate::protocol2::Error> {
+                let [bits] = self.read_raw::<1>(interface, Self::HARDWARE_ERROR_STATUS)?;
+                Ok(crate::protocol2::HardwareError::from_bits(bits))
+            }
+
+            /// The status return level the servo is assumed to have, `StatusReturnLevel::All` unless told otherwise.
+            pub fn status_return_level(&self) -> crate::StatusReturnLevel {
+                self.status_return_level
+            }
+
+            /// Assume the servo has status return level `level`, without communicating with it.
+            ///
+            /// Writes only wait for a status when the level is `StatusReturnLevel::All`.
+            /// Reads fail with `CommunicationError::NoStatus`, without sending anything, when the level is `StatusReturnLevel::Ping`.
+            pub fn assume_status_return_level(&mut self, level: crate::StatusReturnLevel) {
+                self.status_return_level = level;
+            }
+
+            /// Read the status return level from the servo, and assume it from now on.
+            ///
+            /// A servo not answering reads is pinged, and has `StatusReturnLevel::Ping` if it answers.
+            pub fn read_status_return_level(&mut self, interface: &mut I) -> Result<crate::StatusReturnLevel, crate::Error> {
+                let level = match self.read_raw_unchecked::<1>(interface, Self::STATUS_RETURN_LEVEL) {
+                    Ok([value]) => crate::StatusReturnLevel::from_register(value).ok_or(crate::Error::Format{id: Some(u8::from(self.id)), error: crate::FormatError::RegisterValue})?,
+                    Err(crate::protocol2::Error::Communication{error: crate::CommunicationError::TimedOut, ..}) => {
+                        self.ping(interface)?;
+                        crate::StatusReturnLevel::Ping
+                    },
+                    Err(e) => return Err(e.into()),
+                };
+                self.status_return_level = level;
+                Ok(level)
+            }
+
+            /// Write the status return level to the servo, and assume it from now on.
+            ///
+            /// The write is answered according to the new level.
+            pub fn write_status_return_level(&mut self, interface: &mut I, level: crate::StatusReturnLevel) -> Result<(), crate::protocol2::Error> {
+                self.status_return_level = level;
+                self.write_raw(interface, Self::STATUS_RETURN_LEVEL, [u8::from(level)])
+            }
+
+            /// The return delay time of the servo, read from the servo the first time and cached afterwards.
+            pub fn return_delay_time(&mut self, interface: &mut I) -> Result<crate::lib::time::Duration, crate::Error> {
+                if let Some(delay) = self.return_delay {
+                    return Ok(delay);
+                }
+                let [value] = self.read_raw::<1>(interface, Self::RETURN_DELAY_TIME)?;
+                let delay = crate::reply_timing::return_delay_from_register(value);
+                self.return_delay = Some(delay);
+                Ok(delay)
+            }
+
+            /// Write the return delay time, rounded down to a multiple of 2 µs.
+            ///
+            /// At most 508 µs can be written, larger values give `Error::InvalidValue`.
+            pub fn write_return_delay_time(&mut self, interface: &mut I, delay: crate::lib::time::Duration) -> Result<(), crate::Error> {
+                let value = crate::reply_timing::return_delay_to_register(delay)?;
+                self.return_delay = None;
+                self.write_raw(interface, Self::RETURN_DELAY_TIME, [value])?;
+                self.return_delay = Some(crate::reply_timing::return_delay_from_register(value));
+                Ok(())
+            }
+
             /// Ping the servo, returning `Ok(ServoInfo)` if it exists.
             pub fn ping(&mut self, interface: &mut I) -> Result<crate::protocol2::ServoInfo, crate::protocol2::Error> {
-                interface.set_baud_rate(self.baudrate)?;
-                interface.flush();
+                self.transaction(interface, |servo, interface| {
+                    interface.set_baud_rate(servo.baudrate)?;
+                    interface.flush();
                 
-                let ping = crate::protocol2::instruction::Ping::new(crate::protocol2::PacketID::from(self.id));
-                crate::protocol2::write_instruction(interface, ping)?;
-                let pong = crate::protocol2::read_status::<I, crate::protocol2::instruction::Pong>(interface)?;
-                Ok(
-                    crate::protocol2::ServoInfo{
-                        baud_rate: self.baudrate,
-                        model_number: pong.model_number,
-                        fw_version: pong.fw_version,
-                        id: pong.id,
-                    }
-                )
+                    let ping = crate::protocol2::instruction::Ping::new(crate::protocol2::PacketID::from(servo.id));
+                    crate::protocol2::write_instruction(interface, ping)?;
+                    let timeout = servo.timeouts(interface).ping;
+                    let pong = servo.read_status::<crate::protocol2::instruction::Pong>(interface, timeout)?;
+                    Ok(
+                        crate::protocol2::ServoInfo{
+                            baud_rate: servo.baudrate,
+                            model_number: pong.model_number,
+                            fw_version: pong.fw_version,
+                            id: pong.id,
+                        }
+                    )
+                })
             }
 
             /// Write the given data `register` to the servo.
             pub fn write<W: $write>(&mut self, interface: &mut I, register: W) -> Result<(), crate::protocol2::Error> {
-                interface.set_baud_rate(self.baudrate)?;
-                let write = crate::protocol2::instruction::Write::new(crate::protocol2::PacketID::from(self.id), register);
-                crate::protocol2::write_instruction(interface, write)?;
-                crate::protocol2::read_status::<I, crate::protocol2::instruction::WriteResponse>(interface)?;
-                Ok(())
+                self.transaction(interface, |servo, interface| {
+                    interface.set_baud_rate(servo.baudrate)?;
+                    let timeout = servo.write_timeout(interface, <W as crate::protocol2::Register>::ADDRESS);
+                    let write = crate::protocol2::instruction::Write::new(crate::protocol2::PacketID::from(servo.id), register);
+                    crate::protocol2::write_instruction(interface, write)?;
+                    if !servo.status_return_level.replies_to_writes() {
+                        return Ok(());
+                    }
+                    servo.read_status::<crate::protocol2::instruction::WriteResponse>(interface, timeout)?;
+                    Ok(())
+                })
             }
 
             /// Read data from a register
             pub fn read<R: $read>(&mut self, interface: &mut I) -> Result<R, crate::protocol2::Error> {
-                interface.set_baud_rate(self.baudrate)?;
-                interface.flush();
+                self.transaction(interface, |servo, interface| {
+                    if !servo.status_return_level.replies_to_reads() {
+                        return Err(crate::CommunicationError::NoStatus.into());
+                    }
+                    interface.set_baud_rate(servo.baudrate)?;
+                    interface.flush();
                 
-                let read = crate::protocol2::instruction::Read::<R>::new(crate::protocol2::PacketID::from(self.id));
-                crate::protocol2::write_instruction(interface, read)?;
-                Ok(crate::protocol2::read_status::<I, crate::protocol2::instruction::ReadResponse<R>>(interface)?.value)
+                    let read = crate::protocol2::instruction::Read::<R>::new(crate::protocol2::PacketID::from(servo.id));
+                    crate::protocol2::write_instruction(interface, read)?;
+                    let timeout = servo.timeouts(interface).status;
+                    Ok(servo.read_status::<crate::protocol2::instruction::ReadResponse<R>>(interface, timeout)?.value)
+                })
+            }
+
+            /// Write `N` raw bytes to the control table, starting at `address`.
+            ///
+            /// At most 4 bytes can be written at a time.
+            pub fn write_raw<const N: usize>(&mut self, interface: &mut I, address: u16, data: [u8; N]) -> Result<(), crate::protocol2::Error> {
+                self.transaction(interface, |servo, interface| {
+                    interface.set_baud_rate(servo.baudrate)?;
+                    let timeout = servo.write_timeout(interface, address);
+                    let write = crate::protocol2::instruction::WriteRaw::new(crate::protocol2::PacketID::from(servo.id), address, data);
+                    crate::protocol2::write_instruction(interface, write)?;
+                    if !servo.status_return_level.replies_to_writes() {
+                        return Ok(());
+                    }
+                    servo.read_status::<crate::protocol2::instruction::WriteResponse>(interface, timeout)?;
+                    Ok(())
+                })
+            }
+
+            /// Read `N` raw bytes from the control table, starting at `address`.
+            ///
+            /// At most 4 bytes can be read at a time.
+            pub fn read_raw<const N: usize>(&mut self, interface: &mut I, address: u16) -> Result<[u8; N], crate::protocol2::Error> {
+                if !self.status_return_level.replies_to_reads() {
+                    return Err(crate::protocol2::Error::from(crate::CommunicationError::NoStatus).with_id(self.id));
+                }
+                self.read_raw_unchecked(interface, address)
+            }
+
+            /// Read `N` raw bytes, even if the servo is assumed not to answer reads
+            fn read_raw_unchecked<const N: usize>(&mut self, interface: &mut I, address: u16) -> Result<[u8; N], crate::protocol2::Error> {
+                self.transaction(interface, |servo, interface| {
+                    interface.set_baud_rate(servo.baudrate)?;
+                    interface.flush();
+
+                    let read = crate::protocol2::instruction::ReadRaw::<N>::new(crate::protocol2::PacketID::from(servo.id), address);
+                    crate::protocol2::write_instruction(interface, read)?;
+                    let timeout = servo.timeouts(interface).status;
+                    Ok(servo.read_status::<crate::protocol2::instruction::ReadRawResponse<N>>(interface, timeout)?.data)
+                })
+            }
+
+            /// Run `f`, attaching the ID of the servo to its communication and format errors
+            fn transaction<T>(&mut self, interface: &mut I, f: impl FnOnce(&mut Self, &mut I) -> Result<T, crate::protocol2::Error>) -> Result<T, crate::protocol2::Error> {
+                let id = self.id;
+                f(self, interface).map_err(|e| e.with_id(id))
             }
         }
     };
 }
 
+/// Implement `Servo` for a servo with the control table of the X series, with the registers in the module `$table`
+///
+/// `$modes` maps the supported operating modes to values of the Operating Mode register.
+/// The model specific methods, like the one reading Present Current or Present Load, are given in the body.
+macro_rules! x_series_servo {
+    (impl Servo for $name:ident with $table:ident, $modes:path { $($model_specific:tt)* }) => {
+        impl<I: crate::Interface> $name<I> {
+            fn read_position_limits(&mut self, interface: &mut I) -> Result<(i32, i32), crate::Error> {
+                // The position limit registers are ignored in extended position mode
+                if crate::Servo::get_operating_mode(self, interface)? == crate::OperatingMode::ExtendedPosition {
+                    return Ok((-1_048_575, 1_048_575));
+                }
+                let min = i32::from(self.read::<$table::MinPositionLimit>(interface)?);
+                let max = i32::from(self.read::<$table::MaxPositionLimit>(interface)?);
+                Ok((min, max))
+            }
+        }
+
+        protocol2_servo!(impl Servo for $name with crate::protocol2::generic::ENCODER {
+            $($model_specific)*
+
+            fn set_enable_torque(&mut self, interface: &mut I, enable_torque: bool) -> Result<(), crate::Error> {
+                self.write(interface, $table::TorqueEnable::new(enable_torque))?;
+                Ok(())
+            }
+
+            fn set_position(&mut self, interface: &mut I, value: crate::units::Radians) -> Result<(), crate::Error> {
+                let limits = self.position_limits(interface, Self::read_position_limits)?;
+                let goal_position = self.limits.apply(&crate::protocol2::generic::ENCODER, value, limits)?;
+                Ok(self.write(interface, $table::GoalPosition::new(goal_position))?)
+            }
+
+            fn get_position(&mut self, interface: &mut I) -> Result<crate::units::Radians, crate::Error> {
+                let pos_fixed = i32::from(self.read::<$table::PresentPosition>(interface)?);
+                Ok(crate::protocol2::generic::ENCODER.to_radians(pos_fixed))
+            }
+
+            fn set_goal_velocity(&mut self, interface: &mut I, value: crate::units::RadiansPerSecond) -> Result<(), crate::Error> {
+                let rpm = f64::from(crate::units::Rpm::from(value).value());
+                let goal_velocity = crate::units::to_steps(rpm / f64::from(crate::protocol2::generic::VELOCITY_UNIT)).ok_or(crate::Error::InvalidValue)?;
+                Ok(self.write(interface, $table::GoalVelocity::new(goal_velocity))?)
+            }
+
+            fn get_velocity(&mut self, interface: &mut I) -> Result<crate::units::RadiansPerSecond, crate::Error> {
+                let velocity = i32::from(self.read::<$table::PresentVelocity>(interface)?);
+                Ok(crate::units::RadiansPerSecond::from(crate::units::Rpm(velocity as f32 * crate::protocol2::generic::VELOCITY_UNIT)))
+            }
+
+            fn get_temperature(&mut self, interface: &mut I) -> Result<f32, crate::Error> {
+                Ok(f32::from(u8::from(self.read::<$table::PresentTemperature>(interface)?)))
+            }
+
+            fn get_voltage(&mut self, interface: &mut I) -> Result<f32, crate::Error> {
+                Ok(f32::from(u16::from(self.read::<$table::PresentInputVoltage>(interface)?)) / 10.0)
+            }
+
+            fn set_operating_mode(&mut self, interface: &mut I, mode: crate::OperatingMode) -> Result<(), crate::Error> {
+                let value = $modes.iter()
+                    .find(|(supported, _)| *supported == mode)
+                    .map(|(_, value)| *value)
+                    .ok_or(crate::Error::Unsupported(crate::Capability::OperatingMode(mode)))?;
+                self.write_operating_mode(interface, value)
+            }
+
+            fn get_operating_mode(&mut self, interface: &mut I) -> Result<crate::OperatingMode, crate::Error> {
+                let value = u8::from(self.read::<$table::OperatingMode>(interface)?);
+                $modes.iter()
+                    .find(|(_, mode_value)| *mode_value == value)
+                    .map(|(mode, _)| *mode)
+                    .ok_or(crate::Error::Format{id: Some(u8::from(self.id)), error: crate::FormatError::RegisterValue})
+            }
+        });
+    };
+}
+
+pub mod generic;
+
 pub trait Register {
     const SIZE: u16;
     const ADDRESS: u16;
@@ -188,7 +623,7 @@ pub(crate) trait Instruction {
         let serializer = Serializer{
             pos: 0,
             length: 10 + Self::PARAMETERS,
-            crc: crc::CRC::new(),
+            crc: crc::Crc::new(),
             bit_stuffer: BitStuffer::new(),
             instruction: self,
         };
@@ -200,8 +635,8 @@ pub(crate) trait Instruction {
 
         Serializer{
             pos: 0,
-            length: length,
-            crc: crc::CRC::new(),
+            length,
+            crc: crc::Crc::new(),
             bit_stuffer: BitStuffer::new(),
             instruction: self,
         }
@@ -218,7 +653,7 @@ pub(crate) trait Status {
 pub(crate) struct Serializer<'a, T: Instruction + 'a> {
     pos: usize,
     length: u16,
-    crc: crc::CRC,
+    crc: crc::Crc,
     bit_stuffer: BitStuffer,
     instruction: &'a T,
 }
@@ -231,7 +666,7 @@ impl<'a, T: Instruction + 'a> crate::lib::iter::Iterator for Serializer<'a, T> {
         let next_byte = if should_stuff {
             Some(0xfd)
         } else {
-            let next_byte = match self.pos {
+            match self.pos {
                 0 => Some(0xff),
                 1 => Some(0xff),
                 2 => Some(0xfd),
@@ -244,10 +679,7 @@ impl<'a, T: Instruction + 'a> crate::lib::iter::Iterator for Serializer<'a, T> {
                 x if x == 8+T::PARAMETERS as usize => Some(u16::from(self.crc) as u8),
                 x if x == 9+T::PARAMETERS as usize => Some((u16::from(self.crc) >> 8) as u8),
                 _ => None,
-            };
-            
-
-            next_byte
+            }
         };
 
         if self.pos < 8+T::PARAMETERS as usize {
@@ -275,22 +707,25 @@ pub(crate) struct Deserializer<T: Status> {
 }
 
 impl<T: Status> Deserializer<T> {
-    fn new() -> Self {
+    pub(crate) fn new() -> Self {
         Deserializer {
             phantom: crate::lib::marker::PhantomData{},
         }
     }
     
-    fn deserialize_header(self, data: [u8; 9]) -> Result<BodyDeserializer<T>, FormatError> {
+    pub(crate) fn deserialize_header(self, data: [u8; 9]) -> Result<BodyDeserializer<T>, FormatError> {
         if data[0] != 0xff {return Err(FormatError::Header)};
         if data[1] != 0xff {return Err(FormatError::Header)};
         if data[2] != 0xfd {return Err(FormatError::Header)};
         if data[3] != 0x00 {return Err(FormatError::Header)};
         if data[7] != 0x55 {return Err(FormatError::Instruction)};
+        if data[4] > 252 {return Err(FormatError::ID)};
 
+        // The length counts the instruction, error and the two CRC bytes besides the parameters
         let length = data[5] as u16 | (data[6] as u16) << 8;
+        if length < 4 {return Err(FormatError::Length)};
         
-        let mut crc = crc::CRC::new();
+        let mut crc = crc::Crc::new();
         crc.add(&data);
         
         let mut bit_stuffer = BitStuffer::new();
@@ -304,7 +739,7 @@ impl<T: Status> Deserializer<T> {
             id: ServoID::new(data[4]),
             crc_l: None,
             crc_calc: crc,
-            bit_stuffer: bit_stuffer,
+            bit_stuffer,
             alert: data[8].get_bit(7),
             processing_error: ProcessingError::decode(data[8].get_bits(0..7))?,
             parameters: [0u8; 6],
@@ -319,7 +754,7 @@ pub(crate) struct BodyDeserializer<T: Status> {
     parameter_index: usize,
     id: ServoID,
     crc_l: Option<u8>,
-    crc_calc: crc::CRC,
+    crc_calc: crc::Crc,
     bit_stuffer: BitStuffer,
     alert: bool,
     processing_error: Option<ProcessingError>,
@@ -336,12 +771,19 @@ impl<T: Status> BodyDeserializer<T> {
     pub fn remaining_bytes(&self) -> u16 {
         self.remaining_bytes
     }
+
+    /// Whether the alert bit of the status is set, meaning the servo has a hardware error
+    pub fn alert(&self) -> bool {
+        self.alert
+    }
     
     pub fn build(self) -> Result<T, Error> {
         if !self.is_finished() {
             Err(Error::Unfinished)
         } else if let Some(error) = self.processing_error {
-            Err(Error::Processing(error))
+            Err(Error::Processing{id: self.id, error, alert: self.alert})
+        } else if self.parameter_index != T::PARAMETERS as usize {
+            Err(Error::Format{id: None, error: FormatError::Length})
         } else {
             Ok(T::deserialize(self.id, &self.parameters[..T::PARAMETERS as usize]))
         }
@@ -353,6 +795,9 @@ impl<T: Status> BodyDeserializer<T> {
                 self.bit_stuffer = self.bit_stuffer.add_byte(*b)?;
                 self.remaining_bytes -= 1;
             } else if self.remaining_bytes > 2 {
+                if self.parameter_index >= T::PARAMETERS as usize {
+                    return Err(FormatError::Length);
+                }
                 self.bit_stuffer = self.bit_stuffer.add_byte(*b)?;
                 self.crc_calc.add(&[*b]);
                 self.parameters[self.parameter_index] = *b;
@@ -389,28 +834,100 @@ pub struct ServoInfo {
     pub id: ServoID,
 }
 
+impl ServoInfo {
+    /// Look up the model of the servo in the `registry`
+    pub fn model(&self) -> Option<crate::registry::Model> {
+        crate::registry::lookup(crate::registry::Protocol::Protocol2, self.model_number)
+    }
+}
+
+impl crate::lib::fmt::Display for ServoInfo {
+    fn fmt(&self, f: &mut crate::lib::fmt::Formatter) -> crate::lib::fmt::Result {
+        match self.model() {
+            Some(model) => write!(f, "{}", model.name)?,
+            None => write!(f, "unknown model {:#06x}", self.model_number)?,
+        }
+        write!(f, " with id: {} at baud: {} (firmware version: {})", self.id.0, u32::from(self.baud_rate), self.fw_version)
+    }
+}
+
 
 impl From<crate::CommunicationError> for Error {
     fn from(e: crate::CommunicationError) -> Error {
-        Error::Communication(e)
+        Error::Communication{id: None, error: e}
     }
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum Error {
     Unfinished,
-    Communication(CommunicationError),
-    Format(FormatError),
-    Processing(ProcessingError),
+
+    /// An Error originating from the `Interface`, communicating with the servo with ID `id` if it came from a servo method
+    Communication {
+        id: Option<ServoID>,
+        error: CommunicationError,
+    },
+
+    /// A status could not be decoded, from the servo with ID `id` if it came from a servo method
+    Format {
+        id: Option<ServoID>,
+        error: FormatError,
+    },
+
+    /// The servo with ID `id` reported an error processing the instruction
+    Processing {
+        id: ServoID,
+        error: ProcessingError,
+
+        /// Whether the alert bit was also set, meaning the servo has a hardware error
+        alert: bool,
+    },
+}
+
+impl Error {
+    /// Attach `id` to communication and format errors without one
+    pub(crate) fn with_id(self, id: ServoID) -> Self {
+        match self {
+            Error::Communication{id: None, error} => Error::Communication{id: Some(id), error},
+            Error::Format{id: None, error} => Error::Format{id: Some(id), error},
+            e => e,
+        }
+    }
 }
 
 impl From<protocol2::Error> for crate::Error {
     fn from(e: crate::protocol2::Error) -> crate::Error {
         match e {
             protocol2::Error::Unfinished => crate::Error::Unfinished,
-            protocol2::Error::Communication(ce) => crate::Error::Communication(ce),
-            protocol2::Error::Format(_) => crate::Error::Format,
-            protocol2::Error::Processing(_) => crate::Error::Processing,
+            protocol2::Error::Communication{id, error} => crate::Error::Communication{id: id.map(u8::from), error},
+            protocol2::Error::Format{id, error} => crate::Error::Format{id: id.map(u8::from), error: crate::FormatError::Protocol2(error)},
+            protocol2::Error::Processing{id, error, alert} => crate::Error::Processing{id: u8::from(id), error: crate::ProcessingError::Protocol2(error), alert},
+        }
+    }
+}
+
+impl crate::lib::fmt::Display for Error {
+    fn fmt(&self, f: &mut crate::lib::fmt::Formatter) -> crate::lib::fmt::Result {
+        match self {
+            Error::Unfinished => write!(f, "the status was not finished"),
+            Error::Communication{id: Some(id), ..} => write!(f, "communication with servo {} failed", id.0),
+            Error::Communication{id: None, ..} => write!(f, "communication with the servo failed"),
+            Error::Format{id: Some(id), ..} => write!(f, "invalid status from servo {}", id.0),
+            Error::Format{id: None, ..} => write!(f, "invalid status"),
+            Error::Processing{id, alert: false, ..} => write!(f, "servo {} reported an error", id.0),
+            Error::Processing{id, alert: true, ..} => write!(f, "servo {} reported an error and a hardware error", id.0),
+        }
+    }
+}
+
+#[cfg(feature="std")]
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Unfinished => None,
+            Error::Communication{error, ..} => Some(error),
+            Error::Format{error, ..} => Some(error),
+            Error::Processing{error, ..} => Some(error),
         }
     }
 }
@@ -441,10 +958,45 @@ pub enum ProcessingError {
 
 impl From<FormatError> for Error {
     fn from(e: FormatError) -> Error {
-        Error::Format(e)
+        Error::Format{id: None, error: e}
+    }
+}
+
+impl crate::lib::fmt::Display for FormatError {
+    fn fmt(&self, f: &mut crate::lib::fmt::Formatter) -> crate::lib::fmt::Result {
+        match self {
+            FormatError::Header => write!(f, "invalid header"),
+            FormatError::ID => write!(f, "unexpected servo ID"),
+            FormatError::Length => write!(f, "unexpected length"),
+            FormatError::Instruction => write!(f, "not a status packet"),
+            FormatError::InvalidError(e) => write!(f, "invalid error field {:#04x}", e),
+            FormatError::CRC => write!(f, "invalid CRC"),
+            FormatError::StuffByte => write!(f, "invalid byte stuffing"),
+            FormatError::NotFinished => write!(f, "the packet ended early"),
+        }
+    }
+}
+
+#[cfg(feature="std")]
+impl std::error::Error for FormatError {}
+
+impl crate::lib::fmt::Display for ProcessingError {
+    fn fmt(&self, f: &mut crate::lib::fmt::Formatter) -> crate::lib::fmt::Result {
+        match self {
+            ProcessingError::ResultFail => write!(f, "failed to process the instruction"),
+            ProcessingError::InstructionError => write!(f, "undefined instruction, or action without a registered write"),
+            ProcessingError::CRCError => write!(f, "the CRC of the instruction was wrong"),
+            ProcessingError::DataRangeError => write!(f, "data out of range of the register"),
+            ProcessingError::DataLengthError => write!(f, "data shorter than the register"),
+            ProcessingError::DataLimitError => write!(f, "data outside the limits"),
+            ProcessingError::AccessError => write!(f, "the register can't be accessed"),
+        }
     }
 }
 
+#[cfg(feature="std")]
+impl std::error::Error for ProcessingError {}
+
 impl ProcessingError {
     fn decode(e: u8) -> Result<Option<ProcessingError>, FormatError> {
         match e {
@@ -467,6 +1019,62 @@ impl From<ProcessingError> for u8 {
     }
 }
 
+/// The flags of the Hardware Error Status register
+///
+/// Servos set the alert bit of every status while a hardware error is present, and shut down for the errors enabled in the Shutdown register.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct HardwareError(u8);
+
+impl HardwareError {
+    /// The input voltage is out of the range of the voltage limits
+    pub const INPUT_VOLTAGE: HardwareError = HardwareError(1 << 0);
+    /// The internal temperature is above the temperature limit
+    pub const OVERHEATING: HardwareError = HardwareError(1 << 2);
+    /// The motor encoder is malfunctioning
+    pub const MOTOR_ENCODER: HardwareError = HardwareError(1 << 3);
+    /// The motor has an electrical shock, or the circuit is damaged
+    pub const ELECTRICAL_SHOCK: HardwareError = HardwareError(1 << 4);
+    /// The load has been above the maximum output for a while
+    pub const OVERLOAD: HardwareError = HardwareError(1 << 5);
+
+    /// No errors
+    pub fn empty() -> Self {
+        HardwareError(0)
+    }
+
+    /// The flags of a register value, unknown bits are kept
+    pub fn from_bits(bits: u8) -> Self {
+        HardwareError(bits)
+    }
+
+    pub fn bits(self) -> u8 {
+        self.0
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Whether every flag of `other` is set
+    pub fn contains(self, other: HardwareError) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl crate::lib::ops::BitOr for HardwareError {
+    type Output = HardwareError;
+
+    fn bitor(self, other: HardwareError) -> HardwareError {
+        HardwareError(self.0 | other.0)
+    }
+}
+
+impl From<HardwareError> for u8 {
+    fn from(e: HardwareError) -> u8 {
+        e.0
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
 pub struct ServoID(u8);
 
@@ -514,3 +1122,228 @@ impl From<ServoID> for u8 {
         id.0
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::protocol2::*;
+
+    /// Counts the calls to `write`
+    #[derive(Default)]
+    struct WriteCounter {
+        writes: usize,
+        bytes: usize,
+    }
+
+    impl Interface for WriteCounter {
+        fn set_baud_rate(&mut self, _b: crate::BaudRate) -> Result<(), CommunicationError> {
+            Ok(())
+        }
+
+        fn flush(&mut self) {}
+
+        fn read(&mut self, _data: &mut [u8]) -> Result<(), CommunicationError> {
+            Err(CommunicationError::TimedOut)
+        }
+
+        fn write(&mut self, data: &[u8]) -> Result<(), CommunicationError> {
+            self.writes += 1;
+            self.bytes += data.len();
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn write_instruction_batches() {
+        let mut interface = WriteCounter::default();
+        write_instruction(&mut interface, instruction::WriteRaw::new(PacketID::Broadcast, 116, [0xff, 0xff, 0xfd, 0x00])).unwrap();
+        assert_eq!(interface.writes, 1);
+        assert_eq!(interface.bytes, 17);
+    }
+
+    #[test]
+    fn malformed_status() {
+        let header = |id, length: u8| [0xff, 0xff, 0xfd, 0x00, id, length, 0x00, 0x55, 0x00];
+        assert_eq!(Deserializer::<instruction::ReadRawResponse<1>>::new().deserialize_header(header(0xfe, 6)).err(), Some(FormatError::ID));
+        assert_eq!(Deserializer::<instruction::ReadRawResponse<1>>::new().deserialize_header(header(1, 1)).err(), Some(FormatError::Length));
+
+        // More parameters than expected are rejected before they are stored
+        let mut deserializer = Deserializer::<instruction::ReadRawResponse<1>>::new().deserialize_header(header(1, 12)).unwrap();
+        assert_eq!(deserializer.deserialize(&[0; 10]), Err(FormatError::Length));
+
+        // Fewer parameters than expected
+        let mut crc = crc::Crc::new();
+        crc.add(&header(1, 5));
+        crc.add(&[0x12]);
+        let crc = u16::from(crc);
+        let mut deserializer = Deserializer::<instruction::ReadRawResponse<2>>::new().deserialize_header(header(1, 5)).unwrap();
+        assert_eq!(deserializer.deserialize(&[0x12, crc as u8, (crc >> 8) as u8]), Ok(DeserializationStatus::Finished));
+        assert_eq!(deserializer.build(), Err(Error::Format{id: None, error: FormatError::Length}));
+    }
+
+    #[cfg(feature="std")]
+    #[test]
+    fn alert_with_processing_error() {
+        use crate::mock::*;
+
+        let mut mock = MockInterface::new();
+        mock.queue_response(&protocol2_status(1, 0x80 | 0x07, &[]));
+        let mut servo = generic::GenericServo::new(ServoID::new(1), crate::BaudRate::Baud57600);
+        let error = servo.read_raw::<1>(&mut mock, 11).unwrap_err();
+        assert_eq!(error, Error::Processing{id: ServoID::new(1), error: ProcessingError::AccessError, alert: true});
+        assert!(servo.alert());
+        assert_eq!(crate::Error::from(error).to_string(), "servo 1 reported an error and a hardware error");
+
+        let mut mock = MockInterface::new();
+        mock.queue_response(&protocol2_status(1, 0x80 | 0x07, &[]));
+        let timing = crate::ReplyTiming::new(crate::BaudRate::Baud57600, crate::lib::time::Duration::from_millis(1));
+        let servos = [(ServoID::new(1), crate::lib::time::Duration::from_micros(500))];
+        assert_eq!(sync_read_raw::<_, 1>(&mut mock, &timing, 11, &servos), Ok(vec![Err(error)]));
+    }
+
+    #[cfg(feature="std")]
+    #[test]
+    fn operating_mode_restores_torque() {
+        use crate::mock::*;
+        use crate::Servo;
+
+        let mut mock = MockInterface::new();
+        mock.queue_response(&protocol2_status(1, 0, &[1]));
+        mock.queue_response(&protocol2_status(1, 0, &[]));
+        mock.queue_response(&protocol2_status(1, 0x07, &[]));
+        mock.queue_response(&protocol2_status(1, 0, &[]));
+
+        // The failed mode write doesn't leave the servo with torque disabled
+        let mut servo = generic::GenericServo::new(ServoID::new(1), crate::BaudRate::Baud57600);
+        let error = crate::Error::Processing{id: 1, error: crate::ProcessingError::Protocol2(ProcessingError::AccessError), alert: false};
+        assert_eq!(servo.set_operating_mode(&mut mock, crate::OperatingMode::Velocity), Err(error));
+        mock.assert_sent_exactly(&[
+            &protocol2_packet(1, 0x02, &[64, 0, 1, 0]),
+            &protocol2_packet(1, 0x03, &[64, 0, 0]),
+            &protocol2_packet(1, 0x03, &[11, 0, 1]),
+            &protocol2_packet(1, 0x03, &[64, 0, 1]),
+        ]);
+        mock.assert_all_read();
+    }
+
+    #[cfg(feature="std")]
+    #[test]
+    fn sync_read_malformed_reply() {
+        use crate::mock::*;
+
+        let timing = crate::ReplyTiming::new(crate::BaudRate::Baud57600, crate::lib::time::Duration::from_millis(1));
+        let servos = [(ServoID::new(1), crate::lib::time::Duration::from_micros(500))];
+
+        let mut mock = MockInterface::new();
+        mock.queue_response(&protocol2_status(1, 0, &[1, 2, 3, 4, 5, 6, 7, 8]));
+        assert_eq!(sync_read_raw::<_, 1>(&mut mock, &timing, 132, &servos), Ok(vec![Err(Error::Format{id: None, error: FormatError::Length})]));
+
+        let mut mock = MockInterface::new();
+        mock.queue_response(&protocol2_status(0xff, 0, &[1]));
+        assert_eq!(sync_read_raw::<_, 1>(&mut mock, &timing, 132, &servos), Ok(vec![Err(Error::Format{id: None, error: FormatError::ID})]));
+    }
+
+    #[cfg(feature="std")]
+    #[test]
+    fn timeouts() {
+        use crate::mock::*;
+        use crate::lib::time::Duration;
+        use crate::{BaudRate, Servo, Timeouts};
+
+        let timeouts = Timeouts{
+            status: Duration::from_millis(20),
+            ping: Duration::from_millis(5),
+            eeprom_write: Duration::from_millis(300),
+        };
+        let mut mock = MockInterface::new();
+        mock.set_timeouts(timeouts);
+
+        mock.queue_response(&protocol2_status(2, 0, &[0xfc, 0x03, 0x26]));
+        crate::protocol2::enumerate(&mut mock).unwrap();
+        assert!(mock.read_timeouts().iter().all(|t| *t == Some(timeouts.ping)));
+
+        let mut servo = crate::protocol2::generic::GenericServo::new(crate::protocol2::ServoID::new(2), BaudRate::Baud57600);
+        let mut mock = MockInterface::new();
+        mock.set_timeouts(timeouts);
+        mock.queue_response(&protocol2_status(2, 0, &[]));
+        mock.queue_response(&protocol2_status(2, 0, &[]));
+        servo.write_raw(&mut mock, 8, [1]).unwrap();
+        servo.set_enable_torque(&mut mock, true).unwrap();
+        assert_eq!(mock.read_timeouts().first(), Some(&Some(timeouts.eeprom_write)));
+        assert_eq!(mock.read_timeouts().last(), Some(&Some(timeouts.status)));
+
+        let own = Timeouts{status: Duration::from_millis(1), ..timeouts};
+        servo.set_timeouts(Some(own));
+        let mut mock = MockInterface::new();
+        mock.set_timeouts(timeouts);
+        mock.queue_response(&protocol2_status(2, 0, &[0x00, 0x08, 0x00, 0x00]));
+        servo.get_position(&mut mock).unwrap();
+        assert!(mock.read_timeouts().iter().all(|t| *t == Some(own.status)));
+    }
+
+    #[cfg(feature="std")]
+    #[test]
+    fn status_return_level() {
+        use crate::mock::*;
+        use crate::BaudRate;
+        use crate::Servo;
+
+        let mut mock = MockInterface::new();
+        mock.queue_response(&protocol2_status(1, 0, &[1]));
+
+        let mut servo = crate::protocol2::generic::GenericServo::new(crate::protocol2::ServoID::new(1), BaudRate::Baud57600);
+        assert_eq!(servo.read_status_return_level(&mut mock), Ok(crate::StatusReturnLevel::Read));
+        servo.set_enable_torque(&mut mock, true).unwrap();
+        crate::protocol2::broadcast_write_raw(&mut mock, BaudRate::Baud57600, 64, [0]).unwrap();
+
+        mock.assert_sent_exactly(&[
+            &protocol2_packet(1, 0x02, &[68, 0, 1, 0]),
+            &protocol2_packet(1, 0x03, &[64, 0, 1]),
+            &protocol2_packet(0xfe, 0x03, &[64, 0, 0]),
+        ]);
+        mock.assert_all_read();
+
+        // Invalid register values are rejected
+        mock.queue_response(&protocol2_status(1, 0, &[7]));
+        assert_eq!(servo.read_status_return_level(&mut mock), Err(crate::Error::Format{id: Some(1), error: crate::FormatError::RegisterValue}));
+
+        // Reads fail without sending anything at level 0, and the level can still be read
+        let mut mock = MockInterface::new();
+        servo.assume_status_return_level(crate::StatusReturnLevel::Ping);
+        assert_eq!(servo.read_raw::<1>(&mut mock, 132), Err(crate::protocol2::Error::Communication{id: Some(crate::protocol2::ServoID::new(1)), error: CommunicationError::NoStatus}));
+        mock.assert_sent_exactly(&[]);
+        mock.queue_response(&protocol2_status(1, 0, &[2]));
+        assert_eq!(servo.read_status_return_level(&mut mock), Ok(crate::StatusReturnLevel::All));
+    }
+
+    #[cfg(feature="std")]
+    #[test]
+    fn hardware_error() {
+        use crate::mock::*;
+        use crate::BaudRate;
+        use crate::Servo;
+        use crate::units::Radians;
+
+        let mut mock = MockInterface::new();
+        mock.queue_response(&protocol2_status(1, 0x80, &[0x00, 0x08, 0x00, 0x00]));
+        mock.queue_response(&protocol2_status(1, 0x80, &[0x24]));
+        mock.queue_response(&protocol2_status(1, 0, &[]));
+
+        let mut servo = crate::protocol2::generic::GenericServo::new(crate::protocol2::ServoID::new(1), BaudRate::Baud57600);
+        assert!(!servo.alert());
+        assert_eq!(servo.get_position(&mut mock), Ok(Radians(0.0)));
+        assert!(servo.alert());
+
+        let error = Servo::hardware_error(&mut servo, &mut mock).unwrap();
+        mock.assert_sent(&protocol2_packet(1, 0x02, &[70, 0, 1, 0]));
+        assert_eq!(error, crate::protocol2::HardwareError::OVERHEATING | crate::protocol2::HardwareError::OVERLOAD);
+        assert!(error.contains(crate::protocol2::HardwareError::OVERLOAD));
+        assert!(!error.contains(crate::protocol2::HardwareError::INPUT_VOLTAGE));
+
+        // The alert is cleared by the next status without it
+        servo.set_enable_torque(&mut mock, false).unwrap();
+        assert!(!servo.alert());
+
+        let mut ax12 = crate::dynamixel::ax12::AX12::new(crate::protocol1::ServoID::new(1), BaudRate::Baud1000000);
+        assert_eq!(ax12.hardware_error(&mut mock), Err(crate::Error::Unsupported(crate::Capability::HardwareError)));
+    }
+}
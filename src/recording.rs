@@ -0,0 +1,368 @@
+//! Recording and replaying bus traffic
+//!
+//! `Recorder` wraps any `Interface`, forwarding every call and recording it with a timestamp in a `Recording`.
+//! Recordings can be saved as JSON lines, decoded with the `sniffer`, or fed back with `Replay`,
+//! an `Interface` answering the same call sequence with the recorded results.
+//! This makes it possible to reproduce a bug seen in the field without the hardware.
+//!
+//! This module requires the `std` feature.
+
+use std::io::{self, BufRead, Write};
+use std::time::{Duration, Instant};
+
+use crate::{BaudRate, CommunicationError, Interface};
+
+/// A call to an `Interface`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Operation {
+    SetBaudRate(BaudRate),
+    Flush,
+    Write(Vec<u8>),
+
+    /// A read of `length` bytes, `data` is empty if the read failed
+    Read{length: usize, data: Vec<u8>},
+}
+
+/// A recorded call
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// Time since the recording started
+    pub time: Duration,
+    pub operation: Operation,
+    pub result: Result<(), CommunicationError>,
+}
+
+/// A sequence of recorded calls
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Recording {
+    pub entries: Vec<Entry>,
+}
+
+impl Recording {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The bytes seen on the bus, written and successfully read, in order
+    ///
+    /// This is the byte stream a bus tap would capture, and can be decoded with `sniffer::decode`.
+    /// Bytes received by failed reads are not known, and missing.
+    pub fn traffic(&self) -> Vec<u8> {
+        let mut traffic = Vec::new();
+        for entry in &self.entries {
+            match &entry.operation {
+                Operation::Write(data) | Operation::Read{data, ..} => traffic.extend_from_slice(data),
+                Operation::SetBaudRate(_) | Operation::Flush => (),
+            }
+        }
+        traffic
+    }
+
+    /// Write the recording as JSON lines, one object per call
+    ///
+    /// Each line holds `time_ns`, `op` (`baud`, `flush`, `write` or `read`), and depending on the operation
+    /// `baud`, `length` and `data` as a hex string. Failed calls have an `error`.
+    pub fn write_json_lines<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        for entry in &self.entries {
+            write!(writer, "{{\"time_ns\":{}", entry.time.as_nanos())?;
+            match &entry.operation {
+                Operation::SetBaudRate(b) => write!(writer, ",\"op\":\"baud\",\"baud\":{}", u32::from(*b))?,
+                Operation::Flush => write!(writer, ",\"op\":\"flush\"")?,
+                Operation::Write(data) => write!(writer, ",\"op\":\"write\",\"data\":\"{}\"", hex(data))?,
+                Operation::Read{length, data} => write!(writer, ",\"op\":\"read\",\"length\":{},\"data\":\"{}\"", length, hex(data))?,
+            }
+            match entry.result {
+                Ok(()) => (),
+                Err(CommunicationError::TimedOut) => write!(writer, ",\"error\":\"timed out\"")?,
+                Err(CommunicationError::UnsupportedBaud(b)) => write!(writer, ",\"error\":\"unsupported baud {}\"", u32::from(b))?,
+                Err(CommunicationError::Other) => write!(writer, ",\"error\":\"other\"")?,
+                Err(CommunicationError::NoStatus) => write!(writer, ",\"error\":\"no status\"")?,
+            }
+            writeln!(writer, "}}")?;
+        }
+        Ok(())
+    }
+
+    /// Read a recording written by `write_json_lines`
+    ///
+    /// Only the format written by `write_json_lines` is supported, this is not a general JSON parser.
+    pub fn read_json_lines<R: BufRead>(reader: R) -> io::Result<Self> {
+        let mut entries = Vec::new();
+        for (number, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry = parse_entry(&line)
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("invalid recording entry on line {}", number + 1)))?;
+            entries.push(entry);
+        }
+        Ok(Recording{entries})
+    }
+}
+
+fn hex(data: &[u8]) -> String {
+    data.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[allow(clippy::manual_is_multiple_of)]
+fn parse_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(hex.get(i..i+2)?, 16).ok()).collect()
+}
+
+fn parse_baud_rate(baud: u32) -> Option<BaudRate> {
+    BaudRate::variants().iter().copied().find(|b| u32::from(*b) == baud)
+}
+
+/// The raw value of `key` in a JSON object written by `write_json_lines`, without quotes
+fn value<'a>(line: &'a str, key: &str) -> Option<&'a str> {
+    let start = line.find(&format!("\"{}\":", key))? + key.len() + 3;
+    let rest = &line[start..];
+    if let Some(quoted) = rest.strip_prefix('"') {
+        quoted.split('"').next()
+    } else {
+        rest.split([',', '}']).next()
+    }
+}
+
+fn parse_entry(line: &str) -> Option<Entry> {
+    let time = Duration::from_nanos(value(line, "time_ns")?.parse().ok()?);
+    let operation = match value(line, "op")? {
+        "baud" => Operation::SetBaudRate(parse_baud_rate(value(line, "baud")?.parse().ok()?)?),
+        "flush" => Operation::Flush,
+        "write" => Operation::Write(parse_hex(value(line, "data")?)?),
+        "read" => Operation::Read{length: value(line, "length")?.parse().ok()?, data: parse_hex(value(line, "data")?)?},
+        _ => return None,
+    };
+    let result = match value(line, "error") {
+        None => Ok(()),
+        Some("timed out") => Err(CommunicationError::TimedOut),
+        Some("other") => Err(CommunicationError::Other),
+        Some("no status") => Err(CommunicationError::NoStatus),
+        Some(error) => Err(CommunicationError::UnsupportedBaud(parse_baud_rate(error.strip_prefix("unsupported baud ")?.parse().ok()?)?)),
+    };
+    Some(Entry{time, operation, result})
+}
+
+/// An `Interface` wrapper recording every call
+pub struct Recorder<I> {
+    inner: I,
+    start: Instant,
+    recording: Recording,
+}
+
+impl<I> Recorder<I> {
+    /// Record the calls to `inner`, with time starting now
+    pub fn new(inner: I) -> Self {
+        Recorder{
+            inner,
+            start: Instant::now(),
+            recording: Recording::new(),
+        }
+    }
+
+    pub fn recording(&self) -> &Recording {
+        &self.recording
+    }
+
+    /// Take the recorded calls, recording continues from an empty recording
+    pub fn take_recording(&mut self) -> Recording {
+        std::mem::take(&mut self.recording)
+    }
+
+    pub fn inner(&self) -> &I {
+        &self.inner
+    }
+
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.inner
+    }
+
+    /// Unwrap the interface, returning it with the recording
+    pub fn into_inner(self) -> (I, Recording) {
+        (self.inner, self.recording)
+    }
+
+    fn record(&mut self, operation: Operation, result: Result<(), CommunicationError>) -> Result<(), CommunicationError> {
+        self.recording.entries.push(Entry{time: self.start.elapsed(), operation, result});
+        result
+    }
+}
+
+impl<I: Interface> Interface for Recorder<I> {
+    fn set_baud_rate(&mut self, b: BaudRate) -> Result<(), CommunicationError> {
+        let result = self.inner.set_baud_rate(b);
+        self.record(Operation::SetBaudRate(b), result)
+    }
+
+    fn flush(&mut self) {
+        self.inner.flush();
+        let _ = self.record(Operation::Flush, Ok(()));
+    }
+
+    fn read(&mut self, data: &mut [u8]) -> Result<(), CommunicationError> {
+        let result = self.inner.read(data);
+        let read = if result.is_ok() { data.to_vec() } else { Vec::new() };
+        self.record(Operation::Read{length: data.len(), data: read}, result)
+    }
+
+    fn read_with_timeout(&mut self, data: &mut [u8], timeout: Duration) -> Result<(), CommunicationError> {
+        let result = self.inner.read_with_timeout(data, timeout);
+        let read = if result.is_ok() { data.to_vec() } else { Vec::new() };
+        self.record(Operation::Read{length: data.len(), data: read}, result)
+    }
+
+    fn write(&mut self, data: &[u8]) -> Result<(), CommunicationError> {
+        let result = self.inner.write(data);
+        self.record(Operation::Write(data.to_vec()), result)
+    }
+
+    fn timeouts(&self) -> crate::Timeouts {
+        self.inner.timeouts()
+    }
+}
+
+/// An `Interface` replaying a recording
+///
+/// Every call must match the next recorded call, and gets the recorded result.
+/// Writes must write the recorded bytes, and reads must read the recorded number of bytes.
+/// When a call diverges from the recording, it and every following call fail with `CommunicationError::Other`.
+/// The recorded timing is not reproduced.
+#[derive(Debug, Clone)]
+pub struct Replay {
+    recording: Recording,
+    position: usize,
+    diverged: Option<usize>,
+}
+
+impl Replay {
+    pub fn new(recording: Recording) -> Self {
+        Replay{
+            recording,
+            position: 0,
+            diverged: None,
+        }
+    }
+
+    /// The index of the recorded entry where the calls diverged from the recording
+    pub fn diverged(&self) -> Option<usize> {
+        self.diverged
+    }
+
+    /// The number of recorded calls that have not been replayed
+    pub fn remaining(&self) -> usize {
+        self.recording.entries.len() - self.position
+    }
+
+    /// Panic unless every recorded call has been replayed without diverging
+    pub fn assert_finished(&self) {
+        if let Some(index) = self.diverged {
+            panic!("replay diverged from the recording at entry {}: {:?}", index, self.recording.entries.get(index));
+        }
+        assert!(self.remaining() == 0, "{} recorded calls were not replayed", self.remaining());
+    }
+
+    /// Take the next entry if `matches` accepts its operation
+    fn next(&mut self, matches: impl FnOnce(&Operation) -> bool) -> Option<&Entry> {
+        if self.diverged.is_some() {
+            return None;
+        }
+        match self.recording.entries.get(self.position) {
+            Some(entry) if matches(&entry.operation) => {
+                self.position += 1;
+                self.recording.entries.get(self.position - 1)
+            },
+            _ => {
+                self.diverged = Some(self.position);
+                None
+            },
+        }
+    }
+}
+
+impl Interface for Replay {
+    fn set_baud_rate(&mut self, b: BaudRate) -> Result<(), CommunicationError> {
+        match self.next(|op| *op == Operation::SetBaudRate(b)) {
+            Some(entry) => entry.result,
+            None => Err(CommunicationError::Other),
+        }
+    }
+
+    fn flush(&mut self) {
+        self.next(|op| *op == Operation::Flush);
+    }
+
+    fn read(&mut self, data: &mut [u8]) -> Result<(), CommunicationError> {
+        let length = data.len();
+        match self.next(|op| matches!(op, Operation::Read{length: l, ..} if *l == length)) {
+            Some(Entry{operation: Operation::Read{data: recorded, ..}, result, ..}) => {
+                if result.is_ok() {
+                    data.copy_from_slice(recorded);
+                }
+                *result
+            },
+            _ => Err(CommunicationError::Other),
+        }
+    }
+
+    fn write(&mut self, data: &[u8]) -> Result<(), CommunicationError> {
+        match self.next(|op| matches!(op, Operation::Write(recorded) if recorded == data)) {
+            Some(entry) => entry.result,
+            None => Err(CommunicationError::Other),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::recording::*;
+    use crate::sim::{SimServo, Simulator};
+    use crate::units::Radians;
+    use crate::Servo;
+
+    fn record_session() -> Recording {
+        let mut recorder = Recorder::new(Simulator::new().with_servo(SimServo::ax12(1)));
+        let servos = crate::protocol1::enumerate(&mut recorder).unwrap();
+        let mut servo = crate::connect(&mut recorder, crate::ServoProtocol::Protocol1(servos[0].clone())).unwrap();
+        servo.set_position(&mut recorder, Radians(0.5)).unwrap();
+        recorder.into_inner().1
+    }
+
+    #[test]
+    fn json_lines() {
+        let recording = record_session();
+        assert!(recording.entries.iter().any(|e| e.result == Err(CommunicationError::TimedOut)));
+
+        let mut file = Vec::new();
+        recording.write_json_lines(&mut file).unwrap();
+        assert_eq!(Recording::read_json_lines(&file[..]).unwrap(), recording);
+        assert!(Recording::read_json_lines(&b"{\"time_ns\":0,\"op\":\"write\",\"data\":\"f\"}\n"[..]).is_err());
+    }
+
+    #[test]
+    fn replay() {
+        let recording = record_session();
+
+        let mut replay = Replay::new(recording.clone());
+        let servos = crate::protocol1::enumerate(&mut replay).unwrap();
+        let mut servo = crate::connect(&mut replay, crate::ServoProtocol::Protocol1(servos[0].clone())).unwrap();
+        servo.set_position(&mut replay, Radians(0.5)).unwrap();
+        replay.assert_finished();
+
+        let mut replay = Replay::new(recording);
+        let servos = crate::protocol1::enumerate(&mut replay).unwrap();
+        let mut servo = crate::connect(&mut replay, crate::ServoProtocol::Protocol1(servos[0].clone())).unwrap();
+        assert!(servo.set_position(&mut replay, Radians(-0.5)).is_err());
+        assert!(replay.diverged().is_some());
+    }
+
+    #[test]
+    fn traffic() {
+        let events = crate::sniffer::decode(&record_session().traffic());
+        assert!(events.iter().all(|e| matches!(e, crate::sniffer::Event::Packet(_))));
+        assert_eq!(events.last().unwrap().to_string(), "P1 id 1 Status");
+    }
+}
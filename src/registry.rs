@@ -0,0 +1,270 @@
+//! Registry of known servo models
+//!
+//! Maps model numbers to a human readable name and the properties needed to use the servo.
+//! `connect` uses the registry to decide what servo type to create for a model number.
+//! With the `std` feature, models unknown to this crate can be added with `register`.
+
+/// The protocol a servo model communicates with
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Protocol {
+    Protocol1,
+    Protocol2,
+}
+
+/// The control tables known by this crate
+///
+/// Decides what servo type `connect` will create for a model.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ControlTable {
+    /// Connects as `dynamixel::ax12::AX12`
+    AX12,
+
+    /// Connects as `dynamixel::mx28::MX28`
+    MX28,
+
+    /// Connects as `dynamixel::xl430::XL430`
+    XL430,
+
+    /// Connects as `pro::M4210S260R`
+    Pro,
+
+    /// Connects as `protocol1::generic::GenericServo`
+    Protocol1Generic,
+
+    /// Connects as `protocol2::generic::GenericServo`
+    Protocol2Generic,
+}
+
+impl ControlTable {
+    /// The named registers of the control table, ordered by address
+    pub fn registers(self) -> &'static [Register] {
+        match self {
+            ControlTable::AX12 | ControlTable::Protocol1Generic => AX12_REGISTERS,
+            ControlTable::MX28 => MX28_REGISTERS,
+            ControlTable::Pro => PRO_REGISTERS,
+            ControlTable::Protocol2Generic => X_REGISTERS,
+            ControlTable::XL430 => XL430_REGISTERS,
+        }
+    }
+
+    /// The first address after the EEPROM area, which can only be written while torque is disabled
+    pub fn eeprom_end(self) -> u16 {
+        match self {
+            ControlTable::AX12 | ControlTable::MX28 | ControlTable::Protocol1Generic => 24,
+            ControlTable::Protocol2Generic | ControlTable::XL430 => 64,
+            ControlTable::Pro => 562,
+        }
+    }
+
+    /// The register starting at `address`
+    pub fn register(self, address: u16) -> Option<Register> {
+        self.registers().iter().find(|r| r.address == address).copied()
+    }
+}
+
+/// A register in a control table
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Register {
+    /// The name used in the Robotis e-manual, e.g. "Goal Position"
+    pub name: &'static str,
+
+    pub address: u16,
+
+    /// Size in bytes
+    pub size: u8,
+}
+
+const fn reg(address: u16, size: u8, name: &'static str) -> Register {
+    Register{name, address, size}
+}
+
+const AX12_REGISTERS: &[Register] = &[
+    reg(0, 2, "Model Number"), reg(2, 1, "Firmware Version"), reg(3, 1, "ID"), reg(4, 1, "Baud Rate"),
+    reg(5, 1, "Return Delay Time"), reg(6, 2, "CW Angle Limit"), reg(8, 2, "CCW Angle Limit"), reg(11, 1, "Temperature Limit"),
+    reg(12, 1, "Min Voltage Limit"), reg(13, 1, "Max Voltage Limit"), reg(14, 2, "Max Torque"), reg(16, 1, "Status Return Level"),
+    reg(17, 1, "Alarm LED"), reg(18, 1, "Shutdown"), reg(24, 1, "Torque Enable"), reg(25, 1, "LED"),
+    reg(26, 1, "CW Compliance Margin"), reg(27, 1, "CCW Compliance Margin"), reg(28, 1, "CW Compliance Slope"), reg(29, 1, "CCW Compliance Slope"),
+    reg(30, 2, "Goal Position"), reg(32, 2, "Moving Speed"), reg(34, 2, "Torque Limit"), reg(36, 2, "Present Position"),
+    reg(38, 2, "Present Speed"), reg(40, 2, "Present Load"), reg(42, 1, "Present Voltage"), reg(43, 1, "Present Temperature"),
+    reg(44, 1, "Registered"), reg(46, 1, "Moving"), reg(47, 1, "Lock"), reg(48, 2, "Punch"),
+];
+
+const MX28_REGISTERS: &[Register] = &[
+    reg(0, 2, "Model Number"), reg(2, 1, "Firmware Version"), reg(3, 1, "ID"), reg(4, 1, "Baud Rate"),
+    reg(5, 1, "Return Delay Time"), reg(6, 2, "CW Angle Limit"), reg(8, 2, "CCW Angle Limit"), reg(11, 1, "Temperature Limit"),
+    reg(12, 1, "Min Voltage Limit"), reg(13, 1, "Max Voltage Limit"), reg(14, 2, "Max Torque"), reg(16, 1, "Status Return Level"),
+    reg(17, 1, "Alarm LED"), reg(18, 1, "Shutdown"), reg(20, 2, "Multi Turn Offset"), reg(22, 1, "Resolution Divider"),
+    reg(24, 1, "Torque Enable"), reg(25, 1, "LED"), reg(26, 1, "D Gain"), reg(27, 1, "I Gain"),
+    reg(28, 1, "P Gain"), reg(30, 2, "Goal Position"), reg(32, 2, "Moving Speed"), reg(34, 2, "Torque Limit"),
+    reg(36, 2, "Present Position"), reg(38, 2, "Present Speed"), reg(40, 2, "Present Load"), reg(42, 1, "Present Voltage"),
+    reg(43, 1, "Present Temperature"), reg(44, 1, "Registered"), reg(46, 1, "Moving"), reg(47, 1, "Lock"),
+    reg(48, 2, "Punch"), reg(50, 2, "Realtime Tick"), reg(73, 1, "Goal Acceleration"),
+];
+
+const X_REGISTERS: &[Register] = &[
+    reg(0, 2, "Model Number"), reg(2, 4, "Model Information"), reg(6, 1, "Firmware Version"), reg(7, 1, "ID"),
+    reg(8, 1, "Baud Rate"), reg(9, 1, "Return Delay Time"), reg(10, 1, "Drive Mode"), reg(11, 1, "Operating Mode"),
+    reg(12, 1, "Secondary ID"), reg(13, 1, "Protocol Type"), reg(20, 4, "Homing Offset"), reg(24, 4, "Moving Threshold"),
+    reg(31, 1, "Temperature Limit"), reg(32, 2, "Max Voltage Limit"), reg(34, 2, "Min Voltage Limit"), reg(36, 2, "PWM Limit"),
+    reg(38, 2, "Current Limit"), reg(44, 4, "Velocity Limit"), reg(48, 4, "Max Position Limit"), reg(52, 4, "Min Position Limit"),
+    reg(63, 1, "Shutdown"), reg(64, 1, "Torque Enable"), reg(65, 1, "LED"), reg(68, 1, "Status Return Level"),
+    reg(69, 1, "Registered Instruction"), reg(70, 1, "Hardware Error Status"), reg(76, 2, "Velocity I Gain"), reg(78, 2, "Velocity P Gain"),
+    reg(80, 2, "Position D Gain"), reg(82, 2, "Position I Gain"), reg(84, 2, "Position P Gain"), reg(88, 2, "Feedforward 2nd Gain"),
+    reg(90, 2, "Feedforward 1st Gain"), reg(98, 1, "Bus Watchdog"), reg(100, 2, "Goal PWM"), reg(102, 2, "Goal Current"),
+    reg(104, 4, "Goal Velocity"), reg(108, 4, "Profile Acceleration"), reg(112, 4, "Profile Velocity"), reg(116, 4, "Goal Position"),
+    reg(120, 2, "Realtime Tick"), reg(122, 1, "Moving"), reg(123, 1, "Moving Status"), reg(124, 2, "Present PWM"),
+    reg(126, 2, "Present Current"), reg(128, 4, "Present Velocity"), reg(132, 4, "Present Position"), reg(136, 4, "Velocity Trajectory"),
+    reg(140, 4, "Position Trajectory"), reg(144, 2, "Present Input Voltage"), reg(146, 1, "Present Temperature"),
+];
+
+const XL430_REGISTERS: &[Register] = &[
+    reg(0, 2, "Model Number"), reg(2, 4, "Model Information"), reg(6, 1, "Firmware Version"), reg(7, 1, "ID"),
+    reg(8, 1, "Baud Rate"), reg(9, 1, "Return Delay Time"), reg(10, 1, "Drive Mode"), reg(11, 1, "Operating Mode"),
+    reg(12, 1, "Secondary ID"), reg(13, 1, "Protocol Type"), reg(20, 4, "Homing Offset"), reg(24, 4, "Moving Threshold"),
+    reg(31, 1, "Temperature Limit"), reg(32, 2, "Max Voltage Limit"), reg(34, 2, "Min Voltage Limit"), reg(36, 2, "PWM Limit"),
+    reg(44, 4, "Velocity Limit"), reg(48, 4, "Max Position Limit"), reg(52, 4, "Min Position Limit"), reg(63, 1, "Shutdown"),
+    reg(64, 1, "Torque Enable"), reg(65, 1, "LED"), reg(68, 1, "Status Return Level"), reg(69, 1, "Registered Instruction"),
+    reg(70, 1, "Hardware Error Status"), reg(76, 2, "Velocity I Gain"), reg(78, 2, "Velocity P Gain"), reg(80, 2, "Position D Gain"),
+    reg(82, 2, "Position I Gain"), reg(84, 2, "Position P Gain"), reg(88, 2, "Feedforward 2nd Gain"), reg(90, 2, "Feedforward 1st Gain"),
+    reg(98, 1, "Bus Watchdog"), reg(100, 2, "Goal PWM"), reg(104, 4, "Goal Velocity"), reg(108, 4, "Profile Acceleration"),
+    reg(112, 4, "Profile Velocity"), reg(116, 4, "Goal Position"), reg(120, 2, "Realtime Tick"), reg(122, 1, "Moving"),
+    reg(123, 1, "Moving Status"), reg(124, 2, "Present PWM"), reg(126, 2, "Present Load"), reg(128, 4, "Present Velocity"),
+    reg(132, 4, "Present Position"), reg(136, 4, "Velocity Trajectory"), reg(140, 4, "Position Trajectory"), reg(144, 2, "Present Input Voltage"),
+    reg(146, 1, "Present Temperature"),
+];
+
+const PRO_REGISTERS: &[Register] = &[
+    reg(0, 2, "Model Number"), reg(2, 4, "Model Information"), reg(6, 1, "Firmware Version"), reg(7, 1, "ID"),
+    reg(8, 1, "Baud Rate"), reg(9, 1, "Return Delay Time"), reg(11, 1, "Operating Mode"), reg(13, 4, "Homing Offset"),
+    reg(17, 4, "Moving Threshold"), reg(21, 1, "Temperature Limit"), reg(22, 2, "Max Voltage Limit"), reg(24, 2, "Min Voltage Limit"),
+    reg(26, 4, "Acceleration Limit"), reg(30, 2, "Torque Limit"), reg(32, 4, "Velocity Limit"), reg(36, 4, "Max Position Limit"),
+    reg(40, 4, "Min Position Limit"), reg(48, 1, "Shutdown"), reg(562, 1, "Torque Enable"), reg(563, 1, "LED Red"),
+    reg(564, 1, "LED Green"), reg(565, 1, "LED Blue"), reg(586, 2, "Velocity I Gain"), reg(588, 2, "Velocity P Gain"),
+    reg(594, 2, "Position P Gain"), reg(596, 4, "Goal Position"), reg(600, 4, "Goal Velocity"), reg(604, 2, "Goal Torque"),
+    reg(606, 4, "Goal Acceleration"), reg(610, 1, "Moving"), reg(611, 4, "Present Position"), reg(615, 4, "Present Velocity"),
+    reg(621, 2, "Present Current"), reg(623, 2, "Present Input Voltage"), reg(625, 1, "Present Temperature"), reg(890, 1, "Registered Instruction"),
+    reg(891, 1, "Status Return Level"), reg(892, 1, "Hardware Error Status"),
+];
+
+/// Description of a servo model
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Model {
+    /// The model number reported by the servo
+    pub model_number: u16,
+
+    /// The name Robotis uses for the model, e.g. "XM430-W350"
+    pub name: &'static str,
+
+    pub protocol: Protocol,
+
+    /// The position encoder, used to convert positions to register values
+    pub encoder: crate::units::Encoder,
+
+    /// The unit of Present Current, or `None` if the model doesn't report current or the unit is unknown
+    pub current_unit: Option<crate::units::Amperes>,
+
+    pub control_table: ControlTable,
+}
+
+use crate::dynamixel::ax12::ENCODER as AX;
+use crate::dynamixel::mx28::ENCODER as MX;
+use crate::protocol2::generic::ENCODER as X;
+use crate::dynamixel::xl430::ENCODER as XL;
+use crate::pro::ENCODER as PRO;
+use crate::pro::CURRENT_UNIT as PRO_CURRENT;
+use crate::units::Amperes;
+
+const BUILTIN: &[Model] = &[
+    Model{model_number: 12, name: "AX-12", protocol: Protocol::Protocol1, encoder: AX, current_unit: None, control_table: ControlTable::AX12},
+    Model{model_number: 18, name: "AX-18A", protocol: Protocol::Protocol1, encoder: AX, current_unit: None, control_table: ControlTable::AX12},
+    Model{model_number: 300, name: "AX-12W", protocol: Protocol::Protocol1, encoder: AX, current_unit: None, control_table: ControlTable::AX12},
+    Model{model_number: 24, name: "RX-24F", protocol: Protocol::Protocol1, encoder: AX, current_unit: None, control_table: ControlTable::Protocol1Generic},
+    Model{model_number: 28, name: "RX-28", protocol: Protocol::Protocol1, encoder: AX, current_unit: None, control_table: ControlTable::Protocol1Generic},
+    Model{model_number: 64, name: "RX-64", protocol: Protocol::Protocol1, encoder: AX, current_unit: None, control_table: ControlTable::Protocol1Generic},
+    Model{model_number: 29, name: "MX-28", protocol: Protocol::Protocol1, encoder: MX, current_unit: None, control_table: ControlTable::MX28},
+    Model{model_number: 310, name: "MX-64", protocol: Protocol::Protocol1, encoder: MX, current_unit: None, control_table: ControlTable::MX28},
+    Model{model_number: 320, name: "MX-106", protocol: Protocol::Protocol1, encoder: MX, current_unit: None, control_table: ControlTable::MX28},
+    Model{model_number: 30, name: "MX-28(2.0)", protocol: Protocol::Protocol2, encoder: XL, current_unit: None, control_table: ControlTable::XL430},
+    Model{model_number: 311, name: "MX-64(2.0)", protocol: Protocol::Protocol2, encoder: X, current_unit: Some(Amperes(0.00336)), control_table: ControlTable::Protocol2Generic},
+    Model{model_number: 321, name: "MX-106(2.0)", protocol: Protocol::Protocol2, encoder: X, current_unit: Some(Amperes(0.00336)), control_table: ControlTable::Protocol2Generic},
+    Model{model_number: 1060, name: "XL430-W250", protocol: Protocol::Protocol2, encoder: XL, current_unit: None, control_table: ControlTable::XL430},
+    Model{model_number: 1000, name: "XH430-W350", protocol: Protocol::Protocol2, encoder: X, current_unit: Some(Amperes(0.00134)), control_table: ControlTable::Protocol2Generic},
+    Model{model_number: 1010, name: "XH430-W210", protocol: Protocol::Protocol2, encoder: X, current_unit: Some(Amperes(0.00134)), control_table: ControlTable::Protocol2Generic},
+    Model{model_number: 1020, name: "XM430-W350", protocol: Protocol::Protocol2, encoder: X, current_unit: Some(Amperes(0.00269)), control_table: ControlTable::Protocol2Generic},
+    Model{model_number: 1030, name: "XM430-W210", protocol: Protocol::Protocol2, encoder: X, current_unit: Some(Amperes(0.00269)), control_table: ControlTable::Protocol2Generic},
+    Model{model_number: 1120, name: "XM540-W270", protocol: Protocol::Protocol2, encoder: X, current_unit: Some(Amperes(0.00269)), control_table: ControlTable::Protocol2Generic},
+    Model{model_number: 1130, name: "XM540-W150", protocol: Protocol::Protocol2, encoder: X, current_unit: Some(Amperes(0.00269)), control_table: ControlTable::Protocol2Generic},
+    Model{model_number: 0xA918, name: "M42-10-S260-R", protocol: Protocol::Protocol2, encoder: PRO, current_unit: Some(PRO_CURRENT), control_table: ControlTable::Pro},
+];
+
+#[cfg(feature="std")]
+static REGISTERED: std::sync::RwLock<Vec<Model>> = std::sync::RwLock::new(Vec::new());
+
+/// The models that are known by this crate
+pub fn builtin() -> &'static [Model] {
+    BUILTIN
+}
+
+/// Register a model that is not known by this crate
+///
+/// Registered models take precedence over the builtin ones, so this can also be used to change a builtin model.
+/// This function requires the `std` feature.
+#[cfg(feature="std")]
+pub fn register(model: Model) {
+    REGISTERED.write().unwrap_or_else(|e| e.into_inner()).push(model);
+}
+
+/// Look up a model by protocol and model number
+pub fn lookup(protocol: Protocol, model_number: u16) -> Option<Model> {
+    let matches = |m: &&Model| m.protocol == protocol && m.model_number == model_number;
+
+    #[cfg(feature="std")]
+    {
+        let registered = REGISTERED.read().unwrap_or_else(|e| e.into_inner());
+        if let Some(model) = registered.iter().rev().find(matches) {
+            return Some(*model);
+        }
+    }
+
+    BUILTIN.iter().find(matches).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::registry::*;
+
+    #[test]
+    fn lookup_builtin() {
+        assert_eq!(lookup(Protocol::Protocol2, 1020).unwrap().name, "XM430-W350");
+        assert_eq!(lookup(Protocol::Protocol1, 12).unwrap().control_table, ControlTable::AX12);
+        assert_eq!(lookup(Protocol::Protocol2, 1060).unwrap().control_table, ControlTable::XL430);
+        assert_eq!(lookup(Protocol::Protocol1, 1020), None);
+    }
+
+    #[test]
+    fn register_tables() {
+        for table in &[ControlTable::AX12, ControlTable::MX28, ControlTable::XL430, ControlTable::Pro, ControlTable::Protocol1Generic, ControlTable::Protocol2Generic] {
+            for pair in table.registers().windows(2) {
+                assert!(pair[0].address + u16::from(pair[0].size) <= pair[1].address, "{:?} overlaps {:?}", pair[0], pair[1]);
+            }
+        }
+        assert_eq!(ControlTable::Protocol2Generic.register(116).unwrap().name, "Goal Position");
+        assert_eq!(ControlTable::AX12.register(31), None);
+        assert_eq!(ControlTable::XL430.register(126).unwrap().name, "Present Load");
+    }
+
+    #[cfg(feature="std")]
+    #[test]
+    fn lookup_registered() {
+        register(Model{
+            model_number: 0xfffe,
+            name: "Custom",
+            protocol: Protocol::Protocol2,
+            encoder: crate::protocol2::generic::ENCODER,
+            current_unit: None,
+            control_table: ControlTable::Protocol2Generic,
+        });
+        assert_eq!(lookup(Protocol::Protocol2, 0xfffe).unwrap().name, "Custom");
+        assert_eq!(lookup(Protocol::Protocol1, 0xfffe), None);
+    }
+}
@@ -0,0 +1,119 @@
+use crate::lib::convert::TryFrom;
+use crate::lib::time::Duration;
+use crate::{BaudRate, Error};
+#[cfg(feature="std")]
+use crate::CommunicationError;
+
+/// The unit of the Return Delay Time register
+const RETURN_DELAY_STEP: Duration = Duration::from_micros(2);
+
+/// The return delay time for a value of the Return Delay Time register
+pub(crate) fn return_delay_from_register(value: u8) -> Duration {
+    RETURN_DELAY_STEP * u32::from(value)
+}
+
+/// The Return Delay Time register value for `delay`, rounded down to a multiple of 2 µs
+///
+/// Delays above 508 µs can't be represented and give `Error::InvalidValue`.
+pub(crate) fn return_delay_to_register(delay: Duration) -> Result<u8, Error> {
+    match u8::try_from(delay.as_nanos() / RETURN_DELAY_STEP.as_nanos()) {
+        Ok(value) if value <= 254 => Ok(value),
+        _ => Err(Error::InvalidValue),
+    }
+}
+
+/// When the status packets answering an instruction are expected, giving every reply its own deadline
+///
+/// A servo starts replying its return delay time after the instruction, or after the previous reply of a sync or bulk read,
+/// and every byte takes 10 bits to transmit. The margin covers the latency of the interface, e.g. of a USB serial adapter.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ReplyTiming {
+    /// The baud rate of the bus
+    pub baud_rate: BaudRate,
+
+    /// Added to every deadline
+    pub margin: Duration,
+}
+
+impl ReplyTiming {
+    pub fn new(baud_rate: BaudRate, margin: Duration) -> Self {
+        ReplyTiming{baud_rate, margin}
+    }
+
+    /// The time it takes to transmit `bytes` bytes
+    pub fn transmission_time(&self, bytes: usize) -> Duration {
+        let bits = 10 * bytes as u64;
+        Duration::from_nanos(bits * 1_000_000_000 / u64::from(u32::from(self.baud_rate)))
+    }
+
+    /// How long to wait for a reply of `bytes` bytes, from a servo with return delay time `return_delay`
+    pub fn deadline(&self, return_delay: Duration, bytes: usize) -> Duration {
+        self.margin + return_delay + self.transmission_time(bytes)
+    }
+}
+
+/// Collect the replies to a sync or bulk read addressed to the servos with `ids`, matching them to the servos by ID
+///
+/// `read` reads the reply expected from the servo at the given index, returning its ID and data.
+/// Missing servos are skipped by the ones after them, so a reply from a later servo means the ones in between timed out.
+#[cfg(feature="std")]
+pub(crate) fn collect_replies<T, E: From<CommunicationError>>(ids: &[u8], mut read: impl FnMut(usize) -> Result<(u8, T), E>) -> Vec<Result<T, E>> {
+    let mut replies = Vec::with_capacity(ids.len());
+    while replies.len() < ids.len() {
+        match read(replies.len()) {
+            Ok((id, data)) => match ids[replies.len()..].iter().position(|expected| *expected == id) {
+                Some(skipped) => {
+                    replies.extend((0..skipped).map(|_| Err(E::from(CommunicationError::TimedOut))));
+                    replies.push(Ok(data));
+                },
+                None => replies.push(Err(E::from(CommunicationError::Other))),
+            },
+            Err(e) => replies.push(Err(e)),
+        }
+    }
+    replies
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::reply_timing::*;
+
+    #[test]
+    fn return_delay() {
+        assert_eq!(return_delay_from_register(250), Duration::from_micros(500));
+        assert_eq!(return_delay_to_register(Duration::from_micros(500)), Ok(250));
+        assert_eq!(return_delay_to_register(Duration::from_micros(3)), Ok(1));
+        assert_eq!(return_delay_to_register(Duration::from_micros(510)), Err(Error::InvalidValue));
+    }
+
+    #[test]
+    fn deadline() {
+        let timing = ReplyTiming::new(BaudRate::Baud1000000, Duration::from_millis(1));
+        assert_eq!(timing.transmission_time(10), Duration::from_micros(100));
+        assert_eq!(timing.deadline(Duration::from_micros(500), 10), Duration::from_micros(1600));
+    }
+
+    #[cfg(feature="std")]
+    #[test]
+    fn return_delay_time() {
+        use crate::mock::*;
+        use crate::Servo;
+
+        let mut mock = MockInterface::new();
+        mock.queue_response(&protocol2_status(1, 0, &[250]));
+        mock.queue_response(&protocol2_status(1, 0, &[]));
+
+        let mut servo = crate::protocol2::generic::GenericServo::new(crate::protocol2::ServoID::new(1), BaudRate::Baud57600);
+        assert_eq!(servo.get_return_delay_time(&mut mock), Ok(Duration::from_micros(500)));
+        assert_eq!(servo.get_return_delay_time(&mut mock), Ok(Duration::from_micros(500)));
+        servo.set_return_delay_time(&mut mock, Duration::from_micros(20)).unwrap();
+        assert_eq!(servo.get_return_delay_time(&mut mock), Ok(Duration::from_micros(20)));
+        assert_eq!(servo.set_return_delay_time(&mut mock, Duration::from_millis(1)), Err(crate::Error::InvalidValue));
+
+        mock.assert_sent_exactly(&[
+            &protocol2_packet(1, 0x02, &[9, 0, 1, 0]),
+            &protocol2_packet(1, 0x03, &[9, 0, 10]),
+        ]);
+        mock.assert_all_read();
+    }
+}
@@ -1,14 +1,18 @@
-use std;
-use serialport;
-
 use std::ops::DerefMut;
+use std::time::Duration;
+
+use serialport::{ClearBuffer, SerialPort};
 
 use {
     crate::CommunicationError,
     crate::Interface,
     crate::BaudRate,
+    crate::Timeouts,
 };
 
+/// The time between bytes before a read times out, unless another timeout is given
+const TIMEOUT: Duration = Duration::from_millis(100);
+
 impl From<serialport::Error> for CommunicationError {
     fn from(e: serialport::Error) -> CommunicationError {
         match e {
@@ -18,6 +22,14 @@ impl From<serialport::Error> for CommunicationError {
     }
 }
 
+/// Set the timeout of `port`, unless it is already set
+fn ensure_timeout(port: &mut dyn SerialPort, timeout: Duration) -> Result<(), CommunicationError> {
+    if port.timeout() != timeout {
+        port.set_timeout(timeout)?;
+    }
+    Ok(())
+}
+
 impl Interface for std::boxed::Box<dyn serialport::SerialPort> {
     fn set_baud_rate(&mut self, b: BaudRate) -> Result<(), CommunicationError> {
         match serialport::SerialPort::set_baud_rate(self.deref_mut(), u32::from(b)) {
@@ -27,17 +39,186 @@ impl Interface for std::boxed::Box<dyn serialport::SerialPort> {
     }
 
     fn flush(&mut self) {
-        let mut buf = Vec::new();
-        let _res = self.read_to_end(&mut buf);
+        let _res = self.clear(ClearBuffer::Input);
     }
 
     fn read(&mut self, data: &mut [u8]) -> Result<(), CommunicationError> {
-        self.set_timeout(std::time::Duration::new(0, 100000000))?;
+        self.read_with_timeout(data, TIMEOUT)
+    }
+
+    fn read_with_timeout(&mut self, data: &mut [u8], timeout: Duration) -> Result<(), CommunicationError> {
+        ensure_timeout(self.deref_mut(), timeout)?;
         Ok(std::io::Read::read_exact(self, data)?)
     }
 
     fn write(&mut self, data: &[u8]) -> Result<(), CommunicationError> {
-        self.set_timeout(std::time::Duration::new(0, 100000000))?;
+        ensure_timeout(self.deref_mut(), TIMEOUT)?;
         Ok(std::io::Write::write_all(self, data)?)
     }
 }
+
+/// A serial port configured for servo communication
+///
+/// The timeout is configured once, instead of before every read and write,
+/// and flushing discards the input buffer instead of reading until a timeout.
+/// When opened by path, the latency of the serial adapter is reduced where the OS allows it.
+/// The baud rate is only reconfigured when it changes.
+pub struct SerialPortInterface {
+    port: Box<dyn SerialPort>,
+    timeouts: Timeouts,
+    /// The timeout currently configured on the port
+    timeout: Duration,
+    /// The baud rate last set through this interface
+    baud_rate: Option<BaudRate>,
+}
+
+impl SerialPortInterface {
+    /// Use `port` for servo communication
+    pub fn new(mut port: Box<dyn SerialPort>) -> Result<Self, serialport::Error> {
+        let timeouts = Timeouts::default();
+        port.set_timeout(timeouts.status)?;
+        Ok(SerialPortInterface{port, timeouts, timeout: timeouts.status, baud_rate: None})
+    }
+
+    /// Set the timeouts used by servos and enumeration on this interface
+    pub fn set_timeouts(&mut self, timeouts: Timeouts) {
+        self.timeouts = timeouts;
+    }
+
+    /// Open the serial port at `path` with low latency, see `set_low_latency`
+    pub fn open(path: &str) -> Result<Self, serialport::Error> {
+        let mut interface = Self::new(serialport::open(path)?)?;
+        let _ = interface.set_low_latency();
+        Ok(interface)
+    }
+
+    /// Reduce the latency of the serial adapter
+    ///
+    /// USB serial adapters buffer received bytes for a while before passing them on,
+    /// 16 ms by default for FTDI adapters, which is much longer than a servo takes to reply.
+    /// On Linux this sets the latency timer of USB serial adapters to 1 ms, which requires write access to sysfs.
+    /// Returns an error if the latency can't be changed, e.g. for other adapters or operating systems.
+    pub fn set_low_latency(&mut self) -> std::io::Result<()> {
+        let name = self.port.name().ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "serial port without name"))?;
+        set_low_latency(&name)
+    }
+
+    pub fn port(&self) -> &dyn SerialPort {
+        self.port.as_ref()
+    }
+
+    /// The serial port
+    ///
+    /// The baud rate is set again before the next transaction, in case it is changed through the returned reference.
+    pub fn port_mut(&mut self) -> &mut dyn SerialPort {
+        self.baud_rate = None;
+        self.port.as_mut()
+    }
+
+    pub fn into_inner(self) -> Box<dyn SerialPort> {
+        self.port
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> Result<(), CommunicationError> {
+        if self.timeout != timeout {
+            self.port.set_timeout(timeout)?;
+            self.timeout = timeout;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn set_low_latency(name: &str) -> std::io::Result<()> {
+    let device = std::path::Path::new(name).file_name()
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "serial port without device name"))?;
+    let latency_timer = std::path::Path::new("/sys/bus/usb-serial/devices").join(device).join("latency_timer");
+    std::fs::write(latency_timer, "1")
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_low_latency(_name: &str) -> std::io::Result<()> {
+    Err(std::io::Error::new(std::io::ErrorKind::Other, "low latency is not supported on this operating system"))
+}
+
+impl Interface for SerialPortInterface {
+    fn set_baud_rate(&mut self, b: BaudRate) -> Result<(), CommunicationError> {
+        if self.baud_rate == Some(b) {
+            return Ok(());
+        }
+        self.baud_rate = None;
+        match SerialPort::set_baud_rate(self.port.as_mut(), u32::from(b)) {
+            Ok(_) => {
+                self.baud_rate = Some(b);
+                Ok(())
+            },
+            Err(_) => Err(CommunicationError::UnsupportedBaud(b)),
+        }
+    }
+
+    fn flush(&mut self) {
+        let _res = self.port.clear(ClearBuffer::Input);
+    }
+
+    fn read(&mut self, data: &mut [u8]) -> Result<(), CommunicationError> {
+        self.read_with_timeout(data, self.timeouts.status)
+    }
+
+    fn read_with_timeout(&mut self, data: &mut [u8], timeout: Duration) -> Result<(), CommunicationError> {
+        self.set_timeout(timeout)?;
+        Ok(std::io::Read::read_exact(&mut self.port, data)?)
+    }
+
+    fn write(&mut self, data: &[u8]) -> Result<(), CommunicationError> {
+        self.set_timeout(self.timeouts.status)?;
+        Ok(std::io::Write::write_all(&mut self.port, data)?)
+    }
+
+    fn timeouts(&self) -> Timeouts {
+        self.timeouts
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use crate::serial_impl::*;
+    use std::io::{Read, Write};
+    use std::time::Instant;
+
+    /// Echo everything written to `port` back, like a serial adapter with TX and RX connected
+    fn loopback(mut port: serialport::posix::TTYPort) {
+        std::thread::spawn(move || {
+            let mut buffer = [0u8; 64];
+            loop {
+                match port.read(&mut buffer) {
+                    Ok(n) => if port.write_all(&buffer[..n]).is_err() {
+                        return;
+                    },
+                    Err(ref e) if e.kind() == std::io::ErrorKind::TimedOut => (),
+                    Err(_) => return,
+                }
+            }
+        });
+    }
+
+    #[test]
+    fn loopback_latency() {
+        let (master, slave) = serialport::posix::TTYPort::pair().unwrap();
+        loopback(master);
+        let mut interface = SerialPortInterface::new(Box::new(slave)).unwrap();
+
+        let start = Instant::now();
+        for i in 0..20u8 {
+            interface.flush();
+            interface.write(&[0xff, 0xff, i]).unwrap();
+            let mut echo = [0u8; 3];
+            interface.read(&mut echo).unwrap();
+            assert_eq!(echo, [0xff, 0xff, i]);
+        }
+        // Flushing by reading until a timeout took 100 ms per transaction
+        assert!(start.elapsed() < TIMEOUT, "20 transactions took {:?}", start.elapsed());
+
+        let mut data = [0u8; 1];
+        assert_eq!(interface.read_with_timeout(&mut data, Duration::from_millis(10)), Err(CommunicationError::TimedOut));
+    }
+}
@@ -0,0 +1,626 @@
+//! A simulated bus of servos
+//!
+//! `Simulator` implements `Interface`, answering instruction packets from the simulated servos on the bus.
+//! Each `SimServo` holds the full control table of its model, and answers ping, read, write, sync and bulk instructions
+//! with status packets like a real servo would, including error bits.
+//! Statuses are only sent for the instructions the Status Return Level register of the servo allows,
+//! and after the time in its Return Delay Time register.
+//! Present position and velocity follow the goal registers with a simple velocity model.
+//!
+//! Time in the simulation passes with the bytes sent and received on the bus, according to the baud rate,
+//! and can be advanced explicitly with `Simulator::advance`.
+//!
+//! This module requires the `std` feature.
+
+mod servo;
+#[cfg(all(feature="serialport", unix))]
+pub mod pty;
+
+pub use self::servo::SimServo;
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::mock::{protocol1_status, protocol2_status};
+use crate::registry::Protocol;
+use crate::{BaudRate, CommunicationError, Interface};
+
+use self::servo::AccessError;
+
+const BROADCAST_ID: u8 = 0xfe;
+
+mod protocol1_error {
+    pub const RANGE: u8 = 1 << 3;
+    pub const CHECKSUM: u8 = 1 << 4;
+    pub const INSTRUCTION: u8 = 1 << 6;
+}
+
+mod protocol2_error {
+    pub const INSTRUCTION: u8 = 0x02;
+    pub const CRC: u8 = 0x03;
+    pub const DATA_LENGTH: u8 = 0x05;
+    pub const DATA_ACCESS: u8 = 0x07;
+}
+
+/// An instruction packet received on the bus
+#[derive(Debug, PartialEq, Eq)]
+struct Packet {
+    protocol: Protocol,
+    id: u8,
+    instruction: u8,
+    parameters: Vec<u8>,
+    valid_checksum: bool,
+}
+
+/// A simulated bus, with servos answering the packets written to it
+#[derive(Debug, Clone)]
+pub struct Simulator {
+    servos: Vec<SimServo>,
+    baud_rate: BaudRate,
+    received: Vec<u8>,
+    /// Response bytes, with the simulated time they are sent at
+    responses: VecDeque<(Duration, u8)>,
+    elapsed: Duration,
+}
+
+impl Default for Simulator {
+    fn default() -> Self {
+        Simulator{
+            servos: Vec::new(),
+            baud_rate: BaudRate::Baud1000000,
+            received: Vec::new(),
+            responses: VecDeque::new(),
+            elapsed: Duration::from_secs(0),
+        }
+    }
+}
+
+impl Simulator {
+    /// Create a bus without any servos
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a servo to the bus
+    pub fn with_servo(mut self, servo: SimServo) -> Self {
+        self.add_servo(servo);
+        self
+    }
+
+    /// Add a servo to the bus
+    pub fn add_servo(&mut self, servo: SimServo) {
+        self.servos.push(servo);
+    }
+
+    pub fn servos(&self) -> &[SimServo] {
+        &self.servos
+    }
+
+    /// The servo with the given protocol and id
+    pub fn servo(&self, protocol: Protocol, id: u8) -> Option<&SimServo> {
+        self.servos.iter().find(|s| s.protocol() == protocol && s.id() == id)
+    }
+
+    /// The servo with the given protocol and id
+    pub fn servo_mut(&mut self, protocol: Protocol, id: u8) -> Option<&mut SimServo> {
+        self.servos.iter_mut().find(|s| s.protocol() == protocol && s.id() == id)
+    }
+
+    /// The simulated time that has passed
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Let `duration` of simulated time pass
+    pub fn advance(&mut self, duration: Duration) {
+        self.elapsed += duration;
+        for servo in &mut self.servos {
+            servo.advance(duration.as_secs_f64());
+        }
+    }
+
+    /// Let the time it takes to transfer `bytes` bytes pass, at 10 bits per byte
+    fn transfer(&mut self, bytes: usize) {
+        let seconds = (bytes * 10) as f64 / f64::from(u32::from(self.baud_rate));
+        self.advance(Duration::from_secs_f64(seconds));
+    }
+
+    /// Take the response bytes sent by now, letting the time it takes to transfer them pass
+    #[cfg(all(feature="serialport", unix))]
+    pub(crate) fn take_responses(&mut self) -> Vec<u8> {
+        let sent = self.responses.iter().take_while(|(time, _)| *time <= self.elapsed).count();
+        let responses: Vec<u8> = self.responses.drain(..sent).map(|(_, b)| b).collect();
+        self.transfer(responses.len());
+        responses
+    }
+
+    /// Parse the first packet in `received`, returning `None` if more bytes are needed
+    fn parse_packet(&mut self) -> Option<Packet> {
+        // Discard noise before the header
+        while self.received.len() >= 2 && self.received[..2] != [0xff, 0xff] {
+            self.received.remove(0);
+        }
+
+        if self.received.len() >= 4 && self.received[..4] == [0xff, 0xff, 0xfd, 0x00] {
+            if self.received.len() < 7 {
+                return None;
+            }
+            let length = usize::from(u16::from_le_bytes([self.received[5], self.received[6]]));
+            if length < 3 || self.received.len() < 7 + length {
+                return None;
+            }
+            let packet: Vec<u8> = self.received.drain(..7 + length).collect();
+            let crc = u16::from_le_bytes([packet[5 + length], packet[6 + length]]);
+            let valid_checksum = crc == u16::from(crate::protocol2::crc::Crc::calc(&packet[..5 + length]));
+
+            // Remove byte stuffing
+            let mut parameters = Vec::new();
+            for b in &packet[8..5 + length] {
+                if *b == 0xfd && parameters.ends_with(&[0xff, 0xff, 0xfd]) {
+                    continue;
+                }
+                parameters.push(*b);
+            }
+            Some(Packet{protocol: Protocol::Protocol2, id: packet[4], instruction: packet[7], parameters, valid_checksum})
+        } else {
+            if self.received.len() < 4 {
+                return None;
+            }
+            let length = usize::from(self.received[3]);
+            if length < 2 {
+                self.received.drain(..4);
+                return None;
+            }
+            if self.received.len() < 4 + length {
+                return None;
+            }
+            let packet: Vec<u8> = self.received.drain(..4 + length).collect();
+            let sum = packet[2..3 + length].iter().fold(0u8, |sum, b| sum.wrapping_add(*b));
+            Some(Packet{
+                protocol: Protocol::Protocol1,
+                id: packet[2],
+                instruction: packet[4],
+                parameters: packet[5..3 + length].to_vec(),
+                valid_checksum: !sum == packet[3 + length],
+            })
+        }
+    }
+
+    /// Indexes of the servos listening on the current baud rate with `protocol`
+    fn listening(&self, protocol: Protocol) -> impl Iterator<Item=usize> + '_ {
+        let baud_rate = self.baud_rate;
+        self.servos.iter().enumerate()
+            .filter(move |(_, s)| s.protocol() == protocol && s.baud_rate() == baud_rate)
+            .map(|(i, _)| i)
+    }
+
+    fn find(&self, protocol: Protocol, id: u8) -> Option<usize> {
+        self.listening(protocol).find(|i| self.servos[*i].id() == id)
+    }
+
+    /// The servos addressed by `id` in id order, only one unless it is the broadcast id
+    fn addressed(&self, protocol: Protocol, id: u8) -> Vec<usize> {
+        let mut servos: Vec<usize> = self.listening(protocol).filter(|i| id == BROADCAST_ID || self.servos[*i].id() == id).collect();
+        servos.sort_by_key(|i| self.servos[*i].id());
+        servos
+    }
+
+    /// Send a status for `instruction` from `servo`, if its status return level allows it
+    ///
+    /// The status is sent after the return delay time of the servo, counted from the end of the previous status.
+    fn respond(&mut self, protocol: Protocol, instruction: u8, servo: usize, error: u8, parameters: &[u8]) {
+        if !self.servos[servo].answers(instruction) {
+            return;
+        }
+        let id = self.servos[servo].id();
+        let status = match protocol {
+            Protocol::Protocol1 => protocol1_status(id, error, parameters),
+            Protocol::Protocol2 => protocol2_status(id, error, parameters),
+        };
+        let previous = self.responses.back().map_or(self.elapsed, |(time, _)| *time);
+        let time = previous.max(self.elapsed) + self.servos[servo].return_delay();
+        self.responses.extend(status.into_iter().map(|b| (time, b)));
+    }
+
+    fn handle(&mut self, packet: Packet) {
+        match packet.protocol {
+            Protocol::Protocol1 => self.handle_protocol1(packet),
+            Protocol::Protocol2 => self.handle_protocol2(packet),
+        }
+    }
+
+    fn handle_protocol1(&mut self, packet: Packet) {
+        use self::protocol1_error::*;
+        let params = &packet.parameters;
+        let unicast = self.find(Protocol::Protocol1, packet.id);
+
+        if !packet.valid_checksum {
+            if let Some(servo) = unicast {
+                self.respond(Protocol::Protocol1, packet.instruction, servo, CHECKSUM, &[]);
+            }
+            return;
+        }
+
+        match packet.instruction {
+            // Ping
+            0x01 => {
+                for servo in self.addressed(Protocol::Protocol1, packet.id) {
+                    self.respond(Protocol::Protocol1, packet.instruction, servo, 0, &[]);
+                }
+            },
+            // Read
+            0x02 => if let Some(servo) = unicast {
+                match params.get(..2).map(|p| self.servos[servo].read(usize::from(p[0]), usize::from(p[1]))) {
+                    Some(Ok(data)) => {
+                        let data = data.to_vec();
+                        self.respond(Protocol::Protocol1, packet.instruction, servo, 0, &data);
+                    },
+                    _ => self.respond(Protocol::Protocol1, packet.instruction, servo, RANGE, &[]),
+                }
+            },
+            // Write
+            0x03 => {
+                for servo in self.addressed(Protocol::Protocol1, packet.id) {
+                    let result = match params.split_first() {
+                        Some((address, data)) => self.servos[servo].write(usize::from(*address), data),
+                        None => Err(AccessError::Range),
+                    };
+                    if packet.id != BROADCAST_ID {
+                        self.respond(Protocol::Protocol1, packet.instruction, servo, if result.is_ok() { 0 } else { RANGE }, &[]);
+                    }
+                }
+            },
+            // Sync write, never answered
+            0x83 => if params.len() >= 2 {
+                let (address, length) = (usize::from(params[0]), usize::from(params[1]));
+                for chunk in params[2..].chunks(length + 1).filter(|c| c.len() == length + 1) {
+                    if let Some(servo) = self.find(Protocol::Protocol1, chunk[0]) {
+                        let _ = self.servos[servo].write(address, &chunk[1..]);
+                    }
+                }
+            },
+            // Bulk read, answered in the order of the request
+            0x92 => {
+                for request in params.get(1..).unwrap_or(&[]).chunks(3).filter(|r| r.len() == 3) {
+                    if let Some(servo) = self.find(Protocol::Protocol1, request[1]) {
+                        match self.servos[servo].read(usize::from(request[2]), usize::from(request[0])) {
+                            Ok(data) => {
+                                let data = data.to_vec();
+                                self.respond(Protocol::Protocol1, packet.instruction, servo, 0, &data);
+                            },
+                            Err(_) => self.respond(Protocol::Protocol1, packet.instruction, servo, RANGE, &[]),
+                        }
+                    }
+                }
+            },
+            _ => if let Some(servo) = unicast {
+                self.respond(Protocol::Protocol1, packet.instruction, servo, INSTRUCTION, &[]);
+            },
+        }
+    }
+
+    fn handle_protocol2(&mut self, packet: Packet) {
+        use self::protocol2_error::*;
+        let params = &packet.parameters;
+        let unicast = self.find(Protocol::Protocol2, packet.id);
+        let u16_at = |i: usize| usize::from(u16::from_le_bytes([params[i], params[i + 1]]));
+        let access_error = |_: AccessError| DATA_ACCESS;
+
+        if !packet.valid_checksum {
+            if let Some(servo) = unicast {
+                self.respond(Protocol::Protocol2, packet.instruction, servo, CRC, &[]);
+            }
+            return;
+        }
+
+        match packet.instruction {
+            // Ping
+            0x01 => {
+                for servo in self.addressed(Protocol::Protocol2, packet.id) {
+                    let model_number = self.servos[servo].model_number().to_le_bytes();
+                    let fw_version = self.servos[servo].fw_version();
+                    self.respond(Protocol::Protocol2, packet.instruction, servo, 0, &[model_number[0], model_number[1], fw_version]);
+                }
+            },
+            // Read
+            0x02 => if let Some(servo) = unicast {
+                if params.len() != 4 {
+                    self.respond(Protocol::Protocol2, packet.instruction, servo, DATA_LENGTH, &[]);
+                    return;
+                }
+                match self.servos[servo].read(u16_at(0), u16_at(2)) {
+                    Ok(data) => {
+                        let data = data.to_vec();
+                        self.respond(Protocol::Protocol2, packet.instruction, servo, 0, &data);
+                    },
+                    Err(e) => self.respond(Protocol::Protocol2, packet.instruction, servo, access_error(e), &[]),
+                }
+            },
+            // Write
+            0x03 => {
+                for servo in self.addressed(Protocol::Protocol2, packet.id) {
+                    let error = if params.len() < 3 {
+                        DATA_LENGTH
+                    } else {
+                        self.servos[servo].write(u16_at(0), &params[2..]).err().map_or(0, access_error)
+                    };
+                    if packet.id != BROADCAST_ID {
+                        self.respond(Protocol::Protocol2, packet.instruction, servo, error, &[]);
+                    }
+                }
+            },
+            // Sync read, answered in the order of the request
+            0x82 => if params.len() >= 4 {
+                let (address, length) = (u16_at(0), u16_at(2));
+                for id in params[4..].iter().copied() {
+                    if let Some(servo) = self.find(Protocol::Protocol2, id) {
+                        match self.servos[servo].read(address, length) {
+                            Ok(data) => {
+                                let data = data.to_vec();
+                                self.respond(Protocol::Protocol2, packet.instruction, servo, 0, &data);
+                            },
+                            Err(e) => self.respond(Protocol::Protocol2, packet.instruction, servo, access_error(e), &[]),
+                        }
+                    }
+                }
+            },
+            // Sync write, never answered
+            0x83 => if params.len() >= 4 {
+                let (address, length) = (u16_at(0), u16_at(2));
+                for chunk in params[4..].chunks(length + 1).filter(|c| c.len() == length + 1) {
+                    if let Some(servo) = self.find(Protocol::Protocol2, chunk[0]) {
+                        let _ = self.servos[servo].write(address, &chunk[1..]);
+                    }
+                }
+            },
+            // Bulk read, answered in the order of the request
+            0x92 => {
+                for request in params.chunks(5).filter(|r| r.len() == 5) {
+                    let (address, length) = (usize::from(u16::from_le_bytes([request[1], request[2]])), usize::from(u16::from_le_bytes([request[3], request[4]])));
+                    if let Some(servo) = self.find(Protocol::Protocol2, request[0]) {
+                        match self.servos[servo].read(address, length) {
+                            Ok(data) => {
+                                let data = data.to_vec();
+                                self.respond(Protocol::Protocol2, packet.instruction, servo, 0, &data);
+                            },
+                            Err(e) => self.respond(Protocol::Protocol2, packet.instruction, servo, access_error(e), &[]),
+                        }
+                    }
+                }
+            },
+            // Bulk write, never answered
+            0x93 => {
+                let mut rest = &params[..];
+                while rest.len() >= 5 {
+                    let (address, length) = (usize::from(u16::from_le_bytes([rest[1], rest[2]])), usize::from(u16::from_le_bytes([rest[3], rest[4]])));
+                    if rest.len() < 5 + length {
+                        break;
+                    }
+                    if let Some(servo) = self.find(Protocol::Protocol2, rest[0]) {
+                        let _ = self.servos[servo].write(address, &rest[5..5 + length]);
+                    }
+                    rest = &rest[5 + length..];
+                }
+            },
+            _ => if let Some(servo) = unicast {
+                self.respond(Protocol::Protocol2, packet.instruction, servo, INSTRUCTION, &[]);
+            },
+        }
+    }
+}
+
+impl Interface for Simulator {
+    fn set_baud_rate(&mut self, b: BaudRate) -> Result<(), CommunicationError> {
+        self.baud_rate = b;
+        Ok(())
+    }
+
+    fn flush(&mut self) {
+        self.responses.clear();
+    }
+
+    fn read(&mut self, data: &mut [u8]) -> Result<(), CommunicationError> {
+        if self.responses.len() < data.len() {
+            // Nothing more will arrive, consume what is there like a real interface timing out
+            self.responses.clear();
+            return Err(CommunicationError::TimedOut);
+        }
+        // Wait for the servo to send the status
+        let sent = self.responses.front().map_or(self.elapsed, |(time, _)| *time);
+        if sent > self.elapsed {
+            self.advance(sent - self.elapsed);
+        }
+        for byte in data.iter_mut() {
+            *byte = self.responses.pop_front().expect("length checked above").1;
+        }
+        self.transfer(data.len());
+        Ok(())
+    }
+
+    fn read_with_timeout(&mut self, data: &mut [u8], timeout: Duration) -> Result<(), CommunicationError> {
+        let sent = self.responses.front().map_or(self.elapsed, |(time, _)| *time);
+        if sent > self.elapsed + timeout {
+            // The status comes too late, and is lost like on a real interface timing out
+            self.advance(timeout);
+            self.responses.clear();
+            return Err(CommunicationError::TimedOut);
+        }
+        self.read(data)
+    }
+
+    fn write(&mut self, data: &[u8]) -> Result<(), CommunicationError> {
+        self.transfer(data.len());
+        self.received.extend_from_slice(data);
+        while let Some(packet) = self.parse_packet() {
+            self.handle(packet);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sim::*;
+    use crate::mock::{protocol1_packet, protocol2_packet};
+    use crate::units::Radians;
+    use crate::{OperatingMode, Servo};
+
+    fn bus() -> Simulator {
+        Simulator::new()
+            .with_servo(SimServo::ax12(1))
+            .with_servo(SimServo::mx28(2))
+            .with_servo(SimServo::xm430_w350(3).with_baud_rate(BaudRate::Baud1000000))
+            .with_servo(SimServo::m42_10_s260_r(4).with_baud_rate(BaudRate::Baud1000000))
+    }
+
+    #[test]
+    fn enumerate_and_connect() {
+        let mut bus = bus();
+        let mut servos = crate::enumerate(&mut bus).unwrap();
+        servos.extend(crate::protocol2::enumerate(&mut bus).unwrap().into_iter().map(crate::ServoProtocol::Protocol2));
+        let models: Vec<u16> = servos.iter().map(|s| s.model().unwrap().model_number).collect();
+        assert_eq!(models, vec![12, 29, 1020, 0xA918]);
+
+        for info in servos {
+            let mut servo = crate::connect(&mut bus, info).unwrap();
+            assert_eq!(servo.get_operating_mode(&mut bus), Ok(OperatingMode::Position));
+            assert!(servo.get_position(&mut bus).unwrap().value().abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn moves_towards_goal() {
+        let mut bus = bus();
+        let mut servo = crate::protocol2::generic::GenericServo::new(crate::protocol2::ServoID::new(3), BaudRate::Baud1000000);
+        servo.set_enable_torque(&mut bus, true).unwrap();
+        servo.set_position(&mut bus, Radians(1.0)).unwrap();
+
+        bus.advance(Duration::from_millis(50));
+        let position = servo.get_position(&mut bus).unwrap().value();
+        assert!(position > 0.0 && position < 1.0);
+
+        bus.advance(Duration::from_secs(1));
+        assert!((servo.get_position(&mut bus).unwrap().value() - 1.0).abs() < 0.002);
+    }
+
+    #[test]
+    fn operating_mode() {
+        let mut bus = bus();
+        let mut servo = crate::dynamixel::mx28::MX28::new(crate::protocol1::ServoID::new(2), BaudRate::Baud1000000);
+        servo.set_operating_mode(&mut bus, OperatingMode::Velocity).unwrap();
+        servo.set_enable_torque(&mut bus, true).unwrap();
+        servo.set_goal_velocity(&mut bus, crate::units::RadiansPerSecond(2.0)).unwrap();
+        bus.advance(Duration::from_millis(100));
+        assert!((servo.get_velocity(&mut bus).unwrap().value() - 2.0).abs() < 0.05);
+
+        // EEPROM of protocol 2 servos can only be written with torque disabled
+        let mut servo = crate::protocol2::generic::GenericServo::new(crate::protocol2::ServoID::new(3), BaudRate::Baud1000000);
+        servo.set_enable_torque(&mut bus, true).unwrap();
+        servo.set_operating_mode(&mut bus, OperatingMode::Velocity).unwrap();
+        assert_eq!(servo.get_operating_mode(&mut bus), Ok(OperatingMode::Velocity));
+        assert!(servo.write_raw(&mut bus, 11, [3]).is_err());
+    }
+
+    #[test]
+    fn status_errors() {
+        let mut bus = bus();
+        let mut status = [0u8; 6];
+
+        // Read only register
+        bus.write(&protocol1_packet(1, 0x03, &[36, 0])).unwrap();
+        bus.read(&mut status).unwrap();
+        assert_eq!(status[4], protocol1_error::RANGE);
+
+        // Unknown instruction
+        bus.write(&protocol1_packet(1, 0x42, &[])).unwrap();
+        bus.read(&mut status).unwrap();
+        assert_eq!(status[4], protocol1_error::INSTRUCTION);
+
+        // Corrupt checksum
+        let mut packet = protocol2_packet(3, 0x01, &[]);
+        *packet.last_mut().unwrap() ^= 0xff;
+        let mut status = [0u8; 11];
+        bus.write(&packet).unwrap();
+        bus.read(&mut status).unwrap();
+        assert_eq!(status[8], protocol2_error::CRC);
+    }
+
+    #[test]
+    fn sync_and_bulk() {
+        let mut bus = bus().with_servo(SimServo::xm430_w350(5).with_baud_rate(BaudRate::Baud1000000));
+
+        // Sync write goal position 100 to id 3 and 200 to id 5
+        bus.write(&protocol2_packet(0xfe, 0x83, &[116, 0, 4, 0, 3, 100, 0, 0, 0, 5, 200, 0, 0, 0])).unwrap();
+        assert_eq!(bus.servo(Protocol::Protocol2, 3).unwrap().get(116, 4), 100);
+        assert_eq!(bus.servo(Protocol::Protocol2, 5).unwrap().get(116, 4), 200);
+
+        // Sync read model number from id 5 and 3
+        bus.write(&protocol2_packet(0xfe, 0x82, &[0, 0, 2, 0, 5, 3])).unwrap();
+        let mut status = [0u8; 13];
+        bus.read(&mut status).unwrap();
+        assert_eq!((status[4], status[9]), (5, 0xfc));
+        bus.read(&mut status).unwrap();
+        assert_eq!(status[4], 3);
+
+        // Bulk read present temperature from id 2 (protocol 1)
+        bus.write(&protocol1_packet(0xfe, 0x92, &[0x00, 1, 2, 43])).unwrap();
+        let mut status = [0u8; 7];
+        bus.read(&mut status).unwrap();
+        assert_eq!((status[2], status[5]), (2, 30));
+    }
+
+    #[test]
+    fn sync_read() {
+        let mut bus = Simulator::new().with_servo(SimServo::xm430_w350(3)).with_servo(SimServo::xm430_w350(5));
+        let timing = crate::ReplyTiming::new(BaudRate::Baud57600, Duration::from_millis(1));
+        let servos = [3, 4, 5].map(|id| (crate::protocol2::ServoID::new(id), Duration::from_micros(500)));
+
+        let replies = crate::protocol2::sync_read_raw::<_, 1>(&mut bus, &timing, 9, &servos).unwrap();
+        assert_eq!(replies, vec![Ok([250]), Err(crate::protocol2::Error::Communication{id: None, error: crate::CommunicationError::TimedOut}), Ok([250])]);
+    }
+
+    #[test]
+    fn status_return_level() {
+        let mut bus = bus();
+        let mut servo = crate::protocol2::generic::GenericServo::new(crate::protocol2::ServoID::new(3), BaudRate::Baud1000000);
+
+        // Writes are no longer answered, and reads still are
+        servo.write_status_return_level(&mut bus, crate::StatusReturnLevel::Read).unwrap();
+        assert!(bus.responses.is_empty());
+        assert_eq!(servo.read_status_return_level(&mut bus), Ok(crate::StatusReturnLevel::Read));
+
+        // Only pings are answered
+        servo.write_status_return_level(&mut bus, crate::StatusReturnLevel::Ping).unwrap();
+        servo.assume_status_return_level(crate::StatusReturnLevel::All);
+        assert_eq!(servo.read_status_return_level(&mut bus), Ok(crate::StatusReturnLevel::Ping));
+        bus.write(&protocol2_packet(3, 0x02, &[0, 0, 2, 0])).unwrap();
+        assert!(bus.responses.is_empty());
+
+        let mut servo = crate::dynamixel::ax12::AX12::new(crate::protocol1::ServoID::new(1), BaudRate::Baud1000000);
+        servo.write_status_return_level(&mut bus, crate::StatusReturnLevel::Ping).unwrap();
+        assert_eq!(bus.servo(Protocol::Protocol1, 1).unwrap().status_return_level(), 0);
+        assert_eq!(servo.read_status_return_level(&mut bus), Ok(crate::StatusReturnLevel::Ping));
+    }
+
+    #[test]
+    fn return_delay_time() {
+        let mut bus = bus();
+        let mut servo = crate::dynamixel::ax12::AX12::new(crate::protocol1::ServoID::new(1), BaudRate::Baud1000000);
+
+        // 6 bytes of ping and 6 bytes of status at 1 Mbaud take 120 µs, and the default return delay time is 500 µs
+        let start = bus.elapsed();
+        servo.ping(&mut bus).unwrap();
+        assert_eq!(bus.elapsed() - start, Duration::from_micros(620));
+
+        servo.set_return_delay_time(&mut bus, Duration::from_micros(20)).unwrap();
+        let start = bus.elapsed();
+        servo.ping(&mut bus).unwrap();
+        assert_eq!(bus.elapsed() - start, Duration::from_micros(140));
+
+        // A status later than the timeout is lost
+        let timeouts = crate::Timeouts{ping: Duration::from_micros(10), ..crate::Timeouts::default()};
+        servo.set_timeouts(Some(timeouts));
+        assert_eq!(servo.ping(&mut bus), Err(crate::protocol1::Error::Communication{id: Some(crate::protocol1::ServoID::new(1)), error: CommunicationError::TimedOut}));
+        assert!(bus.responses.is_empty());
+    }
+}
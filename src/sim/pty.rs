@@ -0,0 +1,132 @@
+//! Serving a simulated bus on a pseudo-terminal
+//!
+//! `PtyBridge` creates a pseudo-terminal and answers the packets written to it with a `Simulator`,
+//! so tools that open serial ports by path can be used without hardware.
+//! The baud rate set on the port is passed on to the simulator, and simulated time follows wall-clock time.
+//!
+//! This module requires the `std` and `serialport` features, and a unix platform.
+
+use std::io::{Read, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use serialport::posix::TTYPort;
+use serialport::SerialPort;
+
+use crate::sim::Simulator;
+use crate::{BaudRate, Interface};
+
+/// A simulated bus served on a pseudo-terminal
+///
+/// The bus is served from a background thread until the bridge is dropped.
+pub struct PtyBridge {
+    path: String,
+    simulator: Arc<Mutex<Simulator>>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+    // Keeps the pseudo-terminal alive while no client has it open
+    _slave: TTYPort,
+}
+
+impl PtyBridge {
+    /// Create a pseudo-terminal and serve `simulator` on it
+    pub fn spawn(simulator: Simulator) -> Result<Self, serialport::Error> {
+        let (mut master, mut slave) = TTYPort::pair()?;
+        slave.set_exclusive(false)?;
+        master.set_timeout(Duration::from_millis(5))?;
+        let path = slave.name().ok_or_else(|| serialport::Error::new(serialport::ErrorKind::Unknown, "pseudo-terminal without name"))?;
+
+        let simulator = Arc::new(Mutex::new(simulator));
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let simulator = simulator.clone();
+            let stop = stop.clone();
+            std::thread::spawn(move || serve(master, &simulator, &stop))
+        };
+
+        Ok(PtyBridge{
+            path,
+            simulator,
+            stop,
+            thread: Some(thread),
+            _slave: slave,
+        })
+    }
+
+    /// The path of the serial port, e.g. "/dev/pts/3"
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Lock the simulator, to inspect or change the simulated servos
+    pub fn simulator(&self) -> MutexGuard<'_, Simulator> {
+        self.simulator.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Drop for PtyBridge {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn serve(mut master: TTYPort, simulator: &Mutex<Simulator>, stop: &AtomicBool) {
+    let mut buffer = [0u8; 256];
+    let mut last = Instant::now();
+
+    while !stop.load(Ordering::Relaxed) {
+        let received = match master.read(&mut buffer) {
+            Ok(received) => received,
+            Err(ref e) if e.kind() == std::io::ErrorKind::TimedOut => 0,
+            Err(_) => {
+                // No client has the port open
+                std::thread::sleep(Duration::from_millis(5));
+                0
+            },
+        };
+
+        let response = {
+            let mut simulator = simulator.lock().unwrap_or_else(|e| e.into_inner());
+            let now = Instant::now();
+            simulator.advance(now - last);
+            last = now;
+
+            if let Some(baud_rate) = master.baud_rate().ok().and_then(|b| BaudRate::variants().iter().find(|v| u32::from(**v) == b)) {
+                let _ = simulator.set_baud_rate(*baud_rate);
+            }
+            let _ = simulator.write(&buffer[..received]);
+            simulator.take_responses()
+        };
+
+        if !response.is_empty() {
+            let _ = master.write_all(&response);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sim::pty::*;
+    use crate::sim::SimServo;
+    use crate::units::Radians;
+    use crate::Servo;
+
+    #[test]
+    fn serial_port() {
+        let bridge = PtyBridge::spawn(Simulator::new().with_servo(SimServo::ax12(1))).unwrap();
+        let mut port = serialport::open(bridge.path()).unwrap();
+
+        let servos = crate::protocol1::enumerate(&mut port).unwrap();
+        assert_eq!(servos.len(), 1);
+        assert_eq!(servos[0].model_number, 12);
+
+        let mut servo = crate::connect(&mut port, crate::ServoProtocol::Protocol1(servos[0].clone())).unwrap();
+        servo.set_position(&mut port, Radians(0.5)).unwrap();
+        assert_eq!(bridge.simulator().servo(crate::registry::Protocol::Protocol1, 1).unwrap().get(30, 2), i64::from(crate::dynamixel::ax12::ENCODER.to_steps(Radians(0.5)).unwrap()));
+    }
+}
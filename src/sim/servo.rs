@@ -0,0 +1,446 @@
+use std::time::Duration;
+
+use crate::registry::Protocol;
+use crate::BaudRate;
+
+/// The registers the simulation of a protocol 2 model needs
+#[derive(Debug)]
+struct Protocol2Layout {
+    velocity_limit: usize,
+    max_position_limit: usize,
+    min_position_limit: usize,
+    goal_velocity: usize,
+    /// The velocity used when moving to a goal position, the velocity limit is used if this is missing or 0
+    profile_velocity: Option<usize>,
+    goal_position: usize,
+    moving: usize,
+    present_velocity: usize,
+    present_position: usize,
+}
+
+/// Description of a simulated model
+#[derive(Debug)]
+struct Spec {
+    protocol: Protocol,
+    model_number: u16,
+    fw_version: u8,
+    size: usize,
+    id: usize,
+    return_delay_time: usize,
+    status_return_level: usize,
+    torque_enable: usize,
+    /// Inclusive address ranges that can't be written
+    read_only: &'static [(usize, usize)],
+    /// Addresses below this are EEPROM, and can only be written while torque is disabled
+    eeprom_end: Option<usize>,
+    steps_per_revolution: f64,
+    /// The highest position of protocol 1 servos, outside multi-turn mode
+    max_position: i64,
+    /// Velocity register unit, in rpm
+    velocity_unit: f64,
+    /// Velocity used by protocol 1 servos when moving speed is 0, in raw units
+    max_velocity: u32,
+    protocol2: Option<Protocol2Layout>,
+    /// Initial register values as (address, size, value)
+    defaults: &'static [(usize, usize, i64)],
+}
+
+// Protocol 1 registers, shared by the AX and MX series
+const P1_CW_ANGLE_LIMIT: usize = 6;
+const P1_CCW_ANGLE_LIMIT: usize = 8;
+const P1_GOAL_POSITION: usize = 30;
+const P1_MOVING_SPEED: usize = 32;
+const P1_PRESENT_POSITION: usize = 36;
+const P1_PRESENT_SPEED: usize = 38;
+const P1_MOVING: usize = 46;
+
+const AX12: Spec = Spec{
+    protocol: Protocol::Protocol1,
+    model_number: 12,
+    fw_version: 24,
+    size: 50,
+    id: 3,
+    return_delay_time: 5,
+    status_return_level: 16,
+    torque_enable: 24,
+    read_only: &[(0, 2), (36, 46)],
+    eeprom_end: None,
+    steps_per_revolution: 1024.0 * 360.0 / 300.0,
+    max_position: 1023,
+    velocity_unit: 0.111,
+    max_velocity: 1023,
+    protocol2: None,
+    defaults: &[(4, 1, 1), (5, 1, 250), (8, 2, 1023), (11, 1, 70), (12, 1, 60), (13, 1, 140), (14, 2, 1023), (16, 1, 2),
+                (30, 2, 512), (34, 2, 1023), (36, 2, 512), (42, 1, 120), (43, 1, 30)],
+};
+
+const MX28: Spec = Spec{
+    protocol: Protocol::Protocol1,
+    model_number: 29,
+    fw_version: 36,
+    size: 74,
+    id: 3,
+    return_delay_time: 5,
+    status_return_level: 16,
+    torque_enable: 24,
+    read_only: &[(0, 2), (36, 46)],
+    eeprom_end: None,
+    steps_per_revolution: 4096.0,
+    max_position: 4095,
+    velocity_unit: 0.114,
+    max_velocity: 482,
+    protocol2: None,
+    defaults: &[(4, 1, 1), (5, 1, 250), (8, 2, 4095), (11, 1, 80), (12, 1, 60), (13, 1, 160), (14, 2, 1023), (16, 1, 2),
+                (28, 1, 32), (30, 2, 2048), (34, 2, 1023), (36, 2, 2048), (42, 1, 120), (43, 1, 30)],
+};
+
+const XM430_W350: Spec = Spec{
+    protocol: Protocol::Protocol2,
+    model_number: 1020,
+    fw_version: 38,
+    size: 147,
+    id: 7,
+    return_delay_time: 9,
+    status_return_level: 68,
+    torque_enable: 64,
+    read_only: &[(0, 6), (69, 70), (120, 146)],
+    eeprom_end: Some(64),
+    steps_per_revolution: 4096.0,
+    max_position: 0,
+    velocity_unit: 0.229,
+    max_velocity: 265,
+    protocol2: Some(Protocol2Layout{
+        velocity_limit: 44,
+        max_position_limit: 48,
+        min_position_limit: 52,
+        goal_velocity: 104,
+        profile_velocity: Some(112),
+        goal_position: 116,
+        moving: 122,
+        present_velocity: 128,
+        present_position: 132,
+    }),
+    defaults: &[(8, 1, 1), (9, 1, 250), (11, 1, 3), (13, 1, 2), (31, 1, 80), (32, 2, 160), (34, 2, 95), (36, 2, 885),
+                (38, 2, 1193), (44, 4, 265), (48, 4, 4095), (52, 4, 0), (68, 1, 2), (116, 4, 2048), (132, 4, 2048),
+                (144, 2, 120), (146, 1, 30)],
+};
+
+const M42_10_S260_R: Spec = Spec{
+    protocol: Protocol::Protocol2,
+    model_number: 0xA918,
+    fw_version: 25,
+    size: 893,
+    id: 7,
+    return_delay_time: 9,
+    status_return_level: 891,
+    torque_enable: 562,
+    read_only: &[(0, 6), (610, 625), (892, 892)],
+    eeprom_end: Some(562),
+    steps_per_revolution: 263187.0,
+    max_position: 0,
+    velocity_unit: 0.00389076,
+    max_velocity: 8000,
+    protocol2: Some(Protocol2Layout{
+        velocity_limit: 32,
+        max_position_limit: 36,
+        min_position_limit: 40,
+        goal_velocity: 600,
+        profile_velocity: None,
+        goal_position: 596,
+        moving: 610,
+        present_velocity: 615,
+        present_position: 611,
+    }),
+    defaults: &[(8, 1, 1), (9, 1, 250), (11, 1, 3), (21, 1, 80), (22, 2, 400), (24, 2, 150), (30, 2, 620), (32, 4, 8000),
+                (36, 4, 131593), (40, 4, -131593), (891, 1, 2), (623, 2, 240), (625, 1, 30)],
+};
+
+/// Reasons a register write can be refused
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub(crate) enum AccessError {
+    /// The address range is outside the control table
+    Range,
+    /// The address range includes read only registers, or EEPROM while torque is enabled
+    ReadOnly,
+}
+
+/// A simulated servo
+///
+/// Holds the full control table of the model in memory.
+/// Reads and writes go directly to the control table, like they would on a real servo,
+/// and `Simulator` updates the present values as simulated time passes.
+#[derive(Debug, Clone)]
+pub struct SimServo {
+    spec: &'static Spec,
+    table: Vec<u8>,
+    baud_rate: BaudRate,
+    /// The position in raw steps, kept with more precision than the present position register
+    position: f64,
+}
+
+impl SimServo {
+    fn new(spec: &'static Spec, id: u8, baud_rate: BaudRate) -> Self {
+        let mut servo = SimServo{
+            spec,
+            table: vec![0; spec.size],
+            baud_rate,
+            position: 0.0,
+        };
+        servo.set(0, 2, i64::from(spec.model_number));
+        servo.set(if spec.protocol == Protocol::Protocol1 { 2 } else { 6 }, 1, i64::from(spec.fw_version));
+        servo.set(spec.id, 1, i64::from(id));
+        for (address, size, value) in spec.defaults {
+            servo.set(*address, *size, *value);
+        }
+        servo.position = servo.get(servo.present_position_register(), servo.present_position_size()) as f64;
+        servo
+    }
+
+    /// A simulated AX-12 servo, communicating with protocol 1 at 1 Mbaud
+    pub fn ax12(id: u8) -> Self {
+        Self::new(&AX12, id, BaudRate::Baud1000000)
+    }
+
+    /// A simulated MX-28 servo, communicating with protocol 1 at 1 Mbaud
+    pub fn mx28(id: u8) -> Self {
+        Self::new(&MX28, id, BaudRate::Baud1000000)
+    }
+
+    /// A simulated XM430-W350 servo, communicating with protocol 2 at 57600 baud
+    pub fn xm430_w350(id: u8) -> Self {
+        Self::new(&XM430_W350, id, BaudRate::Baud57600)
+    }
+
+    /// A simulated M42-10-S260-R servo, communicating with protocol 2 at 57600 baud
+    pub fn m42_10_s260_r(id: u8) -> Self {
+        Self::new(&M42_10_S260_R, id, BaudRate::Baud57600)
+    }
+
+    /// Use `baud_rate` instead of the factory default
+    pub fn with_baud_rate(mut self, baud_rate: BaudRate) -> Self {
+        self.baud_rate = baud_rate;
+        self
+    }
+
+    pub fn id(&self) -> u8 {
+        self.table[self.spec.id]
+    }
+
+    pub fn baud_rate(&self) -> BaudRate {
+        self.baud_rate
+    }
+
+    pub fn protocol(&self) -> Protocol {
+        self.spec.protocol
+    }
+
+    pub fn model_number(&self) -> u16 {
+        self.spec.model_number
+    }
+
+    pub fn fw_version(&self) -> u8 {
+        self.spec.fw_version
+    }
+
+    /// The Status Return Level register
+    pub fn status_return_level(&self) -> u8 {
+        self.table[self.spec.status_return_level]
+    }
+
+    /// The Return Delay Time register, as the time the servo waits before sending a status
+    pub fn return_delay(&self) -> Duration {
+        crate::reply_timing::return_delay_from_register(self.table[self.spec.return_delay_time])
+    }
+
+    /// Whether the servo answers `instruction` with a status, according to its Status Return Level register
+    ///
+    /// Pings are always answered, reads from level 1 and every other instruction from level 2.
+    /// The instruction values for ping, read, sync read and bulk read are the same in both protocols.
+    pub(crate) fn answers(&self, instruction: u8) -> bool {
+        match instruction {
+            0x01 => true,
+            0x02 | 0x82 | 0x92 => self.status_return_level() >= 1,
+            _ => self.status_return_level() >= 2,
+        }
+    }
+
+    /// The full control table
+    pub fn control_table(&self) -> &[u8] {
+        &self.table
+    }
+
+    /// Read a little endian register of `size` (1, 2 or 4) bytes, sign extended for 4 byte registers
+    pub fn get(&self, address: usize, size: usize) -> i64 {
+        let mut value = 0i64;
+        for (i, b) in self.table[address..address + size].iter().enumerate() {
+            value |= i64::from(*b) << (8 * i);
+        }
+        if size == 4 {
+            value = i64::from(value as u32 as i32);
+        }
+        value
+    }
+
+    /// Set a little endian register of `size` bytes, ignoring read only restrictions
+    pub fn set(&mut self, address: usize, size: usize, value: i64) {
+        for i in 0..size {
+            self.table[address + i] = (value >> (8 * i)) as u8;
+        }
+    }
+
+    /// The present position register value
+    pub fn present_position(&self) -> i64 {
+        let position = self.get(self.present_position_register(), self.present_position_size());
+        if self.spec.protocol == Protocol::Protocol1 {
+            // Negative in multi-turn mode
+            i64::from(position as u16 as i16)
+        } else {
+            position
+        }
+    }
+
+    pub(crate) fn read(&self, address: usize, length: usize) -> Result<&[u8], AccessError> {
+        self.table.get(address..address + length).ok_or(AccessError::Range)
+    }
+
+    pub(crate) fn write(&mut self, address: usize, data: &[u8]) -> Result<(), AccessError> {
+        let end = address + data.len();
+        if data.is_empty() || end > self.table.len() {
+            return Err(AccessError::Range);
+        }
+        let last = end - 1;
+        if self.spec.read_only.iter().any(|(first, read_only_last)| address <= *read_only_last && last >= *first) {
+            return Err(AccessError::ReadOnly);
+        }
+        if let Some(eeprom_end) = self.spec.eeprom_end {
+            if address < eeprom_end && self.table[self.spec.torque_enable] != 0 {
+                return Err(AccessError::ReadOnly);
+            }
+        }
+        self.table[address..end].copy_from_slice(data);
+        Ok(())
+    }
+
+    fn present_position_register(&self) -> usize {
+        self.spec.protocol2.as_ref().map_or(P1_PRESENT_POSITION, |l| l.present_position)
+    }
+
+    fn present_position_size(&self) -> usize {
+        if self.spec.protocol == Protocol::Protocol1 { 2 } else { 4 }
+    }
+
+    /// Raw velocity units to steps per second
+    fn steps_per_second(&self, velocity: f64) -> f64 {
+        velocity * self.spec.velocity_unit * self.spec.steps_per_revolution / 60.0
+    }
+
+    /// Move the position towards the goal, or along with the goal velocity, for `seconds`
+    pub(crate) fn advance(&mut self, seconds: f64) {
+        let (velocity, target) = if self.spec.protocol == Protocol::Protocol1 {
+            self.protocol1_motion()
+        } else {
+            self.protocol2_motion()
+        };
+        let velocity = if self.table[self.spec.torque_enable] != 0 { velocity } else { 0.0 };
+
+        let start = self.position;
+        let present_velocity = match target {
+            Some(target) => {
+                let distance = velocity.abs() * seconds;
+                self.position = if (target - start).abs() <= distance {
+                    target
+                } else {
+                    start + (target - start).signum() * distance
+                };
+                if seconds > 0.0 { (self.position - start) / seconds } else { 0.0 }
+            },
+            None => {
+                self.position += velocity * seconds;
+                velocity
+            },
+        };
+
+        // Wheel mode wraps around within the position range
+        if self.spec.protocol == Protocol::Protocol1 && target.is_none() {
+            let range = (self.spec.max_position + 1) as f64;
+            self.position = self.position.rem_euclid(range);
+        }
+
+        let moving = match target {
+            Some(target) => self.position != target,
+            None => velocity != 0.0,
+        };
+        self.update_present_values(present_velocity, moving);
+    }
+
+    /// The velocity in steps per second, and the goal position if moving towards one
+    fn protocol1_motion(&self) -> (f64, Option<f64>) {
+        let cw = self.get(P1_CW_ANGLE_LIMIT, 2);
+        let ccw = self.get(P1_CCW_ANGLE_LIMIT, 2);
+        let moving_speed = self.get(P1_MOVING_SPEED, 2);
+
+        if cw == 0 && ccw == 0 {
+            // Wheel mode, bit 10 of moving speed is the direction
+            let magnitude = (moving_speed & 0x3ff) as f64;
+            let velocity = if moving_speed & 0x400 != 0 { -magnitude } else { magnitude };
+            (self.steps_per_second(velocity), None)
+        } else {
+            let speed = if moving_speed == 0 { f64::from(self.spec.max_velocity) } else { moving_speed as f64 };
+            let multi_turn = self.spec.max_position == 4095 && cw == 4095 && ccw == 4095;
+            let goal = self.get(P1_GOAL_POSITION, 2);
+            let goal = if multi_turn {
+                i64::from(goal as u16 as i16) as f64
+            } else {
+                (goal as f64).max(cw as f64).min(ccw as f64)
+            };
+            (self.steps_per_second(speed), Some(goal))
+        }
+    }
+
+    /// The velocity in steps per second, and the goal position if moving towards one
+    fn protocol2_motion(&self) -> (f64, Option<f64>) {
+        let layout = self.spec.protocol2.as_ref().expect("protocol 2 model without layout");
+        let velocity_limit = self.get(layout.velocity_limit, 4) as f64;
+        let operating_mode = self.table[11];
+
+        match operating_mode {
+            1 => {
+                let velocity = (self.get(layout.goal_velocity, 4) as f64).max(-velocity_limit).min(velocity_limit);
+                (self.steps_per_second(velocity), None)
+            },
+            3..=5 => {
+                let profile = layout.profile_velocity.map_or(0, |address| self.get(address, 4)) as f64;
+                let speed = if profile == 0.0 { velocity_limit } else { profile.min(velocity_limit) };
+                let goal = self.get(layout.goal_position, 4) as f64;
+                let goal = if operating_mode == 3 {
+                    let min = self.get(layout.min_position_limit, 4) as f64;
+                    let max = self.get(layout.max_position_limit, 4) as f64;
+                    goal.max(min).min(max)
+                } else {
+                    goal
+                };
+                (self.steps_per_second(speed), Some(goal))
+            },
+            _ => (0.0, None),
+        }
+    }
+
+    fn update_present_values(&mut self, velocity: f64, moving: bool) {
+        let position = self.position.round() as i64;
+        let raw_velocity = (velocity * 60.0 / (self.spec.velocity_unit * self.spec.steps_per_revolution)).round() as i64;
+        match self.spec.protocol2.as_ref() {
+            None => {
+                self.set(P1_PRESENT_POSITION, 2, position);
+                let speed = raw_velocity.abs().min(1023) | if raw_velocity < 0 { 0x400 } else { 0 };
+                self.set(P1_PRESENT_SPEED, 2, speed);
+                self.set(P1_MOVING, 1, i64::from(moving));
+            },
+            Some(layout) => {
+                let (present_position, present_velocity, moving_register) = (layout.present_position, layout.present_velocity, layout.moving);
+                self.set(present_position, 4, position);
+                self.set(present_velocity, 4, raw_velocity);
+                self.set(moving_register, 1, i64::from(moving));
+            },
+        }
+    }
+}
@@ -0,0 +1,590 @@
+//! Decoding captured bus traffic
+//!
+//! `Decoder` turns a raw byte stream, like a capture from a passive RS-485 tap, into protocol 1 and protocol 2 packets.
+//! It finds packet headers, resyncs after garbage, undoes protocol 2 byte stuffing and validates checksums and CRCs.
+//! Register accesses are decoded to address and value, and named when the model of the servo is known.
+//! Models are learned from pings and model number reads seen on the bus, or can be given with `Decoder::set_model`.
+//!
+//! Protocol 1 status packets look exactly like instruction packets.
+//! They are told apart by expecting a reply of the right length from the servos addressed by the previous instruction.
+//!
+//! This module requires the `std` feature.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::protocol2::bit_stuffer::BitStuffer;
+use crate::registry::Protocol;
+
+const BROADCAST_ID: u8 = 0xfe;
+
+/// An instruction, as named in the Robotis e-manual
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    Ping,
+    Read,
+    Write,
+    RegWrite,
+    Action,
+    FactoryReset,
+    Reboot,
+    Clear,
+    SyncRead,
+    SyncWrite,
+    BulkRead,
+    BulkWrite,
+    Unknown(u8),
+}
+
+impl Instruction {
+    fn new(protocol: Protocol, value: u8) -> Self {
+        match (protocol, value) {
+            (_, 0x01) => Instruction::Ping,
+            (_, 0x02) => Instruction::Read,
+            (_, 0x03) => Instruction::Write,
+            (_, 0x04) => Instruction::RegWrite,
+            (_, 0x05) => Instruction::Action,
+            (_, 0x06) => Instruction::FactoryReset,
+            (_, 0x08) => Instruction::Reboot,
+            (Protocol::Protocol2, 0x10) => Instruction::Clear,
+            (Protocol::Protocol2, 0x82) => Instruction::SyncRead,
+            (_, 0x83) => Instruction::SyncWrite,
+            (_, 0x92) => Instruction::BulkRead,
+            (Protocol::Protocol2, 0x93) => Instruction::BulkWrite,
+            (_, value) => Instruction::Unknown(value),
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Instruction::Ping => write!(f, "Ping"),
+            Instruction::Read => write!(f, "Read"),
+            Instruction::Write => write!(f, "Write"),
+            Instruction::RegWrite => write!(f, "Reg Write"),
+            Instruction::Action => write!(f, "Action"),
+            Instruction::FactoryReset => write!(f, "Factory Reset"),
+            Instruction::Reboot => write!(f, "Reboot"),
+            Instruction::Clear => write!(f, "Clear"),
+            Instruction::SyncRead => write!(f, "Sync Read"),
+            Instruction::SyncWrite => write!(f, "Sync Write"),
+            Instruction::BulkRead => write!(f, "Bulk Read"),
+            Instruction::BulkWrite => write!(f, "Bulk Write"),
+            Instruction::Unknown(value) => write!(f, "Instruction 0x{:02x}", value),
+        }
+    }
+}
+
+/// Whether a packet was sent by the controller or a servo
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Instruction(Instruction),
+    Status{error: u8},
+}
+
+/// A register access in a packet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    /// The servo the register belongs to
+    pub id: u8,
+
+    pub address: u16,
+
+    /// The register name, if the model of the servo is known and a register starts at `address`
+    pub name: Option<&'static str>,
+
+    /// The number of bytes accessed
+    pub length: u16,
+
+    /// The bytes written or read, empty for read requests
+    pub data: Vec<u8>,
+}
+
+impl Field {
+    /// The little endian value of `data`, sign extended for 4 byte registers
+    pub fn value(&self) -> Option<i64> {
+        match self.data[..] {
+            [a] => Some(i64::from(a)),
+            [a, b] => Some(i64::from(u16::from_le_bytes([a, b]))),
+            [a, b, c, d] => Some(i64::from(i32::from_le_bytes([a, b, c, d]))),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.name {
+            Some(name) => write!(f, "{} ({})", name, self.address)?,
+            None => write!(f, "{}", self.address)?,
+        }
+        if self.data.is_empty() {
+            write!(f, " [{} bytes]", self.length)
+        } else if let Some(value) = self.value() {
+            write!(f, " = {}", value)
+        } else {
+            write!(f, " = {:02x?}", self.data)
+        }
+    }
+}
+
+/// A decoded packet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Packet {
+    pub protocol: Protocol,
+    pub id: u8,
+    pub kind: Kind,
+
+    /// The parameters, with byte stuffing removed and without the error byte of status packets
+    pub parameters: Vec<u8>,
+
+    /// The register accesses of the packet, empty if it has none or they are unknown
+    pub fields: Vec<Field>,
+}
+
+impl fmt::Display for Packet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let protocol = match self.protocol {
+            Protocol::Protocol1 => 1,
+            Protocol::Protocol2 => 2,
+        };
+        write!(f, "P{} id {} ", protocol, self.id)?;
+        match self.kind {
+            Kind::Instruction(instruction) => write!(f, "{}", instruction)?,
+            Kind::Status{error: 0} => write!(f, "Status")?,
+            Kind::Status{error} => write!(f, "Status (error 0x{:02x})", error)?,
+        }
+
+        for (i, field) in self.fields.iter().enumerate() {
+            f.write_str(if i == 0 { ": " } else { ", " })?;
+            if field.id != self.id {
+                write!(f, "id {} ", field.id)?;
+            }
+            write!(f, "{}", field)?;
+        }
+        if self.fields.is_empty() && !self.parameters.is_empty() {
+            write!(f, ": {:02x?}", self.parameters)?;
+        }
+        Ok(())
+    }
+}
+
+/// Something found in the byte stream
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    Packet(Packet),
+
+    /// Bytes that are not part of a valid packet, including packets with a bad checksum or CRC
+    Garbage(Vec<u8>),
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Event::Packet(packet) => write!(f, "{}", packet),
+            Event::Garbage(bytes) => write!(f, "Garbage: {:02x?}", bytes),
+        }
+    }
+}
+
+/// Result of trying to parse a packet at the start of the buffer
+enum Parse {
+    Packet{protocol: Protocol, id: u8, instruction: u8, parameters: Vec<u8>, size: usize},
+    Incomplete,
+    Invalid,
+}
+
+/// A reply expected after an instruction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Reply {
+    /// A status without data
+    Empty,
+    /// A status with the model number and firmware version for protocol 2, and without data for protocol 1
+    Ping,
+    /// A status with the data of a read
+    Read{address: u16, length: u16},
+}
+
+/// Decodes packets from a byte stream
+#[derive(Debug, Clone, Default)]
+pub struct Decoder {
+    buffer: Vec<u8>,
+    garbage: Vec<u8>,
+    models: HashMap<(Protocol, u8), u16>,
+    replies: HashMap<(Protocol, u8), Reply>,
+    /// Set after a broadcast ping, every servo on the bus may reply
+    broadcast_ping: Option<Protocol>,
+}
+
+impl Decoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Name the registers of servo `id` from the control table of `model_number`
+    pub fn set_model(&mut self, protocol: Protocol, id: u8, model_number: u16) {
+        self.models.insert((protocol, id), model_number);
+    }
+
+    /// Decode `bytes`, following the bytes given earlier
+    ///
+    /// Bytes of packets that are not complete yet are kept until the next call.
+    pub fn decode(&mut self, bytes: &[u8]) -> Vec<Event> {
+        self.buffer.extend_from_slice(bytes);
+        self.decode_buffer(false)
+    }
+
+    /// Decode the remaining bytes, at the end of the stream
+    pub fn finish(&mut self) -> Vec<Event> {
+        self.decode_buffer(true)
+    }
+
+    fn decode_buffer(&mut self, finish: bool) -> Vec<Event> {
+        let mut events = Vec::new();
+
+        while !self.buffer.is_empty() {
+            match self.parse(finish) {
+                Parse::Packet{protocol, id, instruction, parameters, size} => {
+                    self.buffer.drain(..size);
+                    if !self.garbage.is_empty() {
+                        events.push(Event::Garbage(std::mem::take(&mut self.garbage)));
+                    }
+                    events.push(Event::Packet(self.interpret(protocol, id, instruction, parameters)));
+                },
+                Parse::Incomplete => break,
+                Parse::Invalid => {
+                    // Resync by searching for a header from the next byte
+                    self.garbage.push(self.buffer.remove(0));
+                },
+            }
+        }
+
+        if finish && !self.garbage.is_empty() {
+            events.push(Event::Garbage(std::mem::take(&mut self.garbage)));
+        }
+        events
+    }
+
+    /// Parse a packet at the start of the buffer
+    fn parse(&self, finish: bool) -> Parse {
+        let incomplete = if finish { Parse::Invalid } else { Parse::Incomplete };
+        let buffer = &self.buffer;
+
+        match buffer.get(..4) {
+            Some([0xff, 0xff, 0xfd, 0x00]) => self.parse_protocol2(finish),
+            Some([0xff, 0xff, 0xff, _]) => Parse::Invalid,
+            Some([0xff, 0xff, _, _]) => {
+                let size = usize::from(buffer[3]) + 4;
+                if buffer[3] < 2 {
+                    Parse::Invalid
+                } else if buffer.len() < size {
+                    incomplete
+                } else if u8::from(crate::protocol1::checksum::Checksum::calc(&buffer[2..size-1])) != buffer[size-1] {
+                    Parse::Invalid
+                } else {
+                    Parse::Packet{
+                        protocol: Protocol::Protocol1,
+                        id: buffer[2],
+                        instruction: buffer[4],
+                        parameters: buffer[5..size-1].to_vec(),
+                        size,
+                    }
+                }
+            },
+            Some(_) => Parse::Invalid,
+            None if buffer[0] != 0xff || buffer.get(1).is_some_and(|b| *b != 0xff) => Parse::Invalid,
+            None => incomplete,
+        }
+    }
+
+    fn parse_protocol2(&self, finish: bool) -> Parse {
+        let incomplete = if finish { Parse::Invalid } else { Parse::Incomplete };
+        let buffer = &self.buffer;
+
+        if buffer.len() < 7 {
+            return incomplete;
+        }
+        let length = usize::from(u16::from_le_bytes([buffer[5], buffer[6]]));
+        let size = length + 7;
+        if length < 3 {
+            return Parse::Invalid;
+        }
+        if buffer.len() < size {
+            return incomplete;
+        }
+
+        let crc = u16::from(crate::protocol2::crc::Crc::calc(&buffer[..size-2]));
+        if crc != u16::from_le_bytes([buffer[size-2], buffer[size-1]]) {
+            return Parse::Invalid;
+        }
+
+        let mut stuffer = BitStuffer::new();
+        for b in &buffer[..4] {
+            stuffer = stuffer.add_byte(*b).expect("header checked above");
+        }
+        let mut body = Vec::with_capacity(length - 2);
+        for b in &buffer[7..size-2] {
+            let stuffed = stuffer.stuff_next();
+            stuffer = match stuffer.add_byte(*b) {
+                Ok(stuffer) => stuffer,
+                Err(_) => return Parse::Invalid,
+            };
+            if !stuffed {
+                body.push(*b);
+            }
+        }
+
+        Parse::Packet{
+            protocol: Protocol::Protocol2,
+            id: buffer[4],
+            instruction: body[0],
+            parameters: body[1..].to_vec(),
+            size,
+        }
+    }
+
+    /// Decide if a packet is an instruction or a status, and decode its register accesses
+    fn interpret(&mut self, protocol: Protocol, id: u8, instruction: u8, parameters: Vec<u8>) -> Packet {
+        let is_status = match protocol {
+            Protocol::Protocol1 => {
+                let expected = self.replies.get(&(protocol, id)).copied()
+                    .or(if self.broadcast_ping == Some(protocol) { Some(Reply::Ping) } else { None });
+                match expected {
+                    Some(Reply::Empty) | Some(Reply::Ping) => parameters.is_empty(),
+                    Some(Reply::Read{length, ..}) => parameters.len() == usize::from(length),
+                    None => false,
+                }
+            },
+            Protocol::Protocol2 => instruction == 0x55,
+        };
+
+        if is_status {
+            let (error, parameters) = match protocol {
+                Protocol::Protocol1 => (instruction, parameters),
+                Protocol::Protocol2 => (parameters.first().copied().unwrap_or(0), parameters.get(1..).unwrap_or(&[]).to_vec()),
+            };
+            let fields = self.status_fields(protocol, id, &parameters);
+            Packet{protocol, id, kind: Kind::Status{error}, parameters, fields}
+        } else {
+            let instruction = Instruction::new(protocol, instruction);
+            let fields = self.instruction_fields(protocol, id, instruction, &parameters);
+            Packet{protocol, id, kind: Kind::Instruction(instruction), parameters, fields}
+        }
+    }
+
+    fn status_fields(&mut self, protocol: Protocol, id: u8, parameters: &[u8]) -> Vec<Field> {
+        let reply = self.replies.remove(&(protocol, id))
+            .or(if self.broadcast_ping == Some(protocol) { Some(Reply::Ping) } else { None });
+        match reply {
+            Some(Reply::Read{address, length}) if parameters.len() == usize::from(length) => {
+                if address == 0 && length >= 2 {
+                    self.set_model(protocol, id, u16::from_le_bytes([parameters[0], parameters[1]]));
+                }
+                self.fields(protocol, id, address, length, parameters)
+            },
+            Some(Reply::Ping) if protocol == Protocol::Protocol2 && parameters.len() == 3 => {
+                // Ping status, with model number and firmware version
+                self.set_model(protocol, id, u16::from_le_bytes([parameters[0], parameters[1]]));
+                self.fields(protocol, id, 0, 2, &parameters[..2])
+            },
+            _ => Vec::new(),
+        }
+    }
+
+    fn instruction_fields(&mut self, protocol: Protocol, id: u8, instruction: Instruction, parameters: &[u8]) -> Vec<Field> {
+        // Replies to an earlier instruction are done once the controller sends a new one
+        self.replies.retain(|(p, _), _| *p != protocol);
+        self.broadcast_ping = None;
+
+        let address_size = match protocol {
+            Protocol::Protocol1 => 1,
+            Protocol::Protocol2 => 2,
+        };
+        let word = |bytes: &[u8]| match protocol {
+            Protocol::Protocol1 => u16::from(bytes[0]),
+            Protocol::Protocol2 => u16::from_le_bytes([bytes[0], bytes[1]]),
+        };
+
+        let mut fields = Vec::new();
+        let mut reads = Vec::new();
+        match instruction {
+            Instruction::Ping if id == BROADCAST_ID => self.broadcast_ping = Some(protocol),
+            Instruction::Ping => {
+                self.replies.insert((protocol, id), Reply::Ping);
+            },
+            Instruction::Read if parameters.len() == 2 * address_size => {
+                let (address, length) = (word(parameters), word(&parameters[address_size..]));
+                fields = self.fields(protocol, id, address, length, &[]);
+                reads.push((id, address, length));
+            },
+            Instruction::Write | Instruction::RegWrite if parameters.len() > address_size => {
+                let data = &parameters[address_size..];
+                fields = self.fields(protocol, id, word(parameters), data.len() as u16, data);
+            },
+            Instruction::SyncRead if parameters.len() >= 4 => {
+                let (address, length) = (word(parameters), word(&parameters[2..]));
+                for servo in &parameters[4..] {
+                    fields.extend(self.fields(protocol, *servo, address, length, &[]));
+                    reads.push((*servo, address, length));
+                }
+            },
+            Instruction::SyncWrite if parameters.len() >= 2 * address_size => {
+                let (address, length) = (word(parameters), word(&parameters[address_size..]));
+                for chunk in parameters[2 * address_size..].chunks(usize::from(length) + 1) {
+                    if chunk.len() == usize::from(length) + 1 {
+                        fields.extend(self.fields(protocol, chunk[0], address, length, &chunk[1..]));
+                    }
+                }
+            },
+            Instruction::BulkRead => {
+                let (groups, size) = match protocol {
+                    Protocol::Protocol1 => (parameters.get(1..).unwrap_or(&[]), 3),
+                    Protocol::Protocol2 => (parameters, 5),
+                };
+                for group in groups.chunks_exact(size) {
+                    let (servo, address, length) = match protocol {
+                        Protocol::Protocol1 => (group[1], u16::from(group[2]), u16::from(group[0])),
+                        Protocol::Protocol2 => (group[0], word(&group[1..]), word(&group[3..])),
+                    };
+                    fields.extend(self.fields(protocol, servo, address, length, &[]));
+                    reads.push((servo, address, length));
+                }
+            },
+            Instruction::BulkWrite => {
+                let mut rest = parameters;
+                while rest.len() >= 5 {
+                    let (servo, address, length) = (rest[0], word(&rest[1..]), word(&rest[3..]));
+                    let Some(data) = rest.get(5..5 + usize::from(length)) else { break };
+                    fields.extend(self.fields(protocol, servo, address, length, data));
+                    rest = &rest[5 + usize::from(length)..];
+                }
+            },
+            _ => (),
+        }
+
+        for (servo, address, length) in reads {
+            self.replies.insert((protocol, servo), Reply::Read{address, length});
+        }
+        if id != BROADCAST_ID && !self.replies.contains_key(&(protocol, id)) {
+            self.replies.insert((protocol, id), Reply::Empty);
+        }
+        fields
+    }
+
+    /// Split an access of `length` bytes at `address` into the registers of the servo's control table
+    ///
+    /// `data` is empty for read requests.
+    fn fields(&self, protocol: Protocol, id: u8, address: u16, length: u16, data: &[u8]) -> Vec<Field> {
+        let field = |start: u16, length: u16, name: Option<&'static str>| {
+            let offset = usize::from(start - address);
+            let data = data.get(offset..offset + usize::from(length)).unwrap_or(&[]).to_vec();
+            Field{id, address: start, name, length, data}
+        };
+
+        let table = match self.models.get(&(protocol, id)).and_then(|m| crate::registry::lookup(protocol, *m)) {
+            Some(model) => model.control_table,
+            None => return vec![field(address, length, None)],
+        };
+
+        let end = u32::from(address) + u32::from(length);
+        let mut fields = Vec::new();
+        let mut unnamed: Option<u16> = None;
+        let mut current = address;
+        while u32::from(current) < end {
+            match table.register(current) {
+                Some(register) if u32::from(current) + u32::from(register.size) <= end => {
+                    if let Some(unnamed) = unnamed.take() {
+                        fields.push(field(unnamed, current - unnamed, None));
+                    }
+                    fields.push(field(current, u16::from(register.size), Some(register.name)));
+                    current += u16::from(register.size);
+                },
+                _ => {
+                    unnamed.get_or_insert(current);
+                    current += 1;
+                },
+            }
+        }
+        if let Some(unnamed) = unnamed {
+            fields.push(field(unnamed, current - unnamed, None));
+        }
+        fields
+    }
+}
+
+/// Decode a complete capture
+pub fn decode(capture: &[u8]) -> Vec<Event> {
+    let mut decoder = Decoder::new();
+    let mut events = decoder.decode(capture);
+    events.append(&mut decoder.finish());
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sniffer::*;
+    use crate::mock::{protocol1_packet, protocol1_status, protocol2_packet, protocol2_status};
+
+    fn packets(events: &[Event]) -> Vec<&Packet> {
+        events.iter().filter_map(|e| match e {
+            Event::Packet(packet) => Some(packet),
+            Event::Garbage(_) => None,
+        }).collect()
+    }
+
+    #[test]
+    fn protocol1_conversation() {
+        let mut capture = protocol1_packet(1, 0x02, &[0, 2]);
+        capture.extend(protocol1_status(1, 0, &[12, 0]));
+        capture.extend(protocol1_packet(1, 0x03, &[30, 0x00, 0x02]));
+        capture.extend(protocol1_status(1, 0, &[]));
+        capture.extend(protocol1_packet(1, 0x02, &[36, 2]));
+        capture.extend(protocol1_status(1, 0, &[0xff, 0x01]));
+
+        let events = decode(&capture);
+        let packets = packets(&events);
+        assert_eq!(events.len(), 6);
+        assert_eq!(packets[1].kind, Kind::Status{error: 0});
+        assert_eq!(packets[2].to_string(), "P1 id 1 Write: Goal Position (30) = 512");
+        assert_eq!(packets[3].kind, Kind::Status{error: 0});
+        assert_eq!(packets[4].to_string(), "P1 id 1 Read: Present Position (36) [2 bytes]");
+        assert_eq!(packets[5].to_string(), "P1 id 1 Status: Present Position (36) = 511");
+    }
+
+    #[test]
+    fn protocol2_conversation() {
+        let mut capture = protocol2_packet(BROADCAST_ID, 0x01, &[]);
+        capture.extend(protocol2_status(1, 0, &[0xfc, 0x03, 0x26]));
+        capture.extend(protocol2_packet(BROADCAST_ID, 0x83, &[116, 0, 4, 0, 1, 0x00, 0x08, 0x00, 0x00, 2, 0xff, 0xff, 0xfd, 0x00]));
+        capture.extend(protocol2_packet(1, 0x02, &[132, 0, 8, 0]));
+        capture.extend(protocol2_status(1, 0x80, &[0x00, 0x08, 0x00, 0x00, 0xff, 0xff, 0xff, 0xff]));
+
+        let events = decode(&capture);
+        let packets = packets(&events);
+        assert_eq!(events.len(), 5);
+        assert_eq!(packets[1].to_string(), "P2 id 1 Status: Model Number (0) = 1020");
+        assert_eq!(packets[2].parameters[9..], [2, 0xff, 0xff, 0xfd, 0x00]);
+        assert_eq!(packets[2].to_string(), "P2 id 254 Sync Write: id 1 Goal Position (116) = 2048, id 2 116 = 16646143");
+        assert_eq!(packets[4].to_string(), "P2 id 1 Status (error 0x80): Present Position (132) = 2048, Velocity Trajectory (136) = -1");
+    }
+
+    #[test]
+    fn resync() {
+        let mut corrupt = protocol1_packet(1, 0x03, &[25, 1]);
+        *corrupt.last_mut().unwrap() ^= 0xff;
+
+        let mut capture = vec![0x00, 0xff, 0x12];
+        capture.extend(&corrupt);
+        capture.extend(protocol2_packet(3, 0x03, &[65, 0, 1]));
+        capture.extend([0xff, 0xff, 0xfd]);
+
+        let mut decoder = Decoder::new();
+        decoder.set_model(Protocol::Protocol2, 3, 1020);
+        let events = decoder.decode(&capture);
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0], Event::Garbage([&[0x00, 0xff, 0x12][..], &corrupt].concat()));
+        assert_eq!(events[1].to_string(), "P2 id 3 Write: LED (65) = 1");
+        assert_eq!(decoder.finish(), vec![Event::Garbage(vec![0xff, 0xff, 0xfd])]);
+    }
+}
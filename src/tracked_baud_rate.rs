@@ -0,0 +1,111 @@
+use crate::lib::time::Duration;
+use crate::{BaudRate, CommunicationError, Interface, Timeouts};
+
+/// An `Interface` wrapper that only changes the baud rate when it differs from the current one
+///
+/// Servos set their baud rate before every transaction, which is expensive for some interfaces,
+/// e.g. reconfiguring a serial port on Linux takes several system calls.
+/// Buses with servos at different baud rates still switch baud rate whenever a servo with another baud rate is used.
+///
+/// If the baud rate is changed without going through this wrapper, `invalidate` must be called,
+/// `inner_mut` does so automatically.
+pub struct TrackedBaudRate<I> {
+    interface: I,
+    baud_rate: Option<BaudRate>,
+}
+
+impl<I> TrackedBaudRate<I> {
+    /// Wrap `interface`, the baud rate is set by the first transaction
+    pub fn new(interface: I) -> Self {
+        TrackedBaudRate{interface, baud_rate: None}
+    }
+
+    /// The current baud rate, or `None` if it is unknown
+    pub fn baud_rate(&self) -> Option<BaudRate> {
+        self.baud_rate
+    }
+
+    /// Forget the current baud rate, so it is set again before the next transaction
+    pub fn invalidate(&mut self) {
+        self.baud_rate = None;
+    }
+
+    pub fn inner(&self) -> &I {
+        &self.interface
+    }
+
+    /// The wrapped interface
+    ///
+    /// The baud rate is set again before the next transaction, in case it is changed through the returned reference.
+    pub fn inner_mut(&mut self) -> &mut I {
+        self.invalidate();
+        &mut self.interface
+    }
+
+    pub fn into_inner(self) -> I {
+        self.interface
+    }
+}
+
+impl<I: Interface> Interface for TrackedBaudRate<I> {
+    fn set_baud_rate(&mut self, b: BaudRate) -> Result<(), CommunicationError> {
+        if self.baud_rate == Some(b) {
+            return Ok(());
+        }
+        // The baud rate is unknown if changing it fails half way
+        self.baud_rate = None;
+        self.interface.set_baud_rate(b)?;
+        self.baud_rate = Some(b);
+        Ok(())
+    }
+
+    fn flush(&mut self) {
+        self.interface.flush()
+    }
+
+    fn read(&mut self, data: &mut [u8]) -> Result<(), CommunicationError> {
+        self.interface.read(data)
+    }
+
+    fn read_with_timeout(&mut self, data: &mut [u8], timeout: Duration) -> Result<(), CommunicationError> {
+        self.interface.read_with_timeout(data, timeout)
+    }
+
+    fn write(&mut self, data: &[u8]) -> Result<(), CommunicationError> {
+        self.interface.write(data)
+    }
+
+    fn timeouts(&self) -> Timeouts {
+        self.interface.timeouts()
+    }
+}
+
+#[cfg(all(test, feature="std"))]
+mod tests {
+    use crate::tracked_baud_rate::*;
+    use crate::mock::{protocol1_status, MockInterface};
+    use crate::units::Radians;
+    use crate::Servo;
+
+    #[test]
+    fn mixed_baud_rates() {
+        let mut mock = MockInterface::new();
+        for _ in 0..5 {
+            mock.queue_response(&protocol1_status(1, 0, &[0x00, 0x02]));
+        }
+        let mut interface = TrackedBaudRate::new(mock);
+        let mut fast = crate::dynamixel::ax12::AX12::new(crate::protocol1::ServoID::new(1), BaudRate::Baud1000000);
+        let mut slow = crate::dynamixel::ax12::AX12::new(crate::protocol1::ServoID::new(1), BaudRate::Baud57600);
+
+        for use_fast in [true, true, false, true] {
+            let servo = if use_fast { &mut fast } else { &mut slow };
+            assert_eq!(servo.get_position(&mut interface), Ok(Radians(0.0)));
+        }
+        assert_eq!(interface.baud_rate(), Some(BaudRate::Baud1000000));
+        assert_eq!(interface.inner().baud_rates(), &[BaudRate::Baud1000000, BaudRate::Baud57600, BaudRate::Baud1000000]);
+
+        interface.invalidate();
+        assert_eq!(fast.get_position(&mut interface), Ok(Radians(0.0)));
+        assert_eq!(interface.inner().baud_rates().len(), 4);
+    }
+}
@@ -0,0 +1,178 @@
+//! Units used by the `Servo` trait
+//!
+//! Every quantity has its own type, so radians can't be mistaken for degrees (or rpm for rad/s).
+//! Conversions between units of the same quantity are done with `From`.
+//! Conversion to raw register values is done with an `Encoder` (for positions), or checked with `to_steps`.
+
+macro_rules! unit {
+    ($name:ident, $symbol:expr) => {
+        #[derive(Debug, PartialEq, PartialOrd, Clone, Copy, Default)]
+        pub struct $name(pub f32);
+
+        impl $name {
+            /// The value in this unit
+            pub fn value(self) -> f32 {
+                self.0
+            }
+        }
+
+        impl crate::lib::ops::Add for $name {
+            type Output = $name;
+
+            fn add(self, rhs: $name) -> $name {
+                $name(self.0 + rhs.0)
+            }
+        }
+
+        impl crate::lib::ops::Sub for $name {
+            type Output = $name;
+
+            fn sub(self, rhs: $name) -> $name {
+                $name(self.0 - rhs.0)
+            }
+        }
+
+        impl crate::lib::ops::Neg for $name {
+            type Output = $name;
+
+            fn neg(self) -> $name {
+                $name(-self.0)
+            }
+        }
+
+        impl crate::lib::ops::Mul<f32> for $name {
+            type Output = $name;
+
+            fn mul(self, rhs: f32) -> $name {
+                $name(self.0 * rhs)
+            }
+        }
+
+        impl crate::lib::ops::Div<f32> for $name {
+            type Output = $name;
+
+            fn div(self, rhs: f32) -> $name {
+                $name(self.0 / rhs)
+            }
+        }
+
+        impl crate::lib::fmt::Display for $name {
+            fn fmt(&self, f: &mut crate::lib::fmt::Formatter) -> crate::lib::fmt::Result {
+                write!(f, "{} {}", self.0, $symbol)
+            }
+        }
+    };
+}
+
+unit!(Radians, "rad");
+unit!(Degrees, "°");
+unit!(RadiansPerSecond, "rad/s");
+unit!(Rpm, "rpm");
+unit!(Amperes, "A");
+unit!(Percent, "%");
+
+impl From<Degrees> for Radians {
+    fn from(d: Degrees) -> Radians {
+        Radians(d.0.to_radians())
+    }
+}
+
+impl From<Radians> for Degrees {
+    fn from(r: Radians) -> Degrees {
+        Degrees(r.0.to_degrees())
+    }
+}
+
+impl From<Rpm> for RadiansPerSecond {
+    fn from(r: Rpm) -> RadiansPerSecond {
+        RadiansPerSecond(r.0 * 2.0 * crate::lib::f32::consts::PI / 60.0)
+    }
+}
+
+impl From<RadiansPerSecond> for Rpm {
+    fn from(r: RadiansPerSecond) -> Rpm {
+        Rpm(r.0 * 60.0 / (2.0 * crate::lib::f32::consts::PI))
+    }
+}
+
+/// Round `value` to the nearest integer, returning `None` if it is not finite or does not fit in an `i32`
+pub fn to_steps(value: f64) -> Option<i32> {
+    if !value.is_finite() {
+        return None;
+    }
+
+    // `round` is not available without `std`
+    let rounded = if value >= 0.0 { value + 0.5 } else { value - 0.5 } as i64;
+    if rounded < i64::from(i32::MIN) || rounded > i64::from(i32::MAX) {
+        None
+    } else {
+        Some(rounded as i32)
+    }
+}
+
+/// Description of the position encoder of a servo model
+///
+/// Used to convert between positions in radians and the raw values of the position registers.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Encoder {
+    /// The number of steps covering `range`
+    pub resolution: u32,
+
+    /// The step value corresponding to 0 radians
+    pub zero_offset: i32,
+
+    /// The angular range covered by `resolution` steps
+    pub range: Radians,
+}
+
+impl Encoder {
+    /// Convert a position to the closest raw step value
+    ///
+    /// Returns `None` if the position is not finite or too large to be represented.
+    pub fn to_steps(&self, position: Radians) -> Option<i32> {
+        let steps = f64::from(position.0) * f64::from(self.resolution) / f64::from(self.range.0);
+        to_steps(steps + f64::from(self.zero_offset))
+    }
+
+    /// The number of steps in one revolution
+    pub fn steps_per_revolution(&self) -> f64 {
+        f64::from(self.resolution) * 2.0 * crate::lib::f64::consts::PI / f64::from(self.range.0)
+    }
+
+    /// Convert a raw step value to a position
+    pub fn to_radians(&self, steps: i32) -> Radians {
+        let steps = f64::from(steps) - f64::from(self.zero_offset);
+        Radians((steps * f64::from(self.range.0) / f64::from(self.resolution)) as f32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::units::*;
+
+    #[test]
+    fn encoder_symmetric() {
+        let encoder = Encoder{resolution: 4096, zero_offset: 2048, range: Radians(2.0 * crate::lib::f32::consts::PI)};
+        for steps in 0..4096 {
+            assert_eq!(encoder.to_steps(encoder.to_radians(steps)), Some(steps));
+        }
+        assert_eq!(encoder.to_steps(Radians(0.0)), Some(2048));
+        assert_eq!(encoder.to_steps(Radians(crate::lib::f32::consts::PI)), Some(4096));
+        assert_eq!(encoder.to_steps(Radians(-crate::lib::f32::consts::PI)), Some(0));
+    }
+
+    #[test]
+    fn checked_steps() {
+        assert_eq!(to_steps(1.5), Some(2));
+        assert_eq!(to_steps(-1.5), Some(-2));
+        assert_eq!(to_steps(f64::NAN), None);
+        assert_eq!(to_steps(f64::INFINITY), None);
+        assert_eq!(to_steps(1e10), None);
+    }
+
+    #[test]
+    fn conversions() {
+        assert!((Radians::from(Degrees(180.0)).value() - crate::lib::f32::consts::PI).abs() < 1e-6);
+        assert!((Rpm::from(RadiansPerSecond::from(Rpm(60.0))).value() - 60.0).abs() < 1e-4);
+    }
+}